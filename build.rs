@@ -1,9 +1,230 @@
 extern crate gcc;
+extern crate pkg_config;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const NOT_FOUND: &'static str =
+    "Could not find Mesos.  Install it so that pkg-config finds `mesos`, or \
+     set MESOS_HOME to its installation prefix, or set both \
+     MESOS_INCLUDE_DIR (containing mesos/scheduler.hpp) and MESOS_LIB_DIR \
+     (containing libmesos)";
+
+// The shared shim built for the `dlopen` feature.
+const SHIM: &'static str = "libmesos-c.so";
+
+// Where the Mesos headers and libraries were found.
+struct Mesos {
+    include_dirs: Vec<PathBuf>,
+    link_dirs: Vec<PathBuf>,
+    libs: Vec<String>,
+}
 
 fn main() {
+    // With neither feature there is no shim to compile, and nothing to
+    // link against.
+    let native = env::var_os("CARGO_FEATURE_NATIVE").is_some();
+    let dlopen = env::var_os("CARGO_FEATURE_DLOPEN").is_some();
+    if !native && !dlopen {
+        return;
+    }
+
+    for var in &["MESOS_HOME", "MESOS_INCLUDE_DIR", "MESOS_LIB_DIR"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    println!("cargo:rerun-if-changed=resources/mesos-c");
+
+    let mesos = find_mesos();
+
+    if native {
+        let mesos = mesos.as_ref().unwrap_or_else(|| fail(NOT_FOUND));
+        compile_static(mesos);
+    }
+
+    // The `dlopen` feature can do without a shim built here, as long as
+    // one is named at runtime.
+    if dlopen {
+        match mesos {
+            Some(ref mesos) => build_shim(mesos),
+            None => println!("cargo:warning=Not building {}: {}", SHIM,
+                             NOT_FOUND),
+        }
+    }
+}
+
+// Compiles the shim into this crate, and links it against libmesos.
+fn compile_static(mesos: &Mesos) {
+    let mut config = gcc::Config::new();
+    config.cpp(true)
+        .flag("-std=c++11")
+        .file("resources/mesos-c/scheduler_driver.cpp")
+        .include("resources/mesos-c");
+    for dir in &mesos.include_dirs {
+        config.include(dir);
+    }
+    config.compile("libmesosc.a");
+
+    // A separate library, only linked by the tests of `native`.
     gcc::Config::new()
         .cpp(true)
-        .file("resources/mesos-c/scheduler_driver.cpp")
+        .flag("-std=c++11")
+        .file("resources/mesos-c/framing_check.cpp")
         .include("resources/mesos-c")
-        .compile("libmesosc.a");
+        .cargo_metadata(false)
+        .compile("libmesosc_framing_check.a");
+
+    let (protobuf_dirs, protobuf_libs) = find_protobuf(mesos);
+    for dir in mesos.link_dirs.iter().chain(&protobuf_dirs) {
+        println!("cargo:rustc-link-search=native={}", dir.display());
+    }
+    for lib in mesos.libs.iter().chain(&protobuf_libs) {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+}
+
+// Builds the shim as a shared library linked against libmesos, for
+// `native::Library` to load.  It is placed next to the binaries of the
+// profile, e.g. `target/release/libmesos-c.so`, where
+// `Library::open_default` looks for it.
+fn build_shim(mesos: &Mesos) {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let shim = out_dir.join(SHIM);
+
+    let (protobuf_dirs, protobuf_libs) = find_protobuf(mesos);
+    let mut command = gcc::Config::new().cpp(true).get_compiler()
+        .to_command();
+    command.args(&["-std=c++11", "-shared", "-fPIC", "-o"])
+        .arg(&shim)
+        .arg("resources/mesos-c/scheduler_driver.cpp")
+        .arg("-Iresources/mesos-c");
+    for dir in &mesos.include_dirs {
+        command.arg(format!("-I{}", dir.display()));
+    }
+    for dir in mesos.link_dirs.iter().chain(&protobuf_dirs) {
+        command.arg(format!("-L{}", dir.display()));
+    }
+    for lib in mesos.libs.iter().chain(&protobuf_libs) {
+        command.arg(format!("-l{}", lib));
+    }
+
+    match command.status() {
+        Ok(ref status) if status.success() => {},
+        Ok(status) => fail(&format!("Failed to build {}: the compiler \
+                                     exited with {}", SHIM, status)),
+        Err(e) => fail(&format!("Failed to run the compiler to build {}: {}",
+                                SHIM, e)),
+    }
+
+    // OUT_DIR is `target/<profile>/build/<package>/out`.
+    if let Some(profile_dir) = out_dir.ancestors().nth(3) {
+        if let Err(e) = fs::copy(&shim, profile_dir.join(SHIM)) {
+            fail(&format!("Failed to copy {} to '{}': {}",
+                          SHIM, profile_dir.display(), e));
+        }
+    }
+}
+
+// Looks for Mesos in, in order: MESOS_INCLUDE_DIR and MESOS_LIB_DIR,
+// MESOS_HOME, pkg-config, and /usr/local.  Fails if the environment points
+// at the wrong place, and returns `None` if Mesos is nowhere to be found.
+fn find_mesos() -> Option<Mesos> {
+    let home = env::var_os("MESOS_HOME").map(PathBuf::from);
+    let include_dir = env::var_os("MESOS_INCLUDE_DIR").map(PathBuf::from)
+        .or(home.as_ref().map(|home| home.join("include")));
+    let lib_dir = env::var_os("MESOS_LIB_DIR").map(PathBuf::from)
+        .or(home.as_ref().map(|home| home.join("lib")));
+
+    if include_dir.is_some() || lib_dir.is_some() {
+        let include_dir = include_dir.unwrap_or_else(|| {
+            fail("MESOS_LIB_DIR is set, but MESOS_INCLUDE_DIR is not")
+        });
+        let lib_dir = lib_dir.unwrap_or_else(|| {
+            fail("MESOS_INCLUDE_DIR is set, but MESOS_LIB_DIR is not")
+        });
+        check_headers(&include_dir, "MESOS_HOME or MESOS_INCLUDE_DIR");
+        check_library(&lib_dir, "MESOS_HOME or MESOS_LIB_DIR");
+        return Some(Mesos {
+            include_dirs: vec![include_dir],
+            link_dirs: vec![lib_dir],
+            libs: vec!["mesos".to_string()],
+        });
+    }
+
+    // The link directives are emitted by whoever links against Mesos.
+    if let Ok(library) = pkg_config::Config::new()
+        .cargo_metadata(false)
+        .probe("mesos") {
+        return Some(Mesos {
+            include_dirs: library.include_paths,
+            link_dirs: library.link_paths,
+            libs: library.libs,
+        });
+    }
+
+    let prefix = Path::new("/usr/local");
+    if prefix.join("include/mesos/scheduler.hpp").is_file() {
+        check_library(&prefix.join("lib"),
+                      "the installation in /usr/local, or set MESOS_HOME");
+        return Some(Mesos {
+            include_dirs: vec![prefix.join("include")],
+            link_dirs: vec![prefix.join("lib")],
+            libs: vec!["mesos".to_string()],
+        });
+    }
+
+    None
+}
+
+// Finds protobuf-lite, through pkg-config or next to libmesos, as Mesos
+// installations often bundle it.  Returns its link directories and
+// libraries.
+fn find_protobuf(mesos: &Mesos) -> (Vec<PathBuf>, Vec<String>) {
+    let error = match pkg_config::Config::new()
+        .cargo_metadata(false)
+        .probe("protobuf-lite") {
+        Ok(library) => return (library.link_paths, library.libs),
+        Err(error) => error,
+    };
+
+    let bundled = mesos.link_dirs.iter()
+        .any(|dir| has_library(dir, "protobuf-lite"));
+    if !bundled {
+        fail(&format!("Could not find protobuf-lite.  Install the protobuf \
+                       development files so that pkg-config finds \
+                       `protobuf-lite`, or install libprotobuf-lite next to \
+                       libmesos.  pkg-config said: {}",
+                      error));
+    }
+    (vec![], vec!["protobuf-lite".to_string()])
+}
+
+// `check` says what to fix if the headers are missing.
+fn check_headers(include_dir: &Path, check: &str) {
+    if !include_dir.join("mesos/scheduler.hpp").is_file() {
+        fail(&format!("Could not find mesos/scheduler.hpp in '{}'; check {}",
+                      include_dir.display(), check));
+    }
+}
+
+// `check` says what to fix if the library is missing.
+fn check_library(lib_dir: &Path, check: &str) {
+    if !has_library(lib_dir, "mesos") {
+        fail(&format!("Could not find libmesos in '{}'; check {}",
+                      lib_dir.display(), check));
+    }
+}
+
+fn has_library(lib_dir: &Path, name: &str) -> bool {
+    ["so", "dylib", "a"].iter()
+        .any(|extension| {
+            lib_dir.join(format!("lib{}.{}", name, extension)).is_file()
+        })
+}
+
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "error: {}", message);
+    process::exit(1);
 }
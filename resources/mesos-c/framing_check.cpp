@@ -0,0 +1,42 @@
+// Entry point to the framing decoder of `utils.hpp` for the tests of
+// `src/native`, which feed it the same inputs as `framing::decode`.
+//
+// Messages are decoded as opaque bytes, so that only the framing is
+// exercised, and this file does not depend on the Mesos headers.
+
+#include <vector>
+
+#include "utils.hpp"
+
+namespace {
+  struct OpaqueMessage {
+    size_t size;
+
+    bool ParseFromArray(const void* data, int size)
+    {
+      this->size = (size_t)size;
+      return data != NULL || size == 0;
+    }
+  };
+}
+
+// Decodes the frames of `data`, writing the length of each message to
+// `lengths` while there is room for it.  Returns the number of messages,
+// or -1 if the frames are malformed.
+extern "C" long long mesos_c_decode_frames(
+    const void* data,
+    size_t size,
+    size_t* lengths,
+    size_t capacity)
+{
+  std::vector<OpaqueMessage> messages;
+  const char* error = NULL;
+  if (!utils::decodeFrames<OpaqueMessage>(messages, data, size, &error)) {
+    return -1;
+  }
+
+  for (size_t i = 0; i < messages.size() && i < capacity; i++) {
+    lengths[i] = messages[i].size;
+  }
+  return (long long)messages.size();
+}
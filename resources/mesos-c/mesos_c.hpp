@@ -192,6 +192,10 @@ SchedulerDriverStatus scheduler_sendFrameworkMessage(
     ProtobufObj* slaveId,  // SlaveID
     const char* data);     // std::string& data
 
+SchedulerDriverStatus scheduler_reconcileTasks(
+    SchedulerDriverPtr driver,
+    ProtobufObj* statuses); // std::vector<TaskStatus>
+
 SchedulerPtrPair scheduler_init(
     SchedulerCallBacks* callbacks, // Scheduler
     void* payload,                 // Opaque Rust scheduler pointer
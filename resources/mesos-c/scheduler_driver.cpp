@@ -315,6 +315,26 @@ SchedulerDriverStatus scheduler_sendFrameworkMessage(
 }
 
 
+SchedulerDriverStatus scheduler_reconcileTasks(
+    SchedulerDriverPtr driver,
+    ProtobufObj* statuses)
+{
+  TRACE("scheduler_reconcileTasks()\n");
+  assert(driver != NULL);
+  assert(statuses != NULL);
+
+  MesosSchedulerDriver* mdriver =
+    reinterpret_cast<MesosSchedulerDriver*>(driver);
+
+  vector<TaskStatus> statuses_;
+  if (!utils::deserialize<TaskStatus>(statuses_, statuses)) {
+    return DRIVER_ABORTED;
+  }
+
+  return mdriver->reconcileTasks(statuses_);
+}
+
+
 void CScheduler::registered(SchedulerDriver* driver,
     const FrameworkID& frameworkId,
     const MasterInfo& masterInfo)
@@ -1,6 +1,10 @@
 #ifndef __MESOS_C_UTILS_HPP__
 #define __MESOS_C_UTILS_HPP__
 
+#include <limits.h>
+#include <stdint.h>
+#include <stdio.h>
+
 #include <string>
 #include <vector>
 
@@ -35,22 +39,66 @@ namespace utils {
     return pbObj;
   }
 
-  template<typename T> inline bool deserialize(
+  // Size of the length prefix of each message in a framed vector.
+  const size_t FRAME_LENGTH_SIZE = 8;
+
+  // Decodes a vector of messages framed by `src/native/framing.rs`: each
+  // message is preceded by its length as a little-endian 64-bit integer.
+  // Every length is checked against the bytes that remain; on failure,
+  // `error` says why.
+  template<typename T> inline bool decodeFrames(
       std::vector<T>& ret,
-      void* data,
-      size_t size)
+      const void* data,
+      size_t size,
+      const char** error)
   {
-    char *end = (char*)data + size;
-    for (char* cur = (char*)data; cur < end;) {
-      uint64_t length = *(uint64_t*)cur;
+    if (data == NULL && size > 0) {
+      *error = "no data";
+      return false;
+    }
+
+    const unsigned char* cur = (const unsigned char*)data;
+    size_t remaining = size;
+    while (remaining > 0) {
+      if (remaining < FRAME_LENGTH_SIZE) {
+        *error = "truncated length";
+        return false;
+      }
+
+      uint64_t length = 0;
+      for (size_t i = 0; i < FRAME_LENGTH_SIZE; i++) {
+        length |= (uint64_t)cur[i] << (8 * i);
+      }
+      cur += FRAME_LENGTH_SIZE;
+      remaining -= FRAME_LENGTH_SIZE;
+
+      if (length > remaining || length > (uint64_t)INT_MAX) {
+        *error = "truncated message";
+        return false;
+      }
+
       T obj;
-      if (!obj.ParseFromArray(cur + sizeof(uint64_t), length)) {
-        printf("Deserialization failed\n");
+      if (!obj.ParseFromArray(cur, (int)length)) {
+        *error = "invalid message";
         return false;
       }
       ret.push_back(obj);
 
-      cur += (sizeof(uint64_t) + length);
+      cur += length;
+      remaining -= length;
+    }
+    return true;
+  }
+
+  template<typename T> inline bool deserialize(
+      std::vector<T>& ret,
+      void* data,
+      size_t size)
+  {
+    const char* error = NULL;
+    if (!decodeFrames<T>(ret, data, size, &error)) {
+      fprintf(stderr, "Deserialization failed: %s\n", error);
+      return false;
     }
     return true;
   }
@@ -0,0 +1,123 @@
+//! # Stream-based scheduler API.
+//!
+//! The callback-style `Scheduler` trait runs all framework logic on the
+//! driver's callback thread, where blocking risks a deadlock.  This module
+//! adapts any `SchedulerDriver` implementation to the `futures` model: the
+//! scheduler callbacks are delivered as a `Stream` of `SchedulerEvent`s, and
+//! driver calls are issued through a cloneable `SchedulerClient` whose
+//! methods return futures of the driver status.  Both can be used from a
+//! tokio runtime, e.g.
+//!
+//! ```ignore
+//! let (scheduler, events, client, calls) = async_scheduler::new();
+//!
+//! thread::spawn(move || {
+//!     let mut driver = MesosSchedulerDriver::new(
+//!         &scheduler, &framework_info, master);
+//!     calls.spawn(driver.handle());
+//!     driver.run();
+//! });
+//!
+//! tokio::run(events.for_each(move |event| {
+//!     if let SchedulerEvent::Offers(offers) = event {
+//!         for offer in offers {
+//!             tokio::spawn(client.decline_offer(offer.get_id(),
+//!                                               &pb::Filters::new())
+//!                 .then(|_| Ok(())));
+//!         }
+//!     }
+//!     Ok(())
+//! }));
+//! ```
+//!
+//! Calls made through the client are performed by the `CallQueue`, on a
+//! thread of its own, as soon as they are made (see `call_queue`).
+
+mod tests;
+
+use call_queue::{self, CallQueue, CallSender, ReplySender};
+use event::{EventHandler, EventScheduler, SchedulerEvent};
+use futures::{Async, Future, Poll, Stream};
+use futures::sync::{mpsc, oneshot};
+use scheduler::SchedulerDriver;
+
+/// Returns a `Scheduler` to be run by a `SchedulerDriver`, the stream of
+/// events it receives, a client for issuing driver calls, and the queue
+/// that performs them.
+pub fn new() -> (AsyncScheduler, EventStream, SchedulerClient,
+                 CallQueue<oneshot::Sender<i32>>) {
+    let (event_sender, event_receiver) = mpsc::unbounded();
+    let (client, calls) = call_queue::new();
+
+    (EventScheduler::new(Bridge { events: event_sender }),
+     EventStream { events: event_receiver },
+     client,
+     calls)
+}
+
+/// The `Scheduler` half of the adapter; pass it to a `SchedulerDriver`.
+pub type AsyncScheduler = EventScheduler<Bridge>;
+
+/// Forwards events to the stream.
+pub struct Bridge {
+    events: mpsc::UnboundedSender<SchedulerEvent>,
+}
+
+impl EventHandler for Bridge {
+    fn handle(&self, _driver: &SchedulerDriver, event: SchedulerEvent) {
+        // If the stream has been dropped nobody is interested in events
+        // any more.
+        let _ = self.events.unbounded_send(event);
+    }
+}
+
+/// The events received by an `AsyncScheduler`, in callback order.  The
+/// stream ends when the scheduler is dropped.
+pub struct EventStream {
+    events: mpsc::UnboundedReceiver<SchedulerEvent>,
+}
+
+impl Stream for EventStream {
+    type Item = SchedulerEvent;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<SchedulerEvent>, ()> {
+        self.events.poll()
+    }
+}
+
+/// Resolves to the driver status of a call made through `SchedulerClient`,
+/// or fails with `oneshot::Canceled` if the call queue was dropped before
+/// the call could be performed.
+pub struct CallFuture {
+    reply: oneshot::Receiver<i32>,
+}
+
+impl Future for CallFuture {
+    type Item = i32;
+    type Error = oneshot::Canceled;
+
+    fn poll(&mut self) -> Poll<i32, oneshot::Canceled> {
+        match try!(self.reply.poll()) {
+            Async::Ready(status) => Ok(Async::Ready(status)),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl ReplySender for oneshot::Sender<i32> {
+    type Reply = CallFuture;
+
+    fn channel() -> (oneshot::Sender<i32>, CallFuture) {
+        let (sender, receiver) = oneshot::channel();
+        (sender, CallFuture { reply: receiver })
+    }
+
+    fn send_status(self, status: i32) {
+        let _ = self.send(status);
+    }
+}
+
+/// Issues driver calls on behalf of an `AsyncScheduler`; each returns a
+/// `CallFuture`.
+pub type SchedulerClient = CallSender<oneshot::Sender<i32>>;
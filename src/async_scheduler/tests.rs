@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+
+    use ::async_scheduler;
+    use ::event::SchedulerEvent;
+    use ::proto::mesos as pb;
+    use ::scheduler::Scheduler;
+    use ::testing::MockSchedulerDriver;
+    use futures::{Future, Stream};
+    use std::thread;
+
+    fn offer(id: &str) -> pb::Offer {
+        let mut offer = pb::Offer::new();
+        offer.mut_id().set_value(id.to_string());
+        offer
+    }
+
+    #[test]
+    fn calls_answering_the_only_event_are_performed() {
+        let (scheduler, events, client, calls) = async_scheduler::new();
+        let served = calls.spawn(MockSchedulerDriver::new());
+
+        // A single offer, and no callback after it.
+        let callbacks = thread::spawn(move || {
+            scheduler.resource_offers(&MockSchedulerDriver::new(),
+                                      vec![offer("o1")]);
+            scheduler
+        });
+
+        let (event, _events) = events.into_future().wait().ok().unwrap();
+        let offers = match event {
+            Some(SchedulerEvent::Offers(offers)) => offers,
+            event => panic!("unexpected {:?}", event),
+        };
+
+        let mut task = pb::TaskInfo::new();
+        task.mut_task_id().set_value("t1".to_string());
+        let launch = client.launch_tasks(offers[0].get_id(), vec![task],
+                                         &pb::Filters::new());
+        assert_eq!(launch.wait(), Ok(pb::Status::DRIVER_RUNNING as i32));
+
+        drop(client);
+        let driver = served.join().unwrap();
+        assert_eq!(driver.launched_tasks().len(), 1);
+        callbacks.join().unwrap();
+    }
+
+    #[test]
+    fn calls_fail_once_the_queue_is_gone() {
+        let (scheduler, events, client, calls) = async_scheduler::new();
+        drop(calls);
+        drop(scheduler);
+
+        assert!(client.revive_offers().wait().is_err());
+        assert_eq!(events.collect().wait(), Ok(vec![]));
+    }
+}
@@ -0,0 +1,650 @@
+//! Runs a command on a Mesos cluster and waits for it to finish:
+//!
+//! ```text
+//! $ mesos-run --master zk://zk1:2181/mesos --instances 3 --cpus 0.5 \
+//!       --mem 256 --state-dir /var/lib/mesos-run -- ./batch --shard $HOST
+//! ```
+//!
+//! Status updates are printed as they arrive.  The process exits with the
+//! exit status of the command, or with the first non-zero exit status if
+//! several instances were run.  With `--state-dir`, the framework id and
+//! the launched tasks are persisted so that a restarted `mesos-run` fails
+//! over to the running tasks instead of launching them again.
+
+extern crate getopts;
+extern crate mesos;
+
+use getopts::{Matches, Options};
+use mesos::builder::{CommandInfoBuilder, TaskInfoBuilder};
+use mesos::native::MesosSchedulerDriver;
+use mesos::proto::mesos as pb;
+use mesos::scheduler::{Scheduler, SchedulerDriver};
+use mesos::state::{FileStateStore, PersistentScheduler, StateStore};
+use mesos::task_registry;
+
+use std::cell::RefCell;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::str;
+
+// The exit status of instances that have finished is kept under this
+// prefix, so that a restarted run does not launch them again.
+const EXIT_STATUS_KEY_PREFIX: &'static str = "mesos-run/exit_status/";
+
+struct Config {
+    master: String,
+    name: String,
+    user: String,
+    role: Option<String>,
+    principal: Option<String>,
+    checkpoint: bool,
+    failover_timeout: Option<f64>,
+    instances: usize,
+    cpus: f64,
+    mem: f64,
+    disk: f64,
+    env: Vec<(String, String)>,
+    uris: Vec<String>,
+    state_dir: Option<String>,
+    command: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instance {
+    Pending,
+    Launched,
+    Exited(i32),
+}
+
+struct Runner<'a> {
+    config: &'a Config,
+    store: Option<&'a StateStore>,
+    instances: RefCell<Vec<Instance>>,
+}
+
+impl<'a> Runner<'a> {
+
+    fn new(config: &'a Config, store: Option<&'a StateStore>) -> Runner<'a> {
+        let mut instances = vec![Instance::Pending; config.instances];
+        if let Some(store) = store {
+            let launched = store.task_ids().unwrap_or_else(|e| {
+                fail(&format!("Failed to read state: {}", e))
+            });
+            for (index, instance) in instances.iter_mut().enumerate() {
+                let task_id = task_id(config, index);
+                let key = exit_status_key(&task_id);
+                match store.get(&key) {
+                    Ok(Some(bytes)) => {
+                        let status = str::from_utf8(&bytes).ok()
+                            .and_then(|status| status.parse().ok())
+                            .unwrap_or(1);
+                        *instance = Instance::Exited(status);
+                    },
+                    Ok(None) => if launched.contains(&task_id) {
+                        *instance = Instance::Launched;
+                    },
+                    Err(e) => fail(&format!("Failed to read state: {}", e)),
+                }
+            }
+        }
+
+        Runner {
+            config: config,
+            store: store,
+            instances: RefCell::new(instances),
+        }
+    }
+
+    // The first non-zero exit status, 0 if every instance succeeded, or
+    // `None` while instances are still to run.
+    fn exit_status(&self) -> Option<i32> {
+        let mut exit_status = 0;
+        for instance in self.instances.borrow().iter() {
+            match *instance {
+                Instance::Exited(status) if exit_status == 0 =>
+                    exit_status = status,
+                Instance::Exited(_) => {},
+                _ => return None,
+            }
+        }
+        Some(exit_status)
+    }
+
+    // Takes the resources for one instance out of `available`, or returns
+    // `None` if the offer is too small.
+    fn take_resources(
+        &self,
+        available: &mut Vec<pb::Resource>
+    ) -> Option<Vec<pb::Resource>> {
+        let config = self.config;
+        let wanted = [("cpus", config.cpus), ("mem", config.mem),
+                      ("disk", config.disk)];
+
+        let mut remaining = available.clone();
+        let mut taken = vec![];
+        for &(name, amount) in wanted.iter() {
+            let mut needed = amount;
+            for offered in remaining.iter_mut() {
+                if needed <= 0.0 {
+                    break;
+                }
+                if offered.get_name() != name ||
+                   offered.get_field_type() != pb::Value_Type::SCALAR {
+                    continue;
+                }
+
+                let value = offered.get_scalar().get_value();
+                let used = value.min(needed);
+                if used <= 0.0 {
+                    continue;
+                }
+
+                let mut resource = offered.clone();
+                resource.mut_scalar().set_value(used);
+                taken.push(resource);
+                offered.mut_scalar().set_value(value - used);
+                needed -= used;
+            }
+            if needed > 1e-9 {
+                return None;
+            }
+        }
+
+        *available = remaining;
+        Some(taken)
+    }
+
+    fn task(
+        &self,
+        offer: &pb::Offer,
+        index: usize,
+        resources: &[pb::Resource]
+    ) -> pb::TaskInfo {
+        let config = self.config;
+        let mut command = CommandInfoBuilder::shell(&config.command);
+        for &(ref name, ref value) in &config.env {
+            command = command.env(name, value);
+        }
+        for uri in &config.uris {
+            command = command.uri(uri, false, true);
+        }
+
+        let command = command.build().unwrap_or_else(|e| {
+            fail(&format!("Invalid command: {}", e))
+        });
+        TaskInfoBuilder::new(&config.name, task_id(config, index).get_value())
+            .offer(offer)
+            .resources(resources)
+            .command(command)
+            .build()
+            .unwrap_or_else(|e| fail(&format!("Invalid task: {}", e)))
+    }
+
+    // Asks for the state of the tasks launched before a failover.
+    fn reconcile(&self, driver: &SchedulerDriver) {
+        let statuses: Vec<pb::TaskStatus> = self.instances.borrow().iter()
+            .enumerate()
+            .filter(|&(_, instance)| *instance == Instance::Launched)
+            .map(|(index, _)| {
+                let mut status = pb::TaskStatus::new();
+                status.set_task_id(task_id(self.config, index));
+                status.set_state(pb::TaskState::TASK_STAGING);
+                status
+            })
+            .collect();
+
+        if !statuses.is_empty() {
+            driver.reconcile_tasks(&statuses.iter().collect());
+        }
+    }
+
+    fn finish_if_done(&self, driver: &SchedulerDriver) {
+        if let Some(exit_status) = self.exit_status() {
+            println!("All instances done, exiting with status {}",
+                     exit_status);
+
+            // The framework is torn down, so the next run starts afresh.
+            if let Some(store) = self.store {
+                let cleared = store.clear_framework_id().and_then(|_| {
+                    for index in 0..self.config.instances {
+                        let task_id = task_id(self.config, index);
+                        try!(store.remove(&exit_status_key(&task_id)));
+                    }
+                    Ok(())
+                });
+                if let Err(e) = cleared {
+                    print_error(&format!("Failed to clear state: {}", e));
+                }
+            }
+            driver.stop(false);
+        }
+    }
+}
+
+impl<'a> Scheduler for Runner<'a> {
+
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        _master_info: &pb::MasterInfo) {
+
+        println!("Registered with framework id {}", framework_id.get_value());
+        self.reconcile(driver);
+        self.finish_if_done(driver);
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        _master_info: &pb::MasterInfo) {
+
+        println!("Re-registered");
+        self.reconcile(driver);
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        for offer in offers {
+            let mut available = offer.get_resources().to_vec();
+            let mut tasks = vec![];
+            loop {
+                let index = match self.instances.borrow().iter()
+                    .position(|instance| *instance == Instance::Pending) {
+                    Some(index) => index,
+                    None => break,
+                };
+                let resources = match self.take_resources(&mut available) {
+                    Some(resources) => resources,
+                    None => break,
+                };
+
+                let task = self.task(&offer, index, &resources);
+                if let Some(store) = self.store {
+                    if let Err(e) = store.add_task_id(task.get_task_id()) {
+                        print_error(&format!(
+                            "Failed to persist task id: {}", e));
+                    }
+                }
+                println!("Launching {} on {}", task.get_task_id().get_value(),
+                         offer.get_hostname());
+                self.instances.borrow_mut()[index] = Instance::Launched;
+                tasks.push(task);
+            }
+
+            if tasks.is_empty() {
+                driver.decline_offer(offer.get_id(), &pb::Filters::new());
+            } else {
+                driver.launch_tasks(offer.get_id(),
+                                    &tasks.iter().collect(),
+                                    &pb::Filters::new());
+            }
+        }
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        let id = task_status.get_task_id();
+        let state = task_status.get_state();
+        if task_status.get_message().is_empty() {
+            println!("{} {:?}", id.get_value(), state);
+        } else {
+            println!("{} {:?}: {}", id.get_value(), state,
+                     task_status.get_message());
+        }
+
+        let index = match (0..self.config.instances)
+            .find(|&index| *id == task_id(self.config, index)) {
+            Some(index) => index,
+            None => return,
+        };
+
+        if task_registry::is_terminal(state) {
+            let status = exit_status(task_status);
+            if let Some(store) = self.store {
+                let key = exit_status_key(id);
+                let value = status.to_string();
+                if let Err(e) = store.set(&key, value.as_bytes()) {
+                    print_error(&format!(
+                        "Failed to persist exit status: {}", e));
+                }
+            }
+            self.instances.borrow_mut()[index] = Instance::Exited(status);
+            self.finish_if_done(driver);
+        }
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        print_error(&format!("Error: {}", message));
+        driver.stop(false);
+    }
+}
+
+fn task_id(config: &Config, index: usize) -> pb::TaskID {
+    let mut task_id = pb::TaskID::new();
+    task_id.set_value(format!("{}.{}", config.name, index));
+    task_id
+}
+
+fn exit_status_key(task_id: &pb::TaskID) -> String {
+    format!("{}{}", EXIT_STATUS_KEY_PREFIX, task_id.get_value())
+}
+
+// Signals by number, name and description (as given by strsignal(3) on
+// Linux), for the messages of command executors.
+const SIGNALS: &'static [(i32, &'static str, &'static str)] = &[
+    (1, "SIGHUP", "Hangup"),
+    (2, "SIGINT", "Interrupt"),
+    (3, "SIGQUIT", "Quit"),
+    (4, "SIGILL", "Illegal instruction"),
+    (5, "SIGTRAP", "Trace/breakpoint trap"),
+    (6, "SIGABRT", "Aborted"),
+    (7, "SIGBUS", "Bus error"),
+    (8, "SIGFPE", "Floating point exception"),
+    (9, "SIGKILL", "Killed"),
+    (10, "SIGUSR1", "User defined signal 1"),
+    (11, "SIGSEGV", "Segmentation fault"),
+    (12, "SIGUSR2", "User defined signal 2"),
+    (13, "SIGPIPE", "Broken pipe"),
+    (14, "SIGALRM", "Alarm clock"),
+    (15, "SIGTERM", "Terminated"),
+    (24, "SIGXCPU", "CPU time limit exceeded"),
+    (25, "SIGXFSZ", "File size limit exceeded"),
+];
+
+// Maps a terminal status to a shell style exit status, using the message
+// of the command executor for failed tasks: "Command exited with status 3",
+// or "Command terminated with signal Killed" from the Mesos executor and
+// "Command terminated by signal 9" from the one of this crate.
+fn exit_status(task_status: &pb::TaskStatus) -> i32 {
+    if task_status.get_state() == pb::TaskState::TASK_FINISHED {
+        return 0;
+    }
+
+    let message = task_status.get_message().trim();
+    if message.starts_with("Command exited with status ") {
+        let status = &message["Command exited with status ".len()..];
+        return status.parse().unwrap_or(1);
+    }
+
+    let prefixes = ["Command terminated with signal ",
+                    "Command terminated by signal "];
+    for prefix in prefixes.iter() {
+        if message.starts_with(prefix) {
+            return signal_number(&message[prefix.len()..])
+                .map_or(1, |signal| 128 + signal);
+        }
+    }
+    1
+}
+
+fn signal_number(signal: &str) -> Option<i32> {
+    signal.parse().ok().or_else(|| {
+        SIGNALS.iter()
+            .find(|&&(_, name, description)| {
+                signal == name || signal.eq_ignore_ascii_case(description)
+            })
+            .map(|&(number, _, _)| number)
+    })
+}
+
+fn print_error(message: &str) {
+    let _ = writeln!(io::stderr(), "{}", message);
+}
+
+fn fail(message: &str) -> ! {
+    print_error(message);
+    process::exit(2);
+}
+
+fn options() -> Options {
+    let mut options = Options::new();
+    options.optopt("", "master", "Mesos master, e.g. zk://host:2181/mesos \
+                                  ($MESOS_MASTER or localhost:5050)", "URL");
+    options.optopt("", "name", "Framework and task name (mesos-run)",
+                   "NAME");
+    options.optopt("", "user", "User to run the command as ($USER)", "USER");
+    options.optopt("", "role", "Role to register with", "ROLE");
+    options.optopt("", "principal", "Principal to register with",
+                   "PRINCIPAL");
+    options.optflag("", "checkpoint",
+                    "Let tasks survive slave restarts");
+    options.optopt("", "failover-timeout",
+                   "Seconds to keep tasks running while mesos-run is \
+                    down", "SECS");
+    options.optopt("", "instances", "Number of instances to run (1)", "N");
+    options.optopt("", "cpus", "CPUs per instance (1)", "CPUS");
+    options.optopt("", "mem", "Memory per instance in MB (128)", "MB");
+    options.optopt("", "disk", "Disk per instance in MB (0)", "MB");
+    options.optmulti("e", "env", "Environment variable of the command",
+                     "NAME=VALUE");
+    options.optmulti("", "uri", "URI to fetch into the sandbox", "URI");
+    options.optopt("", "state-dir",
+                   "Directory to persist the framework id and tasks in, \
+                    for failover", "DIR");
+    options.optflag("h", "help", "Print this help");
+    options
+}
+
+fn number<T: str::FromStr>(matches: &Matches, name: &str, default: T) -> T {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            fail(&format!("--{} expects a number, not '{}'", name, value))
+        }),
+        None => default,
+    }
+}
+
+fn parse_config(options: &Options, args: &[String]) -> Config {
+    let matches = options.parse(args).unwrap_or_else(|e| {
+        fail(&format!("{}\n\n{}", e, usage(options)))
+    });
+    if matches.opt_present("help") {
+        println!("{}", usage(options));
+        process::exit(0);
+    }
+    if matches.free.is_empty() {
+        fail(&format!("No command given\n\n{}", usage(options)));
+    }
+
+    let env = matches.opt_strs("env").iter().map(|variable| {
+        match variable.find('=') {
+            Some(at) => (variable[..at].to_string(),
+                         variable[at + 1..].to_string()),
+            None => fail(&format!("--env expects NAME=VALUE, not '{}'",
+                                  variable)),
+        }
+    }).collect();
+
+    let failover_timeout = matches.opt_str("failover-timeout")
+        .map(|_| number(&matches, "failover-timeout", 0.0));
+
+    Config {
+        master: matches.opt_str("master")
+            .or_else(|| env::var("MESOS_MASTER").ok())
+            .unwrap_or("localhost:5050".to_string()),
+        name: matches.opt_str("name").unwrap_or("mesos-run".to_string()),
+        user: matches.opt_str("user")
+            .or_else(|| env::var("USER").ok())
+            .unwrap_or("root".to_string()),
+        role: matches.opt_str("role"),
+        principal: matches.opt_str("principal"),
+        checkpoint: matches.opt_present("checkpoint"),
+        failover_timeout: failover_timeout,
+        instances: number(&matches, "instances", 1),
+        cpus: number(&matches, "cpus", 1.0),
+        mem: number(&matches, "mem", 128.0),
+        disk: number(&matches, "disk", 0.0),
+        env: env,
+        uris: matches.opt_strs("uri"),
+        state_dir: matches.opt_str("state-dir"),
+        command: matches.free.join(" "),
+    }
+}
+
+fn usage(options: &Options) -> String {
+    options.usage("Usage: mesos-run [options] [--] COMMAND...")
+}
+
+fn framework_info(config: &Config) -> pb::FrameworkInfo {
+    let mut framework_info = pb::FrameworkInfo::new();
+    framework_info.set_name(config.name.clone());
+    framework_info.set_user(config.user.clone());
+    framework_info.set_checkpoint(config.checkpoint);
+    if let Some(ref role) = config.role {
+        framework_info.set_role(role.clone());
+    }
+    if let Some(ref principal) = config.principal {
+        framework_info.set_principal(principal.clone());
+    }
+    if let Some(failover_timeout) = config.failover_timeout {
+        framework_info.set_failover_timeout(failover_timeout);
+    }
+    framework_info
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = parse_config(&options(), &args);
+    let framework_info = framework_info(&config);
+
+    let store = config.state_dir.as_ref().map(|directory| {
+        FileStateStore::new(directory).unwrap_or_else(|e| {
+            fail(&format!("Failed to open {}: {}", directory, e))
+        })
+    });
+    let store = store.as_ref().map(|store| store as &StateStore);
+
+    let runner = Runner::new(&config, store);
+    let exit_status = match store {
+        Some(store) => {
+            let scheduler = PersistentScheduler::new(&runner, store);
+            let mut driver = MesosSchedulerDriver::new(
+                &scheduler, &framework_info, config.master.clone());
+            driver.set_state_store(store);
+            driver.run();
+            runner.exit_status()
+        },
+        None => {
+            let mut driver = MesosSchedulerDriver::new(
+                &runner, &framework_info, config.master.clone());
+            driver.run();
+            runner.exit_status()
+        },
+    };
+
+    // The driver also stops when the framework is removed or aborts.
+    process::exit(exit_status.unwrap_or(1));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use mesos::builder::resource;
+    use mesos::proto::mesos as pb;
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn failed(message: &str) -> pb::TaskStatus {
+        let mut status = pb::TaskStatus::new();
+        status.set_state(pb::TaskState::TASK_FAILED);
+        status.set_message(message.to_string());
+        status
+    }
+
+    #[test]
+    fn exit_statuses_follow_the_shell() {
+        let mut finished = pb::TaskStatus::new();
+        finished.set_state(pb::TaskState::TASK_FINISHED);
+        assert_eq!(exit_status(&finished), 0);
+
+        assert_eq!(exit_status(&failed("Command exited with status 3")), 3);
+        assert_eq!(exit_status(&failed("Command terminated by signal 15")),
+                   143);
+        assert_eq!(
+            exit_status(&failed("Command terminated with signal Killed")),
+            137);
+        assert_eq!(exit_status(&failed(
+            "Command terminated with signal Segmentation fault")), 139);
+        assert_eq!(
+            exit_status(&failed("Command terminated with signal SIGTERM")),
+            143);
+        assert_eq!(
+            exit_status(&failed("Command terminated with signal Unknown")),
+            1);
+        assert_eq!(exit_status(&failed("Executor terminated")), 1);
+        assert_eq!(exit_status(&failed("")), 1);
+    }
+
+    #[test]
+    fn parses_options_and_command() {
+        let config = parse_config(&options(), &args(&[
+            "--master", "zk://zk1:2181/mesos", "--instances", "3",
+            "--cpus", "0.5", "-e", "MODE=batch", "--uri", "http://x/y.tgz",
+            "--failover-timeout", "60", "--", "./batch", "--shard", "$HOST",
+        ]));
+
+        assert_eq!(config.master, "zk://zk1:2181/mesos");
+        assert_eq!(config.name, "mesos-run");
+        assert_eq!(config.instances, 3);
+        assert_eq!(config.cpus, 0.5);
+        assert_eq!(config.mem, 128.0);
+        assert_eq!(config.env,
+                   vec![("MODE".to_string(), "batch".to_string())]);
+        assert_eq!(config.uris, vec!["http://x/y.tgz".to_string()]);
+        assert_eq!(config.failover_timeout, Some(60.0));
+        assert!(!config.checkpoint);
+        assert_eq!(config.command, "./batch --shard $HOST");
+
+        let info = framework_info(&config);
+        assert_eq!(info.get_failover_timeout(), 60.0);
+        assert!(!info.has_role());
+    }
+
+    #[test]
+    fn takes_resources_across_offered_entries() {
+        let config = parse_config(&options(), &args(&[
+            "--cpus", "1.5", "--mem", "256", "true",
+        ]));
+        let runner = Runner::new(&config, None);
+
+        let mut reserved = resource::scalar("cpus", 1.0);
+        reserved.set_role("batch".to_string());
+        let mut available = vec![
+            reserved,
+            resource::scalar("cpus", 2.0),
+            resource::scalar("mem", 300.0),
+            resource::ranges("ports", &[(31000, 32000)]),
+        ];
+
+        let taken = runner.take_resources(&mut available).unwrap();
+        let amounts: Vec<(&str, f64)> = taken.iter()
+            .map(|r| (r.get_name(), r.get_scalar().get_value()))
+            .collect();
+        assert_eq!(amounts, vec![("cpus", 1.0), ("cpus", 0.5),
+                                 ("mem", 256.0)]);
+        assert_eq!(taken[0].get_role(), "batch");
+        assert_eq!(available[1].get_scalar().get_value(), 1.5);
+        assert_eq!(available[2].get_scalar().get_value(), 44.0);
+
+        // Too little memory is left for a second instance, and nothing is
+        // taken.
+        let before = available.clone();
+        assert_eq!(runner.take_resources(&mut available), None);
+        assert_eq!(available, before);
+    }
+}
@@ -0,0 +1,88 @@
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `CommandInfo`.
+///
+/// ```ignore
+/// let command = CommandInfoBuilder::shell("env && sleep 10")
+///     .env("GREETING", "hello")
+///     .uri("http://example.com/app.tgz", false, true)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CommandInfoBuilder {
+    command: pb::CommandInfo,
+}
+
+impl CommandInfoBuilder {
+
+    /// Starts a command that is run via `/bin/sh -c`.
+    pub fn shell(value: &str) -> CommandInfoBuilder {
+        let mut command = pb::CommandInfo::new();
+        command.set_shell(true);
+        command.set_value(value.to_string());
+        CommandInfoBuilder { command: command }
+    }
+
+    /// Starts a command that executes `executable` directly, passing
+    /// `arguments` as its argv (the first argument is conventionally the
+    /// executable name).
+    pub fn exec(executable: &str, arguments: &[&str]) -> CommandInfoBuilder {
+        let mut command = pb::CommandInfo::new();
+        command.set_shell(false);
+        command.set_value(executable.to_string());
+        for argument in arguments {
+            command.mut_arguments().push(argument.to_string());
+        }
+        CommandInfoBuilder { command: command }
+    }
+
+    /// Appends an argument; only meaningful for `exec` commands.
+    pub fn arg(mut self, argument: &str) -> CommandInfoBuilder {
+        self.command.mut_arguments().push(argument.to_string());
+        self
+    }
+
+    /// Adds an environment variable.
+    pub fn env(mut self, name: &str, value: &str) -> CommandInfoBuilder {
+        let mut variable = pb::Environment_Variable::new();
+        variable.set_name(name.to_string());
+        variable.set_value(value.to_string());
+        self.command.mut_environment().mut_variables().push(variable);
+        self
+    }
+
+    /// Adds a URI to be fetched into the sandbox before the command runs.
+    pub fn uri(
+        mut self,
+        value: &str,
+        executable: bool,
+        extract: bool
+    ) -> CommandInfoBuilder {
+        let mut uri = pb::CommandInfo_URI::new();
+        uri.set_value(value.to_string());
+        uri.set_executable(executable);
+        uri.set_extract(extract);
+        self.command.mut_uris().push(uri);
+        self
+    }
+
+    /// Adds a fully specified URI, e.g. one with `cache` set.
+    pub fn uri_info(mut self, uri: pb::CommandInfo_URI) -> CommandInfoBuilder {
+        self.command.mut_uris().push(uri);
+        self
+    }
+
+    /// Sets the user to run the command as.
+    pub fn user(mut self, user: &str) -> CommandInfoBuilder {
+        self.command.set_user(user.to_string());
+        self
+    }
+
+    /// Validates and returns the `CommandInfo`.
+    pub fn build(self) -> Result<pb::CommandInfo, ValidationError> {
+        try!(validation::command_info(&self.command));
+        Ok(self.command)
+    }
+}
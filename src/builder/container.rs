@@ -0,0 +1,148 @@
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `ContainerInfo`, covering both the Docker and the
+/// Mesos containerizer.
+///
+/// ```ignore
+/// let container = ContainerInfoBuilder::docker("nginx:1.9")
+///     .network(pb::ContainerInfo_DockerInfo_Network::BRIDGE)
+///     .port_mapping(31000, 80, "tcp")
+///     .volume(VolumeBuilder::new("/data", pb::Volume_Mode::RW)
+///         .host_path("/var/data")
+///         .build()
+///         .unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContainerInfoBuilder {
+    container: pb::ContainerInfo,
+}
+
+impl ContainerInfoBuilder {
+
+    /// Starts a container run by the Docker containerizer from `image`.
+    pub fn docker(image: &str) -> ContainerInfoBuilder {
+        let mut docker = pb::ContainerInfo_DockerInfo::new();
+        docker.set_image(image.to_string());
+
+        let mut container = pb::ContainerInfo::new();
+        container.set_field_type(pb::ContainerInfo_Type::DOCKER);
+        container.set_docker(docker);
+        ContainerInfoBuilder { container: container }
+    }
+
+    /// Starts a container run by the Mesos containerizer.
+    pub fn mesos() -> ContainerInfoBuilder {
+        let mut container = pb::ContainerInfo::new();
+        container.set_field_type(pb::ContainerInfo_Type::MESOS);
+        ContainerInfoBuilder { container: container }
+    }
+
+    /// Sets the image provisioned by the Mesos containerizer.
+    pub fn image(mut self, image: pb::Image) -> ContainerInfoBuilder {
+        self.container.mut_mesos().set_image(image);
+        self
+    }
+
+    pub fn hostname(mut self, hostname: &str) -> ContainerInfoBuilder {
+        self.container.set_hostname(hostname.to_string());
+        self
+    }
+
+    pub fn volume(mut self, volume: pb::Volume) -> ContainerInfoBuilder {
+        self.container.mut_volumes().push(volume);
+        self
+    }
+
+    /// Sets the Docker network mode.
+    pub fn network(
+        mut self,
+        network: pb::ContainerInfo_DockerInfo_Network
+    ) -> ContainerInfoBuilder {
+        self.container.mut_docker().set_network(network);
+        self
+    }
+
+    /// Maps a host port (which must come from the offer's `ports`
+    /// resource) to a port inside a bridged Docker container.
+    pub fn port_mapping(
+        mut self,
+        host_port: u32,
+        container_port: u32,
+        protocol: &str
+    ) -> ContainerInfoBuilder {
+        let mut mapping = pb::ContainerInfo_DockerInfo_PortMapping::new();
+        mapping.set_host_port(host_port);
+        mapping.set_container_port(container_port);
+        mapping.set_protocol(protocol.to_string());
+        self.container.mut_docker().mut_port_mappings().push(mapping);
+        self
+    }
+
+    pub fn privileged(mut self, privileged: bool) -> ContainerInfoBuilder {
+        self.container.mut_docker().set_privileged(privileged);
+        self
+    }
+
+    pub fn force_pull_image(mut self, force: bool) -> ContainerInfoBuilder {
+        self.container.mut_docker().set_force_pull_image(force);
+        self
+    }
+
+    /// Passes an arbitrary `--key=value` option to `docker run`.
+    pub fn parameter(
+        mut self,
+        key: &str,
+        value: &str
+    ) -> ContainerInfoBuilder {
+        let mut parameter = pb::Parameter::new();
+        parameter.set_key(key.to_string());
+        parameter.set_value(value.to_string());
+        self.container.mut_docker().mut_parameters().push(parameter);
+        self
+    }
+
+    /// Validates and returns the `ContainerInfo`.
+    pub fn build(self) -> Result<pb::ContainerInfo, ValidationError> {
+        try!(validation::container_info(&self.container));
+        Ok(self.container)
+    }
+}
+
+/// Fluent builder for `Volume`.
+#[derive(Clone, Debug)]
+pub struct VolumeBuilder {
+    volume: pb::Volume,
+}
+
+impl VolumeBuilder {
+
+    /// Starts a volume mounted at `container_path` inside the container.
+    pub fn new(container_path: &str, mode: pb::Volume_Mode) -> VolumeBuilder {
+        let mut volume = pb::Volume::new();
+        volume.set_container_path(container_path.to_string());
+        volume.set_mode(mode);
+        VolumeBuilder { volume: volume }
+    }
+
+    /// Mounts `host_path` from the slave (or, if relative, from the
+    /// sandbox).
+    pub fn host_path(mut self, host_path: &str) -> VolumeBuilder {
+        self.volume.set_host_path(host_path.to_string());
+        self
+    }
+
+    /// Mounts the root filesystem of `image`.
+    pub fn image(mut self, image: pb::Image) -> VolumeBuilder {
+        self.volume.set_image(image);
+        self
+    }
+
+    /// Validates and returns the `Volume`.
+    pub fn build(self) -> Result<pb::Volume, ValidationError> {
+        try!(validation::volume(&self.volume));
+        Ok(self.volume)
+    }
+}
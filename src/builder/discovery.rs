@@ -0,0 +1,70 @@
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `DiscoveryInfo`.
+#[derive(Clone, Debug)]
+pub struct DiscoveryInfoBuilder {
+    discovery: pb::DiscoveryInfo,
+}
+
+impl DiscoveryInfoBuilder {
+
+    pub fn new(
+        visibility: pb::DiscoveryInfo_Visibility
+    ) -> DiscoveryInfoBuilder {
+        let mut discovery = pb::DiscoveryInfo::new();
+        discovery.set_visibility(visibility);
+        DiscoveryInfoBuilder { discovery: discovery }
+    }
+
+    pub fn name(mut self, name: &str) -> DiscoveryInfoBuilder {
+        self.discovery.set_name(name.to_string());
+        self
+    }
+
+    pub fn environment(mut self, environment: &str) -> DiscoveryInfoBuilder {
+        self.discovery.set_environment(environment.to_string());
+        self
+    }
+
+    pub fn location(mut self, location: &str) -> DiscoveryInfoBuilder {
+        self.discovery.set_location(location.to_string());
+        self
+    }
+
+    pub fn version(mut self, version: &str) -> DiscoveryInfoBuilder {
+        self.discovery.set_version(version.to_string());
+        self
+    }
+
+    /// Advertises a port, optionally named, with protocol `tcp` or `udp`.
+    pub fn port(
+        mut self,
+        number: u32,
+        name: Option<&str>,
+        protocol: &str
+    ) -> DiscoveryInfoBuilder {
+        let mut port = pb::Port::new();
+        port.set_number(number);
+        if let Some(name) = name {
+            port.set_name(name.to_string());
+        }
+        port.set_protocol(protocol.to_string());
+        self.discovery.mut_ports().mut_ports().push(port);
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> DiscoveryInfoBuilder {
+        let mut label = pb::Label::new();
+        label.set_key(key.to_string());
+        label.set_value(value.to_string());
+        self.discovery.mut_labels().mut_labels().push(label);
+        self
+    }
+
+    /// Validates and returns the `DiscoveryInfo`.
+    pub fn build(self) -> Result<pb::DiscoveryInfo, ValidationError> {
+        try!(validation::discovery_info(&self.discovery));
+        Ok(self.discovery)
+    }
+}
@@ -0,0 +1,79 @@
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `ExecutorInfo`.
+#[derive(Clone, Debug)]
+pub struct ExecutorInfoBuilder {
+    executor: pb::ExecutorInfo,
+}
+
+impl ExecutorInfoBuilder {
+
+    /// Starts an executor with the given id that is launched by running
+    /// `command` on the slave.
+    pub fn new(
+        executor_id: &str,
+        command: pb::CommandInfo
+    ) -> ExecutorInfoBuilder {
+        let mut id = pb::ExecutorID::new();
+        id.set_value(executor_id.to_string());
+
+        let mut executor = pb::ExecutorInfo::new();
+        executor.set_executor_id(id);
+        executor.set_command(command);
+        ExecutorInfoBuilder { executor: executor }
+    }
+
+    pub fn name(mut self, name: &str) -> ExecutorInfoBuilder {
+        self.executor.set_name(name.to_string());
+        self
+    }
+
+    pub fn source(mut self, source: &str) -> ExecutorInfoBuilder {
+        self.executor.set_source(source.to_string());
+        self
+    }
+
+    pub fn framework_id(
+        mut self,
+        framework_id: &pb::FrameworkID
+    ) -> ExecutorInfoBuilder {
+        self.executor.set_framework_id(framework_id.clone());
+        self
+    }
+
+    /// Adds a resource consumed by the executor itself (in addition to the
+    /// resources of its tasks).
+    pub fn resource(mut self, resource: pb::Resource) -> ExecutorInfoBuilder {
+        self.executor.mut_resources().push(resource);
+        self
+    }
+
+    pub fn container(
+        mut self,
+        container: pb::ContainerInfo
+    ) -> ExecutorInfoBuilder {
+        self.executor.set_container(container);
+        self
+    }
+
+    pub fn discovery(
+        mut self,
+        discovery: pb::DiscoveryInfo
+    ) -> ExecutorInfoBuilder {
+        self.executor.set_discovery(discovery);
+        self
+    }
+
+    /// Sets opaque data passed to the executor in `Executor::registered`.
+    pub fn data(mut self, data: Vec<u8>) -> ExecutorInfoBuilder {
+        self.executor.set_data(data);
+        self
+    }
+
+    /// Validates and returns the `ExecutorInfo`.
+    pub fn build(self) -> Result<pb::ExecutorInfo, ValidationError> {
+        try!(validation::executor_info(&self.executor));
+        Ok(self.executor)
+    }
+}
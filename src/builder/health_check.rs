@@ -0,0 +1,80 @@
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `HealthCheck`.  Exactly one of a command or an HTTP
+/// check must be configured.
+///
+/// ```ignore
+/// let check = HealthCheckBuilder::http(8080, "/health")
+///     .interval_seconds(5.0)
+///     .consecutive_failures(3)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct HealthCheckBuilder {
+    check: pb::HealthCheck,
+}
+
+impl HealthCheckBuilder {
+
+    /// Starts a check that passes when `command` exits with status 0.
+    pub fn command(command: pb::CommandInfo) -> HealthCheckBuilder {
+        let mut check = pb::HealthCheck::new();
+        check.set_command(command);
+        HealthCheckBuilder { check: check }
+    }
+
+    /// Starts a check that issues `GET path` against `port` on the task's
+    /// host.
+    pub fn http(port: u32, path: &str) -> HealthCheckBuilder {
+        let mut http = pb::HealthCheck_HTTP::new();
+        http.set_port(port);
+        http.set_path(path.to_string());
+
+        let mut check = pb::HealthCheck::new();
+        check.set_http(http);
+        HealthCheckBuilder { check: check }
+    }
+
+    /// Adds an HTTP status code treated as healthy; if none are given any
+    /// status in 200-399 is.
+    pub fn status(mut self, status: u32) -> HealthCheckBuilder {
+        self.check.mut_http().mut_statuses().push(status);
+        self
+    }
+
+    pub fn delay_seconds(mut self, seconds: f64) -> HealthCheckBuilder {
+        self.check.set_delay_seconds(seconds);
+        self
+    }
+
+    pub fn interval_seconds(mut self, seconds: f64) -> HealthCheckBuilder {
+        self.check.set_interval_seconds(seconds);
+        self
+    }
+
+    pub fn timeout_seconds(mut self, seconds: f64) -> HealthCheckBuilder {
+        self.check.set_timeout_seconds(seconds);
+        self
+    }
+
+    pub fn grace_period_seconds(mut self, seconds: f64) -> HealthCheckBuilder {
+        self.check.set_grace_period_seconds(seconds);
+        self
+    }
+
+    pub fn consecutive_failures(
+        mut self,
+        failures: u32
+    ) -> HealthCheckBuilder {
+        self.check.set_consecutive_failures(failures);
+        self
+    }
+
+    /// Validates and returns the `HealthCheck`.
+    pub fn build(self) -> Result<pb::HealthCheck, ValidationError> {
+        try!(validation::health_check(&self.check));
+        Ok(self.check)
+    }
+}
@@ -0,0 +1,31 @@
+//! # Builders for Mesos protobuf messages.
+//!
+//! The generated protobuf types in `proto::mesos` need many setter calls to
+//! populate, and mistakes only surface as `TASK_ERROR` status updates from
+//! the master.  The builders in this module provide a fluent interface for
+//! the messages involved in launching tasks, and validate the result with
+//! the checks in `builder::validation` before handing it out.
+//!
+//! `SchedulerBuilder` similarly assembles a `Scheduler` from closures, for
+//! frameworks that only need to handle a few of the callbacks.
+
+mod command;
+mod container;
+mod discovery;
+mod executor;
+mod health_check;
+mod scheduler;
+mod task;
+mod tests;
+
+pub mod resource;
+pub mod validation;
+
+pub use self::command::CommandInfoBuilder;
+pub use self::container::{ContainerInfoBuilder, VolumeBuilder};
+pub use self::discovery::DiscoveryInfoBuilder;
+pub use self::executor::ExecutorInfoBuilder;
+pub use self::health_check::HealthCheckBuilder;
+pub use self::scheduler::{ClosureScheduler, SchedulerBuilder};
+pub use self::task::TaskInfoBuilder;
+pub use self::validation::{ValidationError, Violation};
@@ -0,0 +1,45 @@
+use proto::mesos as pb;
+
+/// Returns a scalar resource, e.g. `scalar("cpus", 0.5)`.
+pub fn scalar(name: &str, value: f64) -> pb::Resource {
+    let mut scalar = pb::Value_Scalar::new();
+    scalar.set_value(value);
+
+    let mut resource = pb::Resource::new();
+    resource.set_name(name.to_string());
+    resource.set_field_type(pb::Value_Type::SCALAR);
+    resource.set_scalar(scalar);
+    resource
+}
+
+/// Returns a ranges resource from inclusive `(begin, end)` pairs, e.g.
+/// `ranges("ports", &[(31000, 31009)])`.
+pub fn ranges(name: &str, bounds: &[(u64, u64)]) -> pb::Resource {
+    let mut ranges = pb::Value_Ranges::new();
+    for &(begin, end) in bounds {
+        let mut range = pb::Value_Range::new();
+        range.set_begin(begin);
+        range.set_end(end);
+        ranges.mut_range().push(range);
+    }
+
+    let mut resource = pb::Resource::new();
+    resource.set_name(name.to_string());
+    resource.set_field_type(pb::Value_Type::RANGES);
+    resource.set_ranges(ranges);
+    resource
+}
+
+/// Returns a set resource, e.g. `set("gpus", &["gpu0", "gpu1"])`.
+pub fn set(name: &str, items: &[&str]) -> pb::Resource {
+    let mut set = pb::Value_Set::new();
+    for item in items {
+        set.mut_item().push(item.to_string());
+    }
+
+    let mut resource = pb::Resource::new();
+    resource.set_name(name.to_string());
+    resource.set_field_type(pb::Value_Type::SET);
+    resource.set_set(set);
+    resource
+}
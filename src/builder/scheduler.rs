@@ -0,0 +1,272 @@
+use proto::mesos as pb;
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+
+type RegisteredFn<'a> =
+    Box<Fn(&SchedulerDriver, &pb::FrameworkID, &pb::MasterInfo) + 'a>;
+type ReregisteredFn<'a> = Box<Fn(&SchedulerDriver, &pb::MasterInfo) + 'a>;
+type OffersFn<'a> = Box<Fn(&SchedulerDriver, Vec<pb::Offer>) + 'a>;
+type UpdateFn<'a> = Box<Fn(&SchedulerDriver, &pb::TaskStatus) + 'a>;
+type DisconnectedFn<'a> = Box<Fn(&SchedulerDriver) + 'a>;
+type RescindedFn<'a> = Box<Fn(&SchedulerDriver, &pb::OfferID) + 'a>;
+type SlaveLostFn<'a> = Box<Fn(&SchedulerDriver, &pb::SlaveID) + 'a>;
+type ExecutorLostFn<'a> =
+    Box<Fn(&SchedulerDriver, &pb::ExecutorID, &pb::SlaveID, i32) + 'a>;
+type MessageFn<'a> =
+    Box<Fn(&SchedulerDriver, &pb::ExecutorID, &pb::SlaveID, &String) + 'a>;
+type ErrorFn<'a> = Box<Fn(&SchedulerDriver, &String) + 'a>;
+type DecodeErrorFn<'a> = Box<Fn(&SchedulerDriver, &DecodeError) + 'a>;
+
+/// Builds a `Scheduler` out of closures, one per callback.  Callbacks
+/// without a closure fall back to the logging defaults of the `Scheduler`
+/// trait, e.g.
+///
+/// ```ignore
+/// let scheduler = SchedulerBuilder::new()
+///     .on_offers(|driver, offers| for offer in offers {
+///         driver.decline_offer(offer.get_id(), &pb::Filters::new());
+///     })
+///     .on_update(|_, status| println!("{:?}", status.get_state()))
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct SchedulerBuilder<'a> {
+    scheduler: ClosureScheduler<'a>,
+}
+
+impl<'a> SchedulerBuilder<'a> {
+    pub fn new() -> SchedulerBuilder<'a> {
+        SchedulerBuilder::default()
+    }
+
+    pub fn on_registered<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::FrameworkID, &pb::MasterInfo) + 'a
+    {
+        self.scheduler.registered = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_reregistered<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::MasterInfo) + 'a
+    {
+        self.scheduler.reregistered = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_offers<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, Vec<pb::Offer>) + 'a
+    {
+        self.scheduler.offers = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_update<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::TaskStatus) + 'a
+    {
+        self.scheduler.update = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_disconnected<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver) + 'a
+    {
+        self.scheduler.disconnected = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_rescinded<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::OfferID) + 'a
+    {
+        self.scheduler.rescinded = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_slave_lost<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::SlaveID) + 'a
+    {
+        self.scheduler.slave_lost = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_executor_lost<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::ExecutorID, &pb::SlaveID, i32) + 'a
+    {
+        self.scheduler.executor_lost = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_message<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &pb::ExecutorID, &pb::SlaveID, &String)
+                 + 'a
+    {
+        self.scheduler.message = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_error<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &String) + 'a
+    {
+        self.scheduler.error = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_decode_error<F>(mut self, f: F) -> Self
+        where F: Fn(&SchedulerDriver, &DecodeError) + 'a
+    {
+        self.scheduler.decode_error = Some(Box::new(f));
+        self
+    }
+
+    pub fn build(self) -> ClosureScheduler<'a> {
+        self.scheduler
+    }
+}
+
+/// Provides the trait's default callbacks to `ClosureScheduler`.
+struct Defaults;
+
+impl Scheduler for Defaults {}
+
+/// A `Scheduler` built by `SchedulerBuilder`.
+#[derive(Default)]
+pub struct ClosureScheduler<'a> {
+    registered: Option<RegisteredFn<'a>>,
+    reregistered: Option<ReregisteredFn<'a>>,
+    offers: Option<OffersFn<'a>>,
+    update: Option<UpdateFn<'a>>,
+    disconnected: Option<DisconnectedFn<'a>>,
+    rescinded: Option<RescindedFn<'a>>,
+    slave_lost: Option<SlaveLostFn<'a>>,
+    executor_lost: Option<ExecutorLostFn<'a>>,
+    message: Option<MessageFn<'a>>,
+    error: Option<ErrorFn<'a>>,
+    decode_error: Option<DecodeErrorFn<'a>>,
+}
+
+impl<'a> Scheduler for ClosureScheduler<'a> {
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        master_info: &pb::MasterInfo) {
+
+        match self.registered {
+            Some(ref f) => f(driver, framework_id, master_info),
+            None => Defaults.registered(driver, framework_id, master_info),
+        }
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) {
+
+        match self.reregistered {
+            Some(ref f) => f(driver, master_info),
+            None => Defaults.reregistered(driver, master_info),
+        }
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        match self.offers {
+            Some(ref f) => f(driver, offers),
+            None => Defaults.resource_offers(driver, offers),
+        }
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        match self.update {
+            Some(ref f) => f(driver, task_status),
+            None => Defaults.status_update(driver, task_status),
+        }
+    }
+
+    fn disconnected(
+        &self,
+        driver: &SchedulerDriver) {
+
+        match self.disconnected {
+            Some(ref f) => f(driver),
+            None => Defaults.disconnected(driver),
+        }
+    }
+
+    fn offer_rescinded(
+        &self,
+        driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) {
+
+        match self.rescinded {
+            Some(ref f) => f(driver, offer_id),
+            None => Defaults.offer_rescinded(driver, offer_id),
+        }
+    }
+
+    fn slave_lost(
+        &self,
+        driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) {
+
+        match self.slave_lost {
+            Some(ref f) => f(driver, slave_id),
+            None => Defaults.slave_lost(driver, slave_id),
+        }
+    }
+
+    fn executor_lost(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        status: i32) {
+
+        match self.executor_lost {
+            Some(ref f) => f(driver, executor_id, slave_id, status),
+            None => Defaults.executor_lost(
+                driver, executor_id, slave_id, status),
+        }
+    }
+
+    fn framework_message(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &String) {
+
+        match self.message {
+            Some(ref f) => f(driver, executor_id, slave_id, data),
+            None => Defaults.framework_message(
+                driver, executor_id, slave_id, data),
+        }
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        match self.error {
+            Some(ref f) => f(driver, message),
+            None => Defaults.error(driver, message),
+        }
+    }
+
+    fn decode_error(
+        &self,
+        driver: &SchedulerDriver,
+        error: &DecodeError) {
+
+        match self.decode_error {
+            Some(ref f) => f(driver, error),
+            None => Defaults.decode_error(driver, error),
+        }
+    }
+}
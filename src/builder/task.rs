@@ -0,0 +1,131 @@
+use builder::resource;
+use builder::validation::{self, ValidationError};
+use proto::mesos as pb;
+
+/// Fluent builder for `TaskInfo`.
+///
+/// `build` checks the same invariants as the Mesos master, so mistakes such
+/// as setting both a command and an executor, or forgetting the slave id,
+/// are reported before the task is launched.
+///
+/// ```ignore
+/// let task = TaskInfoBuilder::new("web", "web-1")
+///     .offer(&offer)
+///     .cpus(0.5)
+///     .mem(128.0)
+///     .command(CommandInfoBuilder::shell("python -m SimpleHTTPServer")
+///         .build()
+///         .unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct TaskInfoBuilder {
+    task: pb::TaskInfo,
+}
+
+impl TaskInfoBuilder {
+
+    pub fn new(name: &str, task_id: &str) -> TaskInfoBuilder {
+        let mut id = pb::TaskID::new();
+        id.set_value(task_id.to_string());
+
+        let mut task = pb::TaskInfo::new();
+        task.set_name(name.to_string());
+        task.set_task_id(id);
+        TaskInfoBuilder { task: task }
+    }
+
+    pub fn slave_id(mut self, slave_id: &pb::SlaveID) -> TaskInfoBuilder {
+        self.task.set_slave_id(slave_id.clone());
+        self
+    }
+
+    /// Targets the slave that `offer` was made for.  Resources still have
+    /// to be added explicitly.
+    pub fn offer(self, offer: &pb::Offer) -> TaskInfoBuilder {
+        self.slave_id(offer.get_slave_id())
+    }
+
+    pub fn resource(mut self, resource: pb::Resource) -> TaskInfoBuilder {
+        self.task.mut_resources().push(resource);
+        self
+    }
+
+    pub fn resources(mut self, resources: &[pb::Resource]) -> TaskInfoBuilder {
+        for resource in resources {
+            self.task.mut_resources().push(resource.clone());
+        }
+        self
+    }
+
+    pub fn cpus(self, cpus: f64) -> TaskInfoBuilder {
+        self.resource(resource::scalar("cpus", cpus))
+    }
+
+    pub fn mem(self, megabytes: f64) -> TaskInfoBuilder {
+        self.resource(resource::scalar("mem", megabytes))
+    }
+
+    pub fn disk(self, megabytes: f64) -> TaskInfoBuilder {
+        self.resource(resource::scalar("disk", megabytes))
+    }
+
+    /// Runs the task with the built-in command executor.  Mutually
+    /// exclusive with `executor`.
+    pub fn command(mut self, command: pb::CommandInfo) -> TaskInfoBuilder {
+        self.task.set_command(command);
+        self
+    }
+
+    /// Runs the task with a custom executor.  Mutually exclusive with
+    /// `command`.
+    pub fn executor(mut self, executor: pb::ExecutorInfo) -> TaskInfoBuilder {
+        self.task.set_executor(executor);
+        self
+    }
+
+    pub fn container(
+        mut self,
+        container: pb::ContainerInfo
+    ) -> TaskInfoBuilder {
+        self.task.set_container(container);
+        self
+    }
+
+    pub fn health_check(
+        mut self,
+        health_check: pb::HealthCheck
+    ) -> TaskInfoBuilder {
+        self.task.set_health_check(health_check);
+        self
+    }
+
+    pub fn discovery(
+        mut self,
+        discovery: pb::DiscoveryInfo
+    ) -> TaskInfoBuilder {
+        self.task.set_discovery(discovery);
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> TaskInfoBuilder {
+        let mut label = pb::Label::new();
+        label.set_key(key.to_string());
+        label.set_value(value.to_string());
+        self.task.mut_labels().mut_labels().push(label);
+        self
+    }
+
+    /// Sets opaque data passed to the executor with the task.
+    pub fn data(mut self, data: Vec<u8>) -> TaskInfoBuilder {
+        self.task.set_data(data);
+        self
+    }
+
+    /// Validates and returns the `TaskInfo`.
+    pub fn build(self) -> Result<pb::TaskInfo, ValidationError> {
+        try!(validation::task_info(&self.task));
+        Ok(self.task)
+    }
+}
@@ -0,0 +1,318 @@
+#[cfg(test)]
+mod tests {
+
+    use ::builder::*;
+    use ::builder::resource;
+    use ::proto::mesos as pb;
+    use ::scheduler::{DecodeError, Scheduler};
+    use ::testing::MockSchedulerDriver;
+    use std::cell::RefCell;
+
+    fn slave_id() -> pb::SlaveID {
+        let mut slave_id = pb::SlaveID::new();
+        slave_id.set_value("slave-1".to_string());
+        slave_id
+    }
+
+    fn shell(value: &str) -> pb::CommandInfo {
+        CommandInfoBuilder::shell(value).build().unwrap()
+    }
+
+    #[test]
+    fn task_info_builder_valid() {
+        let task = TaskInfoBuilder::new("task", "task-1")
+            .slave_id(&slave_id())
+            .cpus(0.5)
+            .mem(64.0)
+            .resource(resource::ranges("ports", &[(31000, 31001)]))
+            .command(shell("sleep 10"))
+            .label("team", "infra")
+            .build()
+            .unwrap();
+
+        assert_eq!(task.get_name(), "task");
+        assert_eq!(task.get_task_id().get_value(), "task-1");
+        assert_eq!(task.get_slave_id(), &slave_id());
+        assert_eq!(task.get_resources().len(), 3);
+        assert_eq!(task.get_command().get_value(), "sleep 10");
+        assert_eq!(task.get_labels().get_labels()[0].get_key(), "team");
+    }
+
+    #[test]
+    fn task_info_builder_reports_every_violation() {
+        let executor = pb::ExecutorInfo::new();
+        let error = TaskInfoBuilder::new("task", "a/b")
+            .command(shell("true"))
+            .executor(executor)
+            .build()
+            .unwrap_err();
+
+        let violations = error.violations;
+        assert!(violations.contains(&Violation::Missing {
+            field: "slave_id".to_string(),
+        }));
+        assert!(violations.contains(&Violation::Conflict {
+            field: "command".to_string(),
+            other: "executor".to_string(),
+        }));
+        assert!(violations.contains(&Violation::Invalid {
+            field: "task_id".to_string(),
+            reason: "must not contain path separators".to_string(),
+        }));
+        assert!(violations.contains(&Violation::Invalid {
+            field: "resources".to_string(),
+            reason: "task uses no resources".to_string(),
+        }));
+        assert!(violations.contains(&Violation::Missing {
+            field: "executor.executor_id".to_string(),
+        }));
+    }
+
+    #[test]
+    fn task_info_requires_command_or_executor() {
+        let error = TaskInfoBuilder::new("task", "task-1")
+            .slave_id(&slave_id())
+            .cpus(1.0)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(error.violations, vec![Violation::Missing {
+            field: "command or executor".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn task_info_rejects_negative_scalar() {
+        let error = TaskInfoBuilder::new("task", "task-1")
+            .slave_id(&slave_id())
+            .cpus(-1.0)
+            .command(shell("true"))
+            .build()
+            .unwrap_err();
+
+        assert_eq!(error.violations, vec![Violation::Invalid {
+            field: "resources[0].scalar".to_string(),
+            reason: "must be a non-negative number".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn command_info_builder() {
+        let command = CommandInfoBuilder::exec("/bin/echo", &["echo", "hi"])
+            .env("FOO", "bar")
+            .uri("http://example.com/a.tgz", false, true)
+            .user("nobody")
+            .build()
+            .unwrap();
+
+        assert!(!command.get_shell());
+        assert_eq!(command.get_arguments().len(), 2);
+        assert_eq!(command.get_environment().get_variables().len(), 1);
+        assert!(command.get_uris()[0].get_extract());
+
+        let error = CommandInfoBuilder::shell("")
+            .arg("ignored")
+            .build()
+            .unwrap_err();
+        assert_eq!(error.violations.len(), 2);
+    }
+
+    #[test]
+    fn executor_info_builder() {
+        let executor = ExecutorInfoBuilder::new("exec-1", shell("./executor"))
+            .name("my executor")
+            .resource(resource::scalar("cpus", 0.1))
+            .build()
+            .unwrap();
+
+        assert_eq!(executor.get_executor_id().get_value(), "exec-1");
+
+        let task = TaskInfoBuilder::new("task", "task-1")
+            .slave_id(&slave_id())
+            .cpus(1.0)
+            .executor(executor)
+            .build();
+        assert!(task.is_ok());
+    }
+
+    #[test]
+    fn container_info_builder_docker() {
+        let volume = VolumeBuilder::new("/data", pb::Volume_Mode::RW)
+            .host_path("/var/data")
+            .build()
+            .unwrap();
+
+        let container = ContainerInfoBuilder::docker("nginx")
+            .network(pb::ContainerInfo_DockerInfo_Network::BRIDGE)
+            .port_mapping(31000, 80, "tcp")
+            .volume(volume)
+            .build()
+            .unwrap();
+
+        assert_eq!(container.get_field_type(),
+                   pb::ContainerInfo_Type::DOCKER);
+        assert_eq!(container.get_docker().get_port_mappings().len(), 1);
+
+        let error = ContainerInfoBuilder::docker("")
+            .network(pb::ContainerInfo_DockerInfo_Network::HOST)
+            .port_mapping(0, 80, "sctp")
+            .build()
+            .unwrap_err();
+
+        assert_eq!(error.violations, vec![
+            Violation::Missing {
+                field: "docker.image".to_string(),
+            },
+            Violation::Conflict {
+                field: "docker.port_mappings".to_string(),
+                other: "docker.network".to_string(),
+            },
+            Violation::Invalid {
+                field: "docker.port_mappings[0].host_port".to_string(),
+                reason: "must be a port number in 1-65535".to_string(),
+            },
+            Violation::Invalid {
+                field: "docker.port_mappings[0].protocol".to_string(),
+                reason: "must be 'tcp' or 'udp'".to_string(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn container_info_builder_mesos() {
+        let mut image = pb::Image::new();
+        image.set_field_type(pb::Image_Type::DOCKER);
+        image.mut_docker().set_name("busybox".to_string());
+
+        let container = ContainerInfoBuilder::mesos()
+            .image(image)
+            .build()
+            .unwrap();
+        assert_eq!(container.get_field_type(),
+                   pb::ContainerInfo_Type::MESOS);
+
+        let mut container = container.clone();
+        container.mut_docker().set_image("nginx".to_string());
+        let error = validation::container_info(&container).unwrap_err();
+        assert_eq!(error.violations, vec![Violation::Conflict {
+            field: "docker".to_string(),
+            other: "type".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn volume_builder_rejects_host_path_and_image() {
+        let mut image = pb::Image::new();
+        image.set_field_type(pb::Image_Type::DOCKER);
+        image.mut_docker().set_name("busybox".to_string());
+
+        let error = VolumeBuilder::new("", pb::Volume_Mode::RO)
+            .host_path("/tmp")
+            .image(image)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.violations.len(), 2);
+    }
+
+    #[test]
+    fn health_check_builder() {
+        let check = HealthCheckBuilder::http(8080, "/health")
+            .status(200)
+            .interval_seconds(5.0)
+            .consecutive_failures(3)
+            .build()
+            .unwrap();
+        assert_eq!(check.get_http().get_port(), 8080);
+
+        let error = HealthCheckBuilder::http(8080, "health")
+            .status(42)
+            .timeout_seconds(-1.0)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.violations.len(), 3);
+
+        let mut both = HealthCheckBuilder::command(shell("true"))
+            .build()
+            .unwrap();
+        both.mut_http().set_port(80);
+        assert!(validation::health_check(&both).is_err());
+    }
+
+    #[test]
+    fn discovery_info_builder() {
+        let discovery = DiscoveryInfoBuilder::new(
+                pb::DiscoveryInfo_Visibility::CLUSTER)
+            .name("web")
+            .port(8080, Some("http"), "tcp")
+            .label("canary", "false")
+            .build()
+            .unwrap();
+        assert_eq!(discovery.get_ports().get_ports().len(), 1);
+
+        let error = DiscoveryInfoBuilder::new(
+                pb::DiscoveryInfo_Visibility::FRAMEWORK)
+            .port(70000, None, "tcp")
+            .build()
+            .unwrap_err();
+        assert_eq!(error.violations.len(), 1);
+    }
+
+    #[test]
+    fn validation_error_display() {
+        let error = ValidationError {
+            violations: vec![
+                Violation::Missing { field: "slave_id".to_string() },
+                Violation::Conflict {
+                    field: "command".to_string(),
+                    other: "executor".to_string(),
+                },
+            ],
+        };
+        assert_eq!(format!("{}", error),
+                   "2 violation(s): 'slave_id' is required; 'command' may \
+                    not be set together with 'executor'");
+    }
+
+    #[test]
+    fn scheduler_builder_dispatches_to_closures() {
+        let updates = RefCell::new(vec![]);
+        let decode_errors = RefCell::new(vec![]);
+        let scheduler = SchedulerBuilder::new()
+            .on_offers(|driver, offers| for offer in offers {
+                driver.decline_offer(offer.get_id(), &pb::Filters::new());
+            })
+            .on_update(|_, status| {
+                updates.borrow_mut().push(status.get_state());
+            })
+            .on_decode_error(|_, error| {
+                decode_errors.borrow_mut().push(error.clone());
+            })
+            .build();
+
+        let driver = MockSchedulerDriver::new();
+
+        let mut offer = pb::Offer::new();
+        offer.mut_id().set_value("offer-1".to_string());
+        scheduler.resource_offers(&driver, vec![offer]);
+
+        let mut status = pb::TaskStatus::new();
+        status.set_state(pb::TaskState::TASK_RUNNING);
+        scheduler.status_update(&driver, &status);
+
+        let error = DecodeError {
+            message_type: "Offer".to_string(),
+            bytes: vec![0xff],
+            reason: "truncated".to_string(),
+        };
+        scheduler.decode_error(&driver, &error);
+
+        // Callbacks without a closure fall back to the defaults.
+        scheduler.disconnected(&driver);
+        scheduler.error(&driver, &"boom".to_string());
+
+        assert_eq!(driver.declined_offers()[0].get_value(), "offer-1");
+        assert_eq!(driver.declined_offers().len(), 1);
+        assert_eq!(*updates.borrow(), vec![pb::TaskState::TASK_RUNNING]);
+        assert_eq!(*decode_errors.borrow(), vec![error]);
+    }
+}
@@ -0,0 +1,609 @@
+//! Validation of Mesos protobuf messages.
+//!
+//! These checks mirror the invariants enforced by the Mesos master when it
+//! receives a task launch.  Running them locally means that a malformed
+//! task is reported to the framework immediately, with every problem
+//! listed, rather than as a single `TASK_ERROR` status update later on.
+
+use proto::mesos as pb;
+use std::error::Error;
+use std::fmt;
+
+/// A single invariant that a message failed to satisfy.  The `field`
+/// members hold dotted paths relative to the validated message, e.g.
+/// `container.docker.image`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// A required field was not set.
+    Missing { field: String },
+
+    /// Two fields were set that may not be set together.
+    Conflict { field: String, other: String },
+
+    /// A field was set to a value that Mesos rejects.
+    Invalid { field: String, reason: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Missing { ref field } =>
+                write!(f, "'{}' is required", field),
+            Violation::Conflict { ref field, ref other } =>
+                write!(f, "'{}' may not be set together with '{}'",
+                       field, other),
+            Violation::Invalid { ref field, ref reason } =>
+                write!(f, "'{}' is invalid: {}", field, reason),
+        }
+    }
+}
+
+/// The error returned by the builders and validation functions.  It
+/// carries every violation found, not just the first one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationError {
+    fn from_violations(
+        violations: Vec<Violation>
+    ) -> Result<(), ValidationError> {
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { violations: violations })
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} violation(s): ", self.violations.len()));
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                try!(write!(f, "; "));
+            }
+            try!(write!(f, "{}", violation));
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Validates a `TaskInfo`, including any nested command, executor,
+/// container, health check and discovery information.
+pub fn task_info(task: &pb::TaskInfo) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_task_info(task, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates a `CommandInfo`.
+pub fn command_info(command: &pb::CommandInfo) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_command_info(command, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates an `ExecutorInfo`.
+pub fn executor_info(
+    executor: &pb::ExecutorInfo
+) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_executor_info(executor, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates a `ContainerInfo` of either the Docker or the Mesos type.
+pub fn container_info(
+    container: &pb::ContainerInfo
+) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_container_info(container, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates a `HealthCheck`.
+pub fn health_check(check: &pb::HealthCheck) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_health_check(check, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates a `Volume`.
+pub fn volume(volume: &pb::Volume) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_volume(volume, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+/// Validates a `DiscoveryInfo`.
+pub fn discovery_info(
+    discovery: &pb::DiscoveryInfo
+) -> Result<(), ValidationError> {
+    let mut violations = vec![];
+    check_discovery_info(discovery, "", &mut violations);
+    ValidationError::from_violations(violations)
+}
+
+// Joins a parent path and a field name into a dotted path.
+fn path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", prefix, field)
+    }
+}
+
+fn missing(prefix: &str, field: &str, violations: &mut Vec<Violation>) {
+    violations.push(Violation::Missing { field: path(prefix, field) });
+}
+
+fn conflict(
+    prefix: &str,
+    field: &str,
+    other: &str,
+    violations: &mut Vec<Violation>
+) {
+    violations.push(Violation::Conflict {
+        field: path(prefix, field),
+        other: path(prefix, other),
+    });
+}
+
+fn invalid(
+    prefix: &str,
+    field: &str,
+    reason: &str,
+    violations: &mut Vec<Violation>
+) {
+    violations.push(Violation::Invalid {
+        field: path(prefix, field),
+        reason: reason.to_string(),
+    });
+}
+
+// Mesos rejects identifiers that are empty, that consist only of dots or
+// that contain path separators, since they are used to build sandbox paths
+// on the slave.
+fn check_id(
+    value: &str,
+    prefix: &str,
+    field: &str,
+    violations: &mut Vec<Violation>
+) {
+    if value.is_empty() {
+        invalid(prefix, field, "must not be empty", violations);
+    } else if value == "." || value == ".." {
+        invalid(prefix, field, "must not be '.' or '..'", violations);
+    } else if value.contains('/') || value.contains('\\') {
+        invalid(prefix, field, "must not contain path separators",
+                violations);
+    } else if value.chars().any(|c| c.is_control()) {
+        invalid(prefix, field, "must not contain control characters",
+                violations);
+    }
+}
+
+fn check_task_info(
+    task: &pb::TaskInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if !task.has_name() {
+        missing(prefix, "name", violations);
+    }
+
+    if !task.has_task_id() {
+        missing(prefix, "task_id", violations);
+    } else {
+        check_id(task.get_task_id().get_value(),
+                 prefix, "task_id", violations);
+    }
+
+    if !task.has_slave_id() {
+        missing(prefix, "slave_id", violations);
+    } else if task.get_slave_id().get_value().is_empty() {
+        invalid(prefix, "slave_id", "must not be empty", violations);
+    }
+
+    if task.has_command() && task.has_executor() {
+        conflict(prefix, "command", "executor", violations);
+    } else if !task.has_command() && !task.has_executor() {
+        violations.push(Violation::Missing {
+            field: format!("{} or {}",
+                           path(prefix, "command"),
+                           path(prefix, "executor")),
+        });
+    }
+
+    if task.get_resources().is_empty() {
+        invalid(prefix, "resources", "task uses no resources", violations);
+    }
+    check_resources(task.get_resources(),
+                    &path(prefix, "resources"),
+                    violations);
+
+    if task.has_command() {
+        check_command_info(task.get_command(),
+                           &path(prefix, "command"),
+                           violations);
+    }
+
+    if task.has_executor() {
+        check_executor_info(task.get_executor(),
+                            &path(prefix, "executor"),
+                            violations);
+    }
+
+    if task.has_container() {
+        check_container_info(task.get_container(),
+                             &path(prefix, "container"),
+                             violations);
+    }
+
+    if task.has_health_check() {
+        check_health_check(task.get_health_check(),
+                           &path(prefix, "health_check"),
+                           violations);
+    }
+
+    if task.has_discovery() {
+        check_discovery_info(task.get_discovery(),
+                             &path(prefix, "discovery"),
+                             violations);
+    }
+}
+
+fn check_resources(
+    resources: &[pb::Resource],
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    for (i, resource) in resources.iter().enumerate() {
+        let prefix = format!("{}[{}]", prefix, i);
+
+        if resource.get_name().is_empty() {
+            missing(&prefix, "name", violations);
+        }
+
+        match resource.get_field_type() {
+            pb::Value_Type::SCALAR => {
+                if !resource.has_scalar() {
+                    missing(&prefix, "scalar", violations);
+                } else {
+                    let value = resource.get_scalar().get_value();
+                    if value < 0.0 || value.is_nan() {
+                        invalid(&prefix, "scalar",
+                                "must be a non-negative number",
+                                violations);
+                    }
+                }
+            },
+            pb::Value_Type::RANGES => {
+                if !resource.has_ranges() {
+                    missing(&prefix, "ranges", violations);
+                } else {
+                    for range in resource.get_ranges().get_range() {
+                        if range.get_begin() > range.get_end() {
+                            invalid(&prefix, "ranges",
+                                    "range begin exceeds range end",
+                                    violations);
+                        }
+                    }
+                }
+            },
+            pb::Value_Type::SET => {
+                if !resource.has_set() {
+                    missing(&prefix, "set", violations);
+                }
+            },
+            pb::Value_Type::TEXT => {
+                invalid(&prefix, "type",
+                        "TEXT is not a valid resource type",
+                        violations);
+            },
+        }
+    }
+}
+
+fn check_command_info(
+    command: &pb::CommandInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    // `value` is the shell command when `shell` is true (the default) and
+    // the path of the executable otherwise; it is needed in both cases.
+    if !command.has_value() || command.get_value().is_empty() {
+        missing(prefix, "value", violations);
+    }
+
+    if command.get_shell() && !command.get_arguments().is_empty() {
+        conflict(prefix, "arguments", "shell", violations);
+    }
+
+    for (i, uri) in command.get_uris().iter().enumerate() {
+        if uri.get_value().is_empty() {
+            missing(&format!("{}[{}]", path(prefix, "uris"), i),
+                    "value", violations);
+        }
+    }
+
+    if command.has_environment() {
+        let variables = command.get_environment().get_variables();
+        for (i, variable) in variables.iter().enumerate() {
+            if variable.get_name().is_empty() {
+                missing(&format!("{}[{}]",
+                                 path(prefix, "environment.variables"), i),
+                        "name", violations);
+            }
+        }
+    }
+}
+
+fn check_executor_info(
+    executor: &pb::ExecutorInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if !executor.has_executor_id() {
+        missing(prefix, "executor_id", violations);
+    } else {
+        check_id(executor.get_executor_id().get_value(),
+                 prefix, "executor_id", violations);
+    }
+
+    if !executor.has_command() {
+        missing(prefix, "command", violations);
+    } else {
+        check_command_info(executor.get_command(),
+                           &path(prefix, "command"),
+                           violations);
+    }
+
+    check_resources(executor.get_resources(),
+                    &path(prefix, "resources"),
+                    violations);
+
+    if executor.has_container() {
+        check_container_info(executor.get_container(),
+                             &path(prefix, "container"),
+                             violations);
+    }
+
+    if executor.has_discovery() {
+        check_discovery_info(executor.get_discovery(),
+                             &path(prefix, "discovery"),
+                             violations);
+    }
+}
+
+fn check_container_info(
+    container: &pb::ContainerInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if !container.has_field_type() {
+        missing(prefix, "type", violations);
+    }
+
+    match container.get_field_type() {
+        pb::ContainerInfo_Type::DOCKER => {
+            if container.has_mesos() {
+                conflict(prefix, "mesos", "type", violations);
+            }
+            if !container.has_docker() {
+                missing(prefix, "docker", violations);
+            } else {
+                check_docker_info(container.get_docker(),
+                                  &path(prefix, "docker"),
+                                  violations);
+            }
+        },
+        pb::ContainerInfo_Type::MESOS => {
+            if container.has_docker() {
+                conflict(prefix, "docker", "type", violations);
+            }
+            if container.has_mesos() && container.get_mesos().has_image() {
+                check_image(container.get_mesos().get_image(),
+                            &path(prefix, "mesos.image"),
+                            violations);
+            }
+        },
+    }
+
+    for (i, volume) in container.get_volumes().iter().enumerate() {
+        check_volume(volume,
+                     &format!("{}[{}]", path(prefix, "volumes"), i),
+                     violations);
+    }
+}
+
+fn check_docker_info(
+    docker: &pb::ContainerInfo_DockerInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if docker.get_image().is_empty() {
+        missing(prefix, "image", violations);
+    }
+
+    // Port mappings are only honoured by Docker in bridged networking mode.
+    if !docker.get_port_mappings().is_empty() &&
+        docker.get_network() != pb::ContainerInfo_DockerInfo_Network::BRIDGE {
+        conflict(prefix, "port_mappings", "network", violations);
+    }
+
+    for (i, mapping) in docker.get_port_mappings().iter().enumerate() {
+        let prefix = format!("{}[{}]", path(prefix, "port_mappings"), i);
+        check_port(mapping.get_host_port(), &prefix, "host_port", violations);
+        check_port(mapping.get_container_port(),
+                   &prefix, "container_port", violations);
+        if mapping.has_protocol() {
+            check_protocol(mapping.get_protocol(),
+                           &prefix, "protocol", violations);
+        }
+    }
+
+    for (i, parameter) in docker.get_parameters().iter().enumerate() {
+        if parameter.get_key().is_empty() {
+            missing(&format!("{}[{}]", path(prefix, "parameters"), i),
+                    "key", violations);
+        }
+    }
+}
+
+fn check_image(
+    image: &pb::Image,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    match image.get_field_type() {
+        pb::Image_Type::APPC => {
+            if !image.has_appc() || image.get_appc().get_name().is_empty() {
+                missing(prefix, "appc.name", violations);
+            }
+            if image.has_docker() {
+                conflict(prefix, "docker", "type", violations);
+            }
+        },
+        pb::Image_Type::DOCKER => {
+            if !image.has_docker() ||
+                image.get_docker().get_name().is_empty() {
+                missing(prefix, "docker.name", violations);
+            }
+            if image.has_appc() {
+                conflict(prefix, "appc", "type", violations);
+            }
+        },
+    }
+}
+
+fn check_volume(
+    volume: &pb::Volume,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if !volume.has_mode() {
+        missing(prefix, "mode", violations);
+    }
+
+    if volume.get_container_path().is_empty() {
+        missing(prefix, "container_path", violations);
+    }
+
+    if volume.has_host_path() && volume.has_image() {
+        conflict(prefix, "host_path", "image", violations);
+    }
+
+    if volume.has_image() {
+        check_image(volume.get_image(), &path(prefix, "image"), violations);
+    }
+}
+
+fn check_health_check(
+    check: &pb::HealthCheck,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if check.has_command() && check.has_http() {
+        conflict(prefix, "command", "http", violations);
+    } else if !check.has_command() && !check.has_http() {
+        violations.push(Violation::Missing {
+            field: format!("{} or {}",
+                           path(prefix, "command"),
+                           path(prefix, "http")),
+        });
+    }
+
+    if check.has_command() {
+        check_command_info(check.get_command(),
+                           &path(prefix, "command"),
+                           violations);
+    }
+
+    if check.has_http() {
+        let http = check.get_http();
+        let prefix = path(prefix, "http");
+        check_port(http.get_port(), &prefix, "port", violations);
+        if http.has_path() && !http.get_path().starts_with('/') {
+            invalid(&prefix, "path", "must start with '/'", violations);
+        }
+        for status in http.get_statuses() {
+            if *status < 100 || *status > 599 {
+                invalid(&prefix, "statuses",
+                        "must be valid HTTP status codes", violations);
+                break;
+            }
+        }
+    }
+
+    let durations = [
+        ("delay_seconds", check.get_delay_seconds()),
+        ("interval_seconds", check.get_interval_seconds()),
+        ("timeout_seconds", check.get_timeout_seconds()),
+        ("grace_period_seconds", check.get_grace_period_seconds()),
+    ];
+    for &(field, value) in durations.iter() {
+        if value < 0.0 || value.is_nan() {
+            invalid(prefix, field, "must be a non-negative duration",
+                    violations);
+        }
+    }
+
+    if check.has_interval_seconds() && check.get_interval_seconds() == 0.0 {
+        invalid(prefix, "interval_seconds", "must be positive", violations);
+    }
+
+    // A check that may not take any time always times out.
+    if check.has_timeout_seconds() && check.get_timeout_seconds() == 0.0 {
+        invalid(prefix, "timeout_seconds", "must be positive", violations);
+    }
+}
+
+fn check_discovery_info(
+    discovery: &pb::DiscoveryInfo,
+    prefix: &str,
+    violations: &mut Vec<Violation>
+) {
+    if !discovery.has_visibility() {
+        missing(prefix, "visibility", violations);
+    }
+
+    if discovery.has_ports() {
+        let ports = discovery.get_ports().get_ports();
+        for (i, port) in ports.iter().enumerate() {
+            let prefix = format!("{}[{}]", path(prefix, "ports.ports"), i);
+            check_port(port.get_number(), &prefix, "number", violations);
+            if port.has_protocol() {
+                check_protocol(port.get_protocol(),
+                               &prefix, "protocol", violations);
+            }
+        }
+    }
+}
+
+fn check_port(
+    port: u32,
+    prefix: &str,
+    field: &str,
+    violations: &mut Vec<Violation>
+) {
+    if port == 0 || port > 65535 {
+        invalid(prefix, field, "must be a port number in 1-65535",
+                violations);
+    }
+}
+
+fn check_protocol(
+    protocol: &str,
+    prefix: &str,
+    field: &str,
+    violations: &mut Vec<Violation>
+) {
+    if protocol != "tcp" && protocol != "udp" {
+        invalid(prefix, field, "must be 'tcp' or 'udp'", violations);
+    }
+}
@@ -0,0 +1,177 @@
+//! # Driver calls from any thread.
+//!
+//! The `SchedulerDriver` reference handed to callbacks cannot leave the
+//! driver's callback thread, so frameworks whose logic runs elsewhere send
+//! `SchedulerCall`s through a `CallSender`.  A `CallQueue` performs them
+//! on a driver of its own, typically a thread-safe handle to the driver
+//! that runs the scheduler, as soon as they are sent, whether or not Mesos
+//! has anything to deliver:
+//!
+//! ```ignore
+//! let (calls, queue) = call_queue::new::<mpsc::Sender<i32>>();
+//!
+//! thread::spawn(move || {
+//!     let mut driver = MesosSchedulerDriver::new(
+//!         &scheduler, &framework_info, master);
+//!     queue.spawn(driver.handle());
+//!     driver.run();
+//! });
+//!
+//! calls.revive_offers();
+//! ```
+//!
+//! `async_scheduler` and `channel_scheduler` pair a `CallSender` with a
+//! stream or a channel of the scheduler's callbacks; they only differ in
+//! how the status of each call is returned, which is up to the
+//! `ReplySender` of the queue.
+
+mod tests;
+
+use event::SchedulerCall;
+use proto::mesos as pb;
+use scheduler::SchedulerDriver;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Returns the status of a call to whoever made it.
+pub trait ReplySender: Send + 'static {
+    /// What the caller gets to wait on, e.g. a future.
+    type Reply;
+
+    fn channel() -> (Self, Self::Reply) where Self: Sized;
+
+    /// Sends the status; the caller may have stopped waiting for it.
+    fn send_status(self, status: i32);
+}
+
+impl ReplySender for Sender<i32> {
+    type Reply = Receiver<i32>;
+
+    fn channel() -> (Sender<i32>, Receiver<i32>) {
+        mpsc::channel()
+    }
+
+    fn send_status(self, status: i32) {
+        let _ = self.send(status);
+    }
+}
+
+type PendingCall<S> = (SchedulerCall, S);
+
+/// Returns a sender of driver calls, and the queue performing them.
+pub fn new<S: ReplySender>() -> (CallSender<S>, CallQueue<S>) {
+    let (sender, receiver) = mpsc::channel();
+    (CallSender { calls: sender }, CallQueue { calls: receiver })
+}
+
+/// Performs the calls sent through its `CallSender`s, in order.
+pub struct CallQueue<S: ReplySender> {
+    calls: Receiver<PendingCall<S>>,
+}
+
+impl<S: ReplySender> CallQueue<S> {
+
+    /// Performs calls on `driver` until every `CallSender` is dropped.
+    pub fn serve(self, driver: &SchedulerDriver) {
+        for (call, reply) in self.calls {
+            reply.send_status(call.apply(driver));
+        }
+    }
+
+    /// Performs calls on `driver` from a thread of its own, which returns
+    /// the driver once every `CallSender` is dropped.
+    pub fn spawn<D>(self, driver: D) -> JoinHandle<D>
+        where D: SchedulerDriver + Send + 'static
+    {
+        thread::spawn(move || {
+            self.serve(&driver);
+            driver
+        })
+    }
+}
+
+/// Sends driver calls to a `CallQueue`.  The methods mirror those of
+/// `SchedulerDriver`; if the queue is dropped before a call is performed,
+/// its reply fails.
+pub struct CallSender<S: ReplySender> {
+    calls: Sender<PendingCall<S>>,
+}
+
+impl<S: ReplySender> Clone for CallSender<S> {
+    fn clone(&self) -> CallSender<S> {
+        CallSender { calls: self.calls.clone() }
+    }
+}
+
+impl<S: ReplySender> CallSender<S> {
+
+    /// Sends an arbitrary call.
+    pub fn call(&self, call: SchedulerCall) -> S::Reply {
+        let (sender, reply) = S::channel();
+
+        // If the queue is gone, `sender` is dropped with the failed
+        // message, which fails the reply.
+        let _ = self.calls.send((call, sender));
+
+        reply
+    }
+
+    pub fn stop(&self, failover: bool) -> S::Reply {
+        self.call(SchedulerCall::Stop { failover: failover })
+    }
+
+    pub fn request_resources(&self, requests: &[pb::Request]) -> S::Reply {
+        self.call(SchedulerCall::RequestResources {
+            requests: requests.to_vec(),
+        })
+    }
+
+    pub fn decline_offer(
+        &self,
+        offer_id: &pb::OfferID,
+        filters: &pb::Filters
+    ) -> S::Reply {
+        self.call(SchedulerCall::DeclineOffer {
+            offer_id: offer_id.clone(),
+            filters: filters.clone(),
+        })
+    }
+
+    pub fn launch_tasks(
+        &self,
+        offer_id: &pb::OfferID,
+        tasks: Vec<pb::TaskInfo>,
+        filters: &pb::Filters
+    ) -> S::Reply {
+        self.call(SchedulerCall::LaunchTasks {
+            offer_id: offer_id.clone(),
+            tasks: tasks,
+            filters: filters.clone(),
+        })
+    }
+
+    pub fn revive_offers(&self) -> S::Reply {
+        self.call(SchedulerCall::ReviveOffers)
+    }
+
+    pub fn kill_task(&self, task_id: &pb::TaskID) -> S::Reply {
+        self.call(SchedulerCall::KillTask { task_id: task_id.clone() })
+    }
+
+    pub fn reconcile_tasks(&self, statuses: Vec<pb::TaskStatus>) -> S::Reply {
+        self.call(SchedulerCall::ReconcileTasks { statuses: statuses })
+    }
+
+    pub fn send_framework_message(
+        &self,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: Vec<u8>
+    ) -> S::Reply {
+        self.call(SchedulerCall::SendFrameworkMessage {
+            executor_id: executor_id.clone(),
+            slave_id: slave_id.clone(),
+            data: data,
+        })
+    }
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+
+    use ::call_queue;
+    use ::proto::mesos as pb;
+    use ::testing::{DriverCall, DriverMethod, MockSchedulerDriver};
+    use std::sync::mpsc::Sender;
+
+    #[test]
+    fn calls_are_performed_in_order_without_callbacks() {
+        let (calls, queue) = call_queue::new::<Sender<i32>>();
+        let driver = MockSchedulerDriver::new();
+        driver.return_status_for(DriverMethod::KillTask,
+                                 pb::Status::DRIVER_ABORTED);
+        let served = queue.spawn(driver);
+
+        let mut task_id = pb::TaskID::new();
+        task_id.set_value("t1".to_string());
+        let revive = calls.clone().revive_offers();
+        let kill = calls.kill_task(&task_id);
+
+        assert_eq!(revive.recv(), Ok(pb::Status::DRIVER_RUNNING as i32));
+        assert_eq!(kill.recv(), Ok(pb::Status::DRIVER_ABORTED as i32));
+
+        drop(calls);
+        let driver = served.join().unwrap();
+        assert_eq!(driver.calls(), vec![DriverCall::ReviveOffers,
+                                        DriverCall::KillTask(task_id)]);
+    }
+
+    #[test]
+    fn replies_fail_once_the_queue_is_gone() {
+        let (calls, queue) = call_queue::new::<Sender<i32>>();
+        drop(queue);
+
+        assert!(calls.revive_offers().recv().is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! # Channel-based scheduler API.
+//!
+//! A thread-based alternative to `async_scheduler` that needs nothing
+//! beyond `std`.  Every scheduler callback is forwarded as an owned
+//! `SchedulerEvent` into a `std::sync::mpsc` channel, so the framework
+//! logic can run as an event loop on a thread of its own, and driver calls
+//! are made through a cloneable `SchedulerCommands` handle, e.g.
+//!
+//! ```ignore
+//! let (scheduler, events, commands, calls) = channel_scheduler::new();
+//!
+//! thread::spawn(move || {
+//!     let mut driver = MesosSchedulerDriver::new(
+//!         &scheduler, &framework_info, master);
+//!     calls.spawn(driver.handle());
+//!     driver.run();
+//! });
+//!
+//! loop {
+//!     match events.recv_timeout(Duration::from_secs(1)) {
+//!         Ok(SchedulerEvent::Offers(offers)) => for offer in offers {
+//!             commands.decline_offer(offer.get_id(), &pb::Filters::new());
+//!         },
+//!         Ok(_) => {},
+//!         Err(RecvTimeoutError::Timeout) => { /* timers, other work */ },
+//!         Err(RecvTimeoutError::Disconnected) => break,
+//!     }
+//! }
+//! ```
+//!
+//! Commands are performed by the `CallQueue`, on a thread of its own, as
+//! soon as they are sent (see `call_queue`).  Each returns a `Receiver` of
+//! the driver status, which can be waited on or simply dropped.
+
+mod tests;
+
+use call_queue::{self, CallQueue, CallSender};
+use event::{EventHandler, EventScheduler, SchedulerEvent};
+use scheduler::SchedulerDriver;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Returns a `Scheduler` to be run by a `SchedulerDriver`, the receiving end
+/// of the events it forwards, a handle for issuing driver calls, and the
+/// queue that performs them.
+pub fn new() -> (ChannelScheduler, Receiver<SchedulerEvent>,
+                 SchedulerCommands, CallQueue<Sender<i32>>) {
+    let (event_sender, event_receiver) = mpsc::channel();
+    let (commands, calls) = call_queue::new();
+
+    (EventScheduler::new(Forwarder { events: Mutex::new(event_sender) }),
+     event_receiver,
+     commands,
+     calls)
+}
+
+/// The `Scheduler` half of the adapter; pass it to a `SchedulerDriver`.
+pub type ChannelScheduler = EventScheduler<Forwarder>;
+
+/// Forwards events to the channel.
+pub struct Forwarder {
+    events: Mutex<Sender<SchedulerEvent>>,
+}
+
+impl EventHandler for Forwarder {
+    fn handle(&self, _driver: &SchedulerDriver, event: SchedulerEvent) {
+        // If the receiver has been dropped nobody is interested in events
+        // any more.
+        let _ = self.events.lock().unwrap().send(event);
+    }
+}
+
+/// Issues driver calls on behalf of a `ChannelScheduler`; each returns a
+/// `Receiver` of the driver status.
+pub type SchedulerCommands = CallSender<Sender<i32>>;
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+
+    use ::channel_scheduler;
+    use ::event::SchedulerEvent;
+    use ::proto::mesos as pb;
+    use ::scheduler::{DecodeError, Scheduler};
+    use ::testing::MockSchedulerDriver;
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::time::Duration;
+
+    #[test]
+    fn commands_sent_after_the_last_callback_are_performed() {
+        let (scheduler, events, commands, calls) = channel_scheduler::new();
+        let served = calls.spawn(MockSchedulerDriver::new());
+
+        scheduler.disconnected(&MockSchedulerDriver::new());
+        assert_eq!(events.recv(), Ok(SchedulerEvent::Disconnected));
+        assert_eq!(events.recv_timeout(Duration::from_millis(10)),
+                   Err(RecvTimeoutError::Timeout));
+
+        let stop = commands.stop(true);
+        assert_eq!(stop.recv(), Ok(pb::Status::DRIVER_RUNNING as i32));
+
+        drop(commands);
+        assert_eq!(served.join().unwrap().calls().len(), 1);
+
+        drop(scheduler);
+        assert!(events.recv().is_err());
+    }
+
+    #[test]
+    fn decode_errors_are_events() {
+        let (scheduler, events, _commands, _calls) = channel_scheduler::new();
+        let error = DecodeError {
+            message_type: "TaskStatus".to_string(),
+            bytes: vec![0x0a],
+            reason: "truncated".to_string(),
+        };
+
+        scheduler.decode_error(&MockSchedulerDriver::new(), &error);
+        assert_eq!(events.recv(), Ok(SchedulerEvent::DecodeError(error)));
+    }
+}
@@ -0,0 +1,496 @@
+//! # Running command tasks from an executor.
+//!
+//! `CommandExecutor` is a ready-made `Executor` for tasks described by a
+//! `CommandInfo`.  Each launched task is spawned as a process in its own
+//! process group, with its standard output and error written to
+//! `<sandbox>/<task id>/stdout` and `stderr`, and its progress reported
+//! through the executor driver:
+//!
+//! * `TASK_RUNNING` once the process has been spawned,
+//! * `TASK_FINISHED` when it exits with status zero,
+//! * `TASK_FAILED` when it exits otherwise or cannot be spawned, and
+//! * `TASK_KILLED` when it exits after `kill_task` or `shutdown`.
+//!
+//! The exit status is described in the `message` of the terminal update.
+//! Killing a task sends `SIGTERM` to its process group, followed by
+//! `SIGKILL` if it is still running after the configured grace period.
+//!
+//! Tasks with a `HealthCheck` are checked by a `health_check::HealthChecker`
+//! while they run: changes in their health are reported as `TASK_RUNNING`
+//! updates with `healthy` set, and they are killed once the check has failed
+//! `consecutive_failures` times in a row.
+
+mod tests;
+
+use executor::{Executor, ExecutorDriver};
+use health_check::{HealthChecker, HealthListener};
+use libc;
+use proto::mesos as pb;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Tunables for `CommandExecutor`.
+#[derive(Clone, Debug)]
+pub struct CommandExecutorConfig {
+    /// Working directory of the tasks, under which their output is kept.
+    pub sandbox: PathBuf,
+
+    /// Time between `SIGTERM` and `SIGKILL` when a task is killed.
+    pub kill_grace_period: Duration,
+}
+
+impl Default for CommandExecutorConfig {
+    /// Uses the sandbox provided by the slave in `MESOS_SANDBOX`, or the
+    /// current directory, and a three second grace period.
+    fn default() -> CommandExecutorConfig {
+        let sandbox = env::var_os("MESOS_SANDBOX")
+            .map(PathBuf::from)
+            .unwrap_or(PathBuf::from("."));
+
+        CommandExecutorConfig {
+            sandbox: sandbox,
+            kill_grace_period: Duration::from_secs(3),
+        }
+    }
+}
+
+/// An `Executor` that runs the `CommandInfo` of each task it is given.
+/// Status updates are sent from the threads monitoring the processes, so
+/// the driver is shared rather than passed to callbacks.
+pub struct CommandExecutor {
+    config: CommandExecutorConfig,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    driver: Arc<ExecutorDriver + Send + Sync>,
+    executor_id: Mutex<Option<pb::ExecutorID>>,
+    tasks: Mutex<HashMap<String, Process>>,
+    reaped: Condvar,
+    kill_grace_period: Duration,
+    launches: AtomicUsize,
+}
+
+struct Process {
+    pid: libc::pid_t,
+    // Tells this launch from later ones of a task with the same id.
+    generation: usize,
+    slave_id: Option<pb::SlaveID>,
+    killing: bool,
+
+    // Stops checking when the process is forgotten.
+    health_checker: Option<HealthChecker>,
+}
+
+impl CommandExecutor {
+    pub fn new(
+        driver: Arc<ExecutorDriver + Send + Sync>,
+        config: CommandExecutorConfig
+    ) -> CommandExecutor {
+        let kill_grace_period = config.kill_grace_period;
+
+        CommandExecutor {
+            config: config,
+            shared: Arc::new(Shared {
+                driver: driver,
+                executor_id: Mutex::new(None),
+                tasks: Mutex::new(HashMap::new()),
+                reaped: Condvar::new(),
+                kill_grace_period: kill_grace_period,
+                launches: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of tasks whose process has not been reaped.
+    pub fn running(&self) -> usize {
+        self.shared.tasks.lock().unwrap().len()
+    }
+
+}
+
+/// Sends `SIGTERM` to the process of a task, and `SIGKILL` after the grace
+/// period unless it has been reaped by then.
+fn kill(shared: &Arc<Shared>, task_id: &str, process: &mut Process) {
+    if process.killing {
+        return;
+    }
+
+    process.killing = true;
+    signal(process.pid, libc::SIGTERM);
+
+    let shared = shared.clone();
+    let task_id = task_id.to_string();
+    let (pid, generation) = (process.pid, process.generation);
+
+    thread::spawn(move || {
+        thread::sleep(shared.kill_grace_period);
+
+        // The task is forgotten once its process has been reaped, and may
+        // have been launched again since.
+        let tasks = shared.tasks.lock().unwrap();
+        match tasks.get(&task_id) {
+            Some(process) if process.pid == pid &&
+                             process.generation == generation => {
+                warn!("Task [{}] did not terminate within {:?}, \
+                       sending SIGKILL", task_id, shared.kill_grace_period);
+                signal(pid, libc::SIGKILL);
+            },
+            _ => {},
+        }
+    });
+}
+
+impl Shared {
+    fn report(
+        &self,
+        task_id: &str,
+        slave_id: Option<&pb::SlaveID>,
+        state: pb::TaskState,
+        message: Option<String>) {
+
+        let status = self.status(task_id, slave_id, state, message);
+        self.driver.send_status_update(&status);
+    }
+
+    fn status(
+        &self,
+        task_id: &str,
+        slave_id: Option<&pb::SlaveID>,
+        state: pb::TaskState,
+        message: Option<String>) -> pb::TaskStatus {
+
+        let mut status = pb::TaskStatus::new();
+        status.mut_task_id().set_value(task_id.to_string());
+        status.set_state(state);
+        status.set_source(pb::TaskStatus_Source::SOURCE_EXECUTOR);
+
+        if let Some(slave_id) = slave_id {
+            status.set_slave_id(slave_id.clone());
+        }
+        if let Some(ref executor_id) = *self.executor_id.lock().unwrap() {
+            status.set_executor_id(executor_id.clone());
+        }
+        if let Some(message) = message {
+            status.set_message(message);
+        }
+        if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            status.set_timestamp(
+                now.as_secs() as f64 + now.subsec_nanos() as f64 * 1e-9);
+        }
+
+        status
+    }
+
+    /// Waits for the process of a task and reports its outcome.
+    fn monitor(&self, task_id: String, mut child: Child) {
+        let result = child.wait();
+
+        // The update is sent with the lock held, so that the task is only
+        // forgotten once its terminal update is out.
+        let mut tasks = self.tasks.lock().unwrap();
+        let process = tasks.remove(&task_id).unwrap();
+
+        let (state, message) = match result {
+            Ok(status) => (terminal_state(status, process.killing),
+                           describe(status)),
+            Err(error) => (pb::TaskState::TASK_FAILED,
+                           format!("Failed to wait for command: {}", error)),
+        };
+
+        info!("Task [{}] is {:?}: {}", task_id, state, message);
+        self.report(&task_id, process.slave_id.as_ref(), state,
+                    Some(message));
+
+        self.reaped.notify_all();
+    }
+}
+
+/// Reports the health of a running task, and kills it once unhealthy.
+struct TaskHealth {
+    shared: Arc<Shared>,
+    task_id: String,
+}
+
+impl HealthListener for TaskHealth {
+    fn health_changed(&self, healthy: bool, message: &str) {
+        let tasks = self.shared.tasks.lock().unwrap();
+        let process = match tasks.get(&self.task_id) {
+            Some(process) if !process.killing => process,
+            _ => return,
+        };
+
+        let mut status = self.shared.status(
+            &self.task_id, process.slave_id.as_ref(),
+            pb::TaskState::TASK_RUNNING, Some(message.to_string()));
+        status.set_healthy(healthy);
+        self.shared.driver.send_status_update(&status);
+    }
+
+    fn unhealthy(&self, message: &str) {
+        let mut tasks = self.shared.tasks.lock().unwrap();
+        if let Some(process) = tasks.get_mut(&self.task_id) {
+            warn!("Killing unhealthy task [{}]: {}", self.task_id, message);
+            kill(&self.shared, &self.task_id, process);
+        }
+    }
+}
+
+impl Executor for CommandExecutor {
+    fn registered(
+        &self,
+        executor_info: &pb::ExecutorInfo,
+        _: &pb::FrameworkInfo,
+        slave_info: &pb::SlaveInfo) {
+
+        info!("Registered executor [{}] on slave [{}]",
+              executor_info.get_executor_id().get_value(),
+              slave_info.get_hostname());
+
+        *self.shared.executor_id.lock().unwrap() =
+            Some(executor_info.get_executor_id().clone());
+    }
+
+    fn reregistered(
+        &self,
+        slave_info: &pb::SlaveInfo) {
+
+        info!("Re-registered on slave [{}]", slave_info.get_hostname());
+    }
+
+    fn disconnected(&self) {
+        warn!("Disconnected from slave");
+    }
+
+    fn launch_task(
+        &self,
+        task: &pb::TaskInfo) {
+
+        let task_id = task.get_task_id().get_value();
+        let slave_id = if task.has_slave_id() {
+            Some(task.get_slave_id())
+        } else {
+            None
+        };
+
+        if !task.has_command() {
+            self.shared.report(
+                task_id, slave_id, pb::TaskState::TASK_FAILED,
+                Some("Task has no command".to_string()));
+            return;
+        }
+
+        // The output of a task goes to a directory named after it, which
+        // must stay inside the sandbox.
+        if task_id.is_empty() || task_id == "." || task_id == ".." ||
+            task_id.contains('/') || task_id.contains('\0') {
+            self.shared.report(
+                task_id, slave_id, pb::TaskState::TASK_FAILED,
+                Some(format!("Task id '{}' cannot name a directory in \
+                              the sandbox", task_id)));
+            return;
+        }
+
+        let mut tasks = self.shared.tasks.lock().unwrap();
+        if tasks.contains_key(task_id) {
+            warn!("Ignoring launch of task [{}], which is already running",
+                  task_id);
+            return;
+        }
+
+        let output = self.config.sandbox.join(task_id);
+        let child = match spawn(task.get_command(),
+                                &self.config.sandbox,
+                                &output) {
+            Ok(child) => child,
+            Err(error) => {
+                self.shared.report(
+                    task_id, slave_id, pb::TaskState::TASK_FAILED,
+                    Some(format!("Failed to launch command: {}", error)));
+                return;
+            },
+        };
+
+        tasks.insert(task_id.to_string(), Process {
+            pid: child.id() as libc::pid_t,
+            generation: self.shared.launches.fetch_add(1, Ordering::SeqCst),
+            slave_id: slave_id.cloned(),
+            killing: false,
+            health_checker: None,
+        });
+
+        // Sent with the lock held so that it precedes the terminal update.
+        self.shared.report(
+            task_id, slave_id, pb::TaskState::TASK_RUNNING, None);
+
+        if task.has_health_check() {
+            let health = TaskHealth {
+                shared: self.shared.clone(),
+                task_id: task_id.to_string(),
+            };
+            match HealthChecker::start(task.get_health_check(), health) {
+                Ok(checker) => {
+                    let process = tasks.get_mut(task_id).unwrap();
+                    process.health_checker = Some(checker);
+                },
+                Err(error) => error!(
+                    "Not checking the health of task [{}]: {}",
+                    task_id, error),
+            }
+        }
+
+        let shared = self.shared.clone();
+        let task_id = task_id.to_string();
+        thread::spawn(move || shared.monitor(task_id, child));
+    }
+
+    fn kill_task(
+        &self,
+        task_id: &pb::TaskID) {
+
+        let mut tasks = self.shared.tasks.lock().unwrap();
+        match tasks.get_mut(task_id.get_value()) {
+            Some(process) => {
+                kill(&self.shared, task_id.get_value(), process)
+            },
+            None => warn!("Ignoring kill of unknown task [{}]",
+                          task_id.get_value()),
+        }
+    }
+
+    fn framework_message(
+        &self,
+        data: &Vec<u8>) {
+
+        debug!("Ignoring framework message of [{}] bytes", data.len());
+    }
+
+    /// Kills all tasks and waits for their processes to be reaped.
+    fn shutdown(&self) {
+        let mut tasks = self.shared.tasks.lock().unwrap();
+
+        for (task_id, process) in tasks.iter_mut() {
+            kill(&self.shared, task_id, process);
+        }
+
+        while !tasks.is_empty() {
+            tasks = self.shared.reaped.wait(tasks).unwrap();
+        }
+    }
+
+    fn error(&self, message: String) {
+        error!("Executor error: {}", message);
+    }
+}
+
+/// Spawns `command` in `sandbox` as the leader of a new process group,
+/// with its output redirected to files in `output`.
+fn spawn(
+    command: &pb::CommandInfo,
+    sandbox: &Path,
+    output: &Path
+) -> io::Result<Child> {
+    try!(fs::create_dir_all(output));
+    let stdout = try!(File::create(output.join("stdout")));
+    let stderr = try!(File::create(output.join("stderr")));
+
+    let mut process = try!(self::command(command));
+    process.current_dir(sandbox)
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(stdout))
+        .stderr(Stdio::from(stderr));
+
+    new_process_group(&mut process).spawn()
+}
+
+/// Makes `process` the leader of a new process group once spawned, so that
+/// signalling the group also reaches the processes it starts.
+pub fn new_process_group(process: &mut Command) -> &mut Command {
+    unsafe {
+        process.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        })
+    }
+}
+
+/// Prepares the process described by `command`: `/bin/sh -c value` for shell
+/// commands, otherwise `value` with `arguments` (of which the first is the
+/// program name, as with execve(2)), with `environment` and `user` applied.
+pub fn command(command: &pb::CommandInfo) -> io::Result<Command> {
+    let mut process = if command.get_shell() {
+        let mut process = Command::new("/bin/sh");
+        process.arg("-c").arg(command.get_value());
+        process
+    } else {
+        let mut process = Command::new(command.get_value());
+        process.args(&command.get_arguments().iter().skip(1)
+                     .collect::<Vec<_>>());
+        process
+    };
+
+    for variable in command.get_environment().get_variables() {
+        process.env(variable.get_name(), variable.get_value());
+    }
+
+    if command.has_user() {
+        let (uid, gid) = try!(lookup_user(command.get_user()));
+        process.uid(uid).gid(gid);
+    }
+
+    Ok(process)
+}
+
+fn lookup_user(user: &str) -> io::Result<(libc::uid_t, libc::gid_t)> {
+    let name = try!(CString::new(user).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "Invalid user name")
+    }));
+
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+    if passwd.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No such user '{}'", user)));
+    }
+
+    unsafe { Ok(((*passwd).pw_uid, (*passwd).pw_gid)) }
+}
+
+/// Signals the process group led by `pid`.
+fn signal(pid: libc::pid_t, signal: libc::c_int) {
+    unsafe {
+        libc::kill(-pid, signal);
+    }
+}
+
+fn terminal_state(status: ExitStatus, killing: bool) -> pb::TaskState {
+    if killing {
+        pb::TaskState::TASK_KILLED
+    } else if status.success() {
+        pb::TaskState::TASK_FINISHED
+    } else {
+        pb::TaskState::TASK_FAILED
+    }
+}
+
+fn describe(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("Command exited with status {}", code),
+        (None, Some(signal)) =>
+            format!("Command terminated by signal {}", signal),
+        (None, None) => format!("Command terminated: {}", status),
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod tests {
+
+    use ::command_executor::*;
+    use ::executor::{Executor, ExecutorDriver};
+    use ::proto::mesos as pb;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::{Arc, Mutex};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+    use std::time::Duration;
+
+    struct UpdateRecorder {
+        updates: Mutex<Sender<pb::TaskStatus>>,
+    }
+
+    impl ExecutorDriver for UpdateRecorder {
+        fn run(&mut self) -> i32 { 0 }
+        fn stop(&self) -> i32 { 0 }
+        fn send_status_update(&self, task_status: &pb::TaskStatus) -> i32 {
+            self.updates.lock().unwrap().send(task_status.clone()).unwrap();
+            0
+        }
+        fn send_framework_message(&self, _: &Vec<u8>) -> i32 { 0 }
+    }
+
+    fn executor(name: &str) -> (CommandExecutor, Receiver<pb::TaskStatus>,
+                                PathBuf) {
+        let sandbox = env::temp_dir().join(format!(
+            "mesos-rust-command-executor-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&sandbox);
+        fs::create_dir_all(&sandbox).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let driver = Arc::new(UpdateRecorder {
+            updates: Mutex::new(sender),
+        });
+        let config = CommandExecutorConfig {
+            sandbox: sandbox.clone(),
+            kill_grace_period: Duration::from_millis(100),
+        };
+
+        (CommandExecutor::new(driver, config), receiver, sandbox)
+    }
+
+    fn task(id: &str, command: pb::CommandInfo) -> pb::TaskInfo {
+        let mut task = pb::TaskInfo::new();
+        task.set_name(id.to_string());
+        task.mut_task_id().set_value(id.to_string());
+        task.mut_slave_id().set_value("slave-1".to_string());
+        task.set_command(command);
+        task
+    }
+
+    fn shell(value: &str) -> pb::CommandInfo {
+        let mut command = pb::CommandInfo::new();
+        command.set_value(value.to_string());
+        command
+    }
+
+    fn next(updates: &Receiver<pb::TaskStatus>) -> pb::TaskStatus {
+        updates.recv_timeout(Duration::from_secs(10)).unwrap()
+    }
+
+    fn read(path: PathBuf) -> String {
+        let mut contents = String::new();
+        File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn reports_exit_status_and_captures_output() {
+        let (executor, updates, sandbox) = executor("exit");
+
+        executor.launch_task(&task("t1", shell("echo out; echo err >&2; \
+                                                exit 3")));
+
+        let running = next(&updates);
+        assert_eq!(running.get_state(), pb::TaskState::TASK_RUNNING);
+        assert_eq!(running.get_slave_id().get_value(), "slave-1");
+
+        let failed = next(&updates);
+        assert_eq!(failed.get_state(), pb::TaskState::TASK_FAILED);
+        assert_eq!(failed.get_message(), "Command exited with status 3");
+        assert_eq!(failed.get_source(),
+                   pb::TaskStatus_Source::SOURCE_EXECUTOR);
+
+        assert_eq!(read(sandbox.join("t1").join("stdout")), "out\n");
+        assert_eq!(read(sandbox.join("t1").join("stderr")), "err\n");
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn task_ids_must_stay_in_the_sandbox() {
+        let (executor, updates, sandbox) = executor("escape");
+
+        for id in &["../escaped", "/tmp/escaped", "a/b", "..", ""] {
+            executor.launch_task(&task(id, shell("true")));
+
+            let failed = next(&updates);
+            assert_eq!(failed.get_state(), pb::TaskState::TASK_FAILED);
+            assert_eq!(failed.get_task_id().get_value(), *id);
+        }
+
+        assert!(!sandbox.parent().unwrap().join("escaped").exists());
+        assert_eq!(fs::read_dir(&sandbox).unwrap().count(), 0);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn runs_argv_commands_with_environment() {
+        let (executor, updates, sandbox) = executor("argv");
+
+        let mut command = pb::CommandInfo::new();
+        command.set_shell(false);
+        command.set_value("/bin/sh".to_string());
+        for argument in &["sh", "-c", "echo $GREETING"] {
+            command.mut_arguments().push(argument.to_string());
+        }
+        let mut variable = pb::Environment_Variable::new();
+        variable.set_name("GREETING".to_string());
+        variable.set_value("hello".to_string());
+        command.mut_environment().mut_variables().push(variable);
+
+        executor.launch_task(&task("t1", command));
+
+        assert_eq!(next(&updates).get_state(),
+                   pb::TaskState::TASK_RUNNING);
+        assert_eq!(next(&updates).get_state(),
+                   pb::TaskState::TASK_FINISHED);
+        assert_eq!(read(sandbox.join("t1").join("stdout")), "hello\n");
+
+        // A missing program fails the task without a TASK_RUNNING.
+        let mut command = pb::CommandInfo::new();
+        command.set_shell(false);
+        command.set_value("/nonexistent".to_string());
+        executor.launch_task(&task("t2", command));
+
+        assert_eq!(next(&updates).get_state(), pb::TaskState::TASK_FAILED);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn kill_escalates_to_sigkill() {
+        let (executor, updates, sandbox) = executor("kill");
+
+        executor.launch_task(&task("polite", shell("exec sleep 30")));
+        executor.launch_task(&task("stubborn", shell(
+            "trap '' TERM; echo ready; while true; do sleep 1; done")));
+        assert_eq!(next(&updates).get_state(),
+                   pb::TaskState::TASK_RUNNING);
+        assert_eq!(next(&updates).get_state(),
+                   pb::TaskState::TASK_RUNNING);
+
+        // Make sure that SIGTERM is being ignored before killing.
+        let stdout = sandbox.join("stubborn").join("stdout");
+        while read(stdout.clone()) != "ready\n" {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut task_id = pb::TaskID::new();
+        task_id.set_value("polite".to_string());
+        executor.kill_task(&task_id);
+
+        let killed = next(&updates);
+        assert_eq!(killed.get_task_id().get_value(), "polite");
+        assert_eq!(killed.get_state(), pb::TaskState::TASK_KILLED);
+        assert_eq!(killed.get_message(), "Command terminated by signal 15");
+
+        task_id.set_value("stubborn".to_string());
+        executor.kill_task(&task_id);
+
+        let killed = next(&updates);
+        assert_eq!(killed.get_state(), pb::TaskState::TASK_KILLED);
+        assert_eq!(killed.get_message(), "Command terminated by signal 9");
+        assert_eq!(executor.running(), 0);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn sigkill_spares_a_relaunched_task() {
+        let (executor, updates, sandbox) = executor("relaunch");
+
+        executor.launch_task(&task("t1", shell("sleep 10")));
+        assert_eq!(next(&updates).get_state(), pb::TaskState::TASK_RUNNING);
+        executor.kill_task(task("t1", shell("")).get_task_id());
+        assert_eq!(next(&updates).get_state(), pb::TaskState::TASK_KILLED);
+
+        // Relaunched within the grace period of the kill, and still running
+        // when it expires.
+        executor.launch_task(&task("t1", shell("sleep 0.5")));
+        assert_eq!(next(&updates).get_state(), pb::TaskState::TASK_RUNNING);
+        assert_eq!(next(&updates).get_state(),
+                   pb::TaskState::TASK_FINISHED);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn shutdown_kills_all_tasks() {
+        let (executor, updates, sandbox) = executor("shutdown");
+
+        executor.launch_task(&task("t1", shell("sleep 30")));
+        executor.launch_task(&task("t2", shell("sleep 30")));
+        executor.shutdown();
+
+        assert_eq!(executor.running(), 0);
+        let states: Vec<pb::TaskState> = updates.try_iter()
+            .map(|status| status.get_state())
+            .collect();
+        assert_eq!(states, vec![pb::TaskState::TASK_RUNNING,
+                                pb::TaskState::TASK_RUNNING,
+                                pb::TaskState::TASK_KILLED,
+                                pb::TaskState::TASK_KILLED]);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn unhealthy_tasks_are_killed() {
+        let (executor, updates, sandbox) = executor("health");
+
+        let mut check = pb::HealthCheck::new();
+        check.set_command(shell("exit 1"));
+        check.set_delay_seconds(0.0);
+        check.set_interval_seconds(0.01);
+        check.set_grace_period_seconds(0.0);
+        check.set_consecutive_failures(1);
+
+        let mut task = task("t1", shell("exec sleep 30"));
+        task.set_health_check(check);
+        executor.launch_task(&task);
+
+        let running = next(&updates);
+        assert_eq!(running.get_state(), pb::TaskState::TASK_RUNNING);
+        assert!(!running.has_healthy());
+
+        let unhealthy = next(&updates);
+        assert_eq!(unhealthy.get_state(), pb::TaskState::TASK_RUNNING);
+        assert_eq!(unhealthy.get_healthy(), false);
+
+        assert_eq!(next(&updates).get_state(), pb::TaskState::TASK_KILLED);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+//! Owned representations of scheduler callbacks and driver calls.
+//!
+//! `SchedulerEvent` captures a single `Scheduler` callback, and
+//! `SchedulerCall` a single `SchedulerDriver` call, as plain values that can
+//! be sent across threads, queued or stored.  They are the basis for the
+//! adapters that move scheduler logic off the driver's callback thread.
+
+use proto::mesos as pb;
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+
+/// A `Scheduler` callback, with its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchedulerEvent {
+    Registered {
+        framework_id: pb::FrameworkID,
+        master_info: pb::MasterInfo,
+    },
+    Reregistered {
+        master_info: pb::MasterInfo,
+    },
+    Offers(Vec<pb::Offer>),
+    Update(pb::TaskStatus),
+    Disconnected,
+    Rescind(pb::OfferID),
+    Message {
+        executor_id: pb::ExecutorID,
+        slave_id: pb::SlaveID,
+        data: String,
+    },
+    SlaveLost(pb::SlaveID),
+    ExecutorLost {
+        executor_id: pb::ExecutorID,
+        slave_id: pb::SlaveID,
+        status: i32,
+    },
+    Error(String),
+    DecodeError(DecodeError),
+}
+
+impl SchedulerEvent {
+
+    /// Invokes the callback of `scheduler` that corresponds to this event.
+    pub fn dispatch(self, scheduler: &Scheduler, driver: &SchedulerDriver) {
+        match self {
+            SchedulerEvent::Registered { framework_id, master_info } =>
+                scheduler.registered(driver, &framework_id, &master_info),
+            SchedulerEvent::Reregistered { master_info } =>
+                scheduler.reregistered(driver, &master_info),
+            SchedulerEvent::Offers(offers) =>
+                scheduler.resource_offers(driver, offers),
+            SchedulerEvent::Update(status) =>
+                scheduler.status_update(driver, &status),
+            SchedulerEvent::Disconnected =>
+                scheduler.disconnected(driver),
+            SchedulerEvent::Rescind(offer_id) =>
+                scheduler.offer_rescinded(driver, &offer_id),
+            SchedulerEvent::Message { executor_id, slave_id, data } =>
+                scheduler.framework_message(
+                    driver, &executor_id, &slave_id, &data),
+            SchedulerEvent::SlaveLost(slave_id) =>
+                scheduler.slave_lost(driver, &slave_id),
+            SchedulerEvent::ExecutorLost { executor_id, slave_id, status } =>
+                scheduler.executor_lost(
+                    driver, &executor_id, &slave_id, status),
+            SchedulerEvent::Error(message) =>
+                scheduler.error(driver, &message),
+            SchedulerEvent::DecodeError(error) =>
+                scheduler.decode_error(driver, &error),
+        }
+    }
+}
+
+/// A `SchedulerDriver` call, with its arguments.  `run` is not included
+/// since it is only ever invoked by the owner of the driver.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchedulerCall {
+    Stop {
+        failover: bool,
+    },
+    RequestResources {
+        requests: Vec<pb::Request>,
+    },
+    DeclineOffer {
+        offer_id: pb::OfferID,
+        filters: pb::Filters,
+    },
+    LaunchTasks {
+        offer_id: pb::OfferID,
+        tasks: Vec<pb::TaskInfo>,
+        filters: pb::Filters,
+    },
+    ReviveOffers,
+    KillTask {
+        task_id: pb::TaskID,
+    },
+    ReconcileTasks {
+        statuses: Vec<pb::TaskStatus>,
+    },
+    SendFrameworkMessage {
+        executor_id: pb::ExecutorID,
+        slave_id: pb::SlaveID,
+        data: Vec<u8>,
+    },
+}
+
+impl SchedulerCall {
+
+    /// Performs this call on `driver`, returning the driver status.
+    pub fn apply(&self, driver: &SchedulerDriver) -> i32 {
+        match *self {
+            SchedulerCall::Stop { failover } =>
+                driver.stop(failover),
+            SchedulerCall::RequestResources { ref requests } =>
+                driver.request_resources(&requests.iter().collect()),
+            SchedulerCall::DeclineOffer { ref offer_id, ref filters } =>
+                driver.decline_offer(offer_id, filters),
+            SchedulerCall::LaunchTasks {
+                ref offer_id, ref tasks, ref filters } =>
+                driver.launch_tasks(
+                    offer_id, &tasks.iter().collect(), filters),
+            SchedulerCall::ReviveOffers =>
+                driver.revive_offers(),
+            SchedulerCall::KillTask { ref task_id } =>
+                driver.kill_task(task_id),
+            SchedulerCall::ReconcileTasks { ref statuses } =>
+                driver.reconcile_tasks(&statuses.iter().collect()),
+            SchedulerCall::SendFrameworkMessage {
+                ref executor_id, ref slave_id, ref data } =>
+                driver.send_framework_message(executor_id, slave_id, data),
+        }
+    }
+}
+
+/// Receives every scheduler callback as an owned `SchedulerEvent`.
+pub trait EventHandler {
+    fn handle(&self, driver: &SchedulerDriver, event: SchedulerEvent);
+}
+
+/// A `Scheduler` that converts each callback into a `SchedulerEvent` and
+/// passes it to an `EventHandler`.
+pub struct EventScheduler<H: EventHandler> {
+    handler: H,
+}
+
+impl<H: EventHandler> EventScheduler<H> {
+
+    pub fn new(handler: H) -> EventScheduler<H> {
+        EventScheduler { handler: handler }
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+}
+
+impl<H: EventHandler> Scheduler for EventScheduler<H> {
+
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        master_info: &pb::MasterInfo) {
+
+        self.handler.handle(driver, SchedulerEvent::Registered {
+            framework_id: framework_id.clone(),
+            master_info: master_info.clone(),
+        });
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) {
+
+        self.handler.handle(driver, SchedulerEvent::Reregistered {
+            master_info: master_info.clone(),
+        });
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        self.handler.handle(driver, SchedulerEvent::Offers(offers));
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        self.handler.handle(driver,
+                            SchedulerEvent::Update(task_status.clone()));
+    }
+
+    fn disconnected(
+        &self,
+        driver: &SchedulerDriver) {
+
+        self.handler.handle(driver, SchedulerEvent::Disconnected);
+    }
+
+    fn offer_rescinded(
+        &self,
+        driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) {
+
+        self.handler.handle(driver, SchedulerEvent::Rescind(offer_id.clone()));
+    }
+
+    fn framework_message(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &String) {
+
+        self.handler.handle(driver, SchedulerEvent::Message {
+            executor_id: executor_id.clone(),
+            slave_id: slave_id.clone(),
+            data: data.clone(),
+        });
+    }
+
+    fn slave_lost(
+        &self,
+        driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) {
+
+        self.handler.handle(driver,
+                            SchedulerEvent::SlaveLost(slave_id.clone()));
+    }
+
+    fn executor_lost(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        status: i32) {
+
+        self.handler.handle(driver, SchedulerEvent::ExecutorLost {
+            executor_id: executor_id.clone(),
+            slave_id: slave_id.clone(),
+            status: status,
+        });
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        self.handler.handle(driver, SchedulerEvent::Error(message.clone()));
+    }
+
+    fn decode_error(
+        &self,
+        driver: &SchedulerDriver,
+        error: &DecodeError) {
+
+        self.handler.handle(driver,
+                            SchedulerEvent::DecodeError(error.clone()));
+    }
+}
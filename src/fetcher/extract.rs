@@ -0,0 +1,73 @@
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path};
+use tar::Archive;
+use zip::ZipArchive;
+
+/// Extracts `archive` into `directory` if its name has a supported
+/// extension, and otherwise leaves it alone.
+pub fn extract(archive: &Path, directory: &Path) -> io::Result<()> {
+    let name = archive.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or(String::new());
+
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let file = try!(File::open(archive));
+        untar(GzDecoder::new(file), directory)
+    } else if name.ends_with(".tar.bz2") {
+        let file = try!(File::open(archive));
+        untar(BzDecoder::new(file), directory)
+    } else if name.ends_with(".tar") {
+        untar(try!(File::open(archive)), directory)
+    } else if name.ends_with(".zip") {
+        unzip(archive, directory)
+    } else {
+        Ok(())
+    }
+}
+
+/// `tar` refuses to write outside of `directory` on its own.
+fn untar<R: Read>(reader: R, directory: &Path) -> io::Result<()> {
+    Archive::new(reader).unpack(directory)
+}
+
+fn unzip(archive: &Path, directory: &Path) -> io::Result<()> {
+    let mut zip = try!(ZipArchive::new(try!(File::open(archive))));
+
+    for i in 0..zip.len() {
+        let mut entry = try!(zip.by_index(i));
+
+        let relative = Path::new(entry.name()).to_path_buf();
+        let enclosed = relative.components().all(|component| match component {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false,
+        });
+        if !enclosed {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Archive entry {:?} is outside of the sandbox",
+                        relative)));
+        }
+
+        let path = directory.join(&relative);
+        if entry.is_dir() {
+            try!(fs::create_dir_all(&path));
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            try!(fs::create_dir_all(parent));
+        }
+        try!(io::copy(&mut entry, &mut try!(File::create(&path))));
+
+        if let Some(mode) = entry.unix_mode() {
+            try!(fs::set_permissions(
+                &path, fs::Permissions::from_mode(mode)));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use fetcher::FetchError;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const MAX_REDIRECTS: usize = 5;
+
+/// Downloads `url` into `out` with a plain HTTP/1.0 `GET`, following
+/// redirects.
+pub fn get<W: Write>(
+    url: &str,
+    timeout: Duration,
+    mut out: W
+) -> Result<(), FetchError> {
+    let mut url = url.to_string();
+
+    for _ in 0..(MAX_REDIRECTS + 1) {
+        let io_error = |error| FetchError::Io {
+            uri: url.clone(),
+            error: error,
+        };
+
+        let (host, path) = try!(split(&url).ok_or(
+            FetchError::UnsupportedUri { uri: url.clone() }));
+        let response = try!(request(&host, &path, timeout)
+                            .map_err(&io_error));
+        let (status, headers, mut body) = response;
+
+        match status {
+            200 => {
+                let expected = header(&headers, "content-length")
+                    .and_then(|length| length.parse::<u64>().ok());
+                let copied = try!(io::copy(&mut body, &mut out)
+                                  .map_err(&io_error));
+
+                return match expected {
+                    Some(expected) if expected != copied => Err(io_error(
+                        io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!("Received {} of {} bytes",
+                                    copied, expected)))),
+                    _ => Ok(()),
+                };
+            },
+            301 | 302 | 303 | 307 | 308 => {
+                let location = try!(header(&headers, "location").ok_or(
+                    FetchError::Http { uri: url.clone(), status: status }));
+                url = resolve(&host, &path, &location);
+            },
+            _ => return Err(FetchError::Http {
+                uri: url.clone(),
+                status: status,
+            }),
+        }
+    }
+
+    Err(FetchError::Io {
+        uri: url,
+        error: io::Error::new(io::ErrorKind::Other, "Too many redirects"),
+    })
+}
+
+/// Splits `http://host[:port]/path` into `host[:port]` and `/path`.
+fn split(url: &str) -> Option<(String, String)> {
+    if !url.starts_with("http://") {
+        return None;
+    }
+
+    let rest = &url["http://".len()..];
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    // Fragments are not sent to the server.
+    let path = path.split('#').next().unwrap_or("/");
+
+    if host.is_empty() {
+        None
+    } else {
+        Some((host.to_string(), path.to_string()))
+    }
+}
+
+/// Resolves the `Location` of a redirect from `http://host/path`.
+fn resolve(host: &str, path: &str, location: &str) -> String {
+    if location.contains("://") {
+        location.to_string()
+    } else if location.starts_with("//") {
+        format!("http:{}", location)
+    } else if location.starts_with('/') {
+        format!("http://{}{}", host, location)
+    } else {
+        // Relative to the directory of the path, without its query.
+        let path = path.split('?').next().unwrap_or("/");
+        let directory = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        format!("http://{}{}{}", host, directory, location)
+    }
+}
+
+type Response = (u32, Vec<(String, String)>, BufReader<TcpStream>);
+
+fn request(host: &str, path: &str, timeout: Duration)
+           -> io::Result<Response> {
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+
+    let address = try!(try!(address.to_socket_addrs()).next().ok_or(
+        io::Error::new(io::ErrorKind::NotFound,
+                       format!("Could not resolve '{}'", host))));
+
+    let mut stream = try!(TcpStream::connect_timeout(&address, timeout));
+    try!(stream.set_read_timeout(Some(timeout)));
+    try!(stream.set_write_timeout(Some(timeout)));
+    try!(write!(stream,
+                "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                path, host));
+
+    let mut reader = BufReader::new(stream);
+
+    // E.g. "HTTP/1.1 200 OK".
+    let mut status_line = String::new();
+    try!(reader.read_line(&mut status_line));
+    let status = try!(status_line.split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u32>().ok())
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Malformed HTTP status line: {:?}",
+                    status_line.trim_end()))));
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if try!(reader.read_line(&mut line)) == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(colon) = line.find(':') {
+            headers.push((line[..colon].trim().to_lowercase(),
+                          line[colon + 1..].trim().to_string()));
+        }
+    }
+
+    if header(&headers, "transfer-encoding")
+        .map_or(false, |encoding| encoding != "identity") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unsupported HTTP transfer encoding"));
+    }
+
+    Ok((status, headers, reader))
+}
+
+fn header(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers.iter()
+        .find(|&&(ref key, _)| key == name)
+        .map(|&(_, ref value)| value.clone())
+}
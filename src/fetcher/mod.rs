@@ -0,0 +1,235 @@
+//! # Fetching `CommandInfo` URIs into a sandbox.
+//!
+//! `Fetcher` implements the parts of the Mesos fetcher that custom
+//! executors need: each `CommandInfo_URI` is downloaded into the sandbox
+//! from a `file://` URI (or a plain absolute path) or an `http://` URI,
+//! made executable if `executable` is set, and otherwise extracted in place
+//! if `extract` is set and it is a `.tar`, `.tar.gz`, `.tgz`, `.tar.bz2` or
+//! `.zip` archive.  URIs with `cache` set are kept in a local cache keyed by
+//! URI, if the fetcher was given a cache directory, and only downloaded
+//! once.
+//!
+//! This module is only available with the `fetcher` feature.
+
+mod extract;
+mod http;
+mod tests;
+
+use proto::mesos as pb;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The reasons a URI could not be fetched.
+#[derive(Debug)]
+pub enum FetchError {
+    /// The URI scheme is not supported, or the URI has no file name.
+    UnsupportedUri { uri: String },
+
+    /// An HTTP server responded with a status other than 200.
+    Http { uri: String, status: u32 },
+
+    /// Reading, writing or extracting the file failed.
+    Io { uri: String, error: io::Error },
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FetchError::UnsupportedUri { ref uri } =>
+                write!(f, "Unsupported URI '{}'", uri),
+            FetchError::Http { ref uri, status } =>
+                write!(f, "Fetching '{}' failed with HTTP status {}",
+                       uri, status),
+            FetchError::Io { ref uri, ref error } =>
+                write!(f, "Fetching '{}' failed: {}", uri, error),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+/// Fetches URIs into sandboxes, optionally through a cache.
+#[derive(Clone, Debug)]
+pub struct Fetcher {
+    cache: Option<PathBuf>,
+    timeout: Duration,
+}
+
+impl Fetcher {
+
+    /// Returns a fetcher without a cache, which ignores the `cache` flag.
+    pub fn new() -> Fetcher {
+        Fetcher {
+            cache: None,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Returns a fetcher which keeps URIs with `cache` set in `dir`.
+    pub fn with_cache<P: AsRef<Path>>(dir: P) -> Fetcher {
+        Fetcher {
+            cache: Some(dir.as_ref().to_path_buf()),
+            ..Fetcher::new()
+        }
+    }
+
+    /// Sets the connect and read timeout of HTTP downloads.
+    pub fn timeout(mut self, timeout: Duration) -> Fetcher {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Fetches every URI of `command` into `sandbox`, in order.
+    pub fn fetch_all(
+        &self,
+        command: &pb::CommandInfo,
+        sandbox: &Path
+    ) -> Result<Vec<PathBuf>, FetchError> {
+        let mut paths = vec![];
+        for uri in command.get_uris() {
+            paths.push(try!(self.fetch(uri, sandbox)));
+        }
+        Ok(paths)
+    }
+
+    /// Fetches `uri` into `sandbox` and returns the path of the fetched
+    /// file.  Archives are extracted next to it.
+    pub fn fetch(
+        &self,
+        uri: &pb::CommandInfo_URI,
+        sandbox: &Path
+    ) -> Result<PathBuf, FetchError> {
+        let value = uri.get_value();
+        let source = try!(Source::parse(value));
+        let name = try!(source.file_name().ok_or(
+            FetchError::UnsupportedUri { uri: value.to_string() }));
+        let destination = sandbox.join(&name);
+
+        let io_error = |error| FetchError::Io {
+            uri: value.to_string(),
+            error: error,
+        };
+
+        match self.cache {
+            Some(ref cache) if uri.get_cache() => {
+                let cached = cache.join(cache_key(value, &name));
+                if !cached.exists() {
+                    try!(fs::create_dir_all(cache).map_err(&io_error));
+                    try!(self.download(&source, value, &cached));
+                }
+                debug!("Fetching '{}' from cache", value);
+                try!(fs::copy(&cached, &destination).map_err(&io_error));
+            },
+            _ => try!(self.download(&source, value, &destination)),
+        }
+
+        if uri.get_executable() {
+            try!(make_executable(&destination).map_err(&io_error));
+        } else if uri.get_extract() {
+            try!(extract::extract(&destination, sandbox)
+                 .map_err(&io_error));
+        }
+
+        Ok(destination)
+    }
+
+    /// Downloads to a temporary file first so that no partial file is
+    /// left behind, or found in the cache.
+    fn download(
+        &self,
+        source: &Source,
+        uri: &str,
+        destination: &Path
+    ) -> Result<(), FetchError> {
+        info!("Fetching '{}' to {:?}", uri, destination);
+
+        let mut partial = destination.as_os_str().to_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+
+        let io_error = |error| FetchError::Io {
+            uri: uri.to_string(),
+            error: error,
+        };
+
+        let result = match *source {
+            Source::File(ref path) => {
+                fs::copy(path, &partial).map(|_| ()).map_err(&io_error)
+            },
+            Source::Http(ref url) => {
+                File::create(&partial).map_err(&io_error).and_then(|file| {
+                    http::get(url, self.timeout, file)
+                })
+            },
+        };
+
+        match result {
+            Ok(()) => fs::rename(&partial, destination).map_err(&io_error),
+            Err(error) => {
+                let _ = fs::remove_file(&partial);
+                Err(error)
+            },
+        }
+    }
+}
+
+enum Source {
+    File(PathBuf),
+    Http(String),
+}
+
+impl Source {
+    fn parse(uri: &str) -> Result<Source, FetchError> {
+        if uri.starts_with("file://") {
+            Ok(Source::File(PathBuf::from(&uri["file://".len()..])))
+        } else if uri.starts_with('/') {
+            Ok(Source::File(PathBuf::from(uri)))
+        } else if uri.starts_with("http://") {
+            Ok(Source::Http(uri.to_string()))
+        } else {
+            Err(FetchError::UnsupportedUri { uri: uri.to_string() })
+        }
+    }
+
+    fn file_name(&self) -> Option<String> {
+        let path = match *self {
+            Source::File(ref path) => path.to_string_lossy().into_owned(),
+            Source::Http(ref url) => {
+                let rest = &url["http://".len()..];
+                let path = rest.find('/').map(|i| &rest[i..]).unwrap_or("");
+                path.split(|c| c == '?' || c == '#')
+                    .next()
+                    .unwrap_or("")
+                    .to_string()
+            },
+        };
+
+        match path.rsplit('/').next() {
+            Some(name) if !name.is_empty() && name != "." && name != ".." =>
+                Some(name.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps cache entries for different URIs with the same file name apart.
+/// The key outlives the process, so the hash of the URI has to be stable
+/// across builds, unlike `DefaultHasher`: it is a 64-bit FNV-1a.
+fn cache_key(uri: &str, name: &str) -> String {
+    let hash = uri.bytes().fold(0xcbf29ce484222325, |hash: u64, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}-{}", hash, name)
+}
+
+fn make_executable(path: &Path) -> io::Result<()> {
+    let mut permissions = try!(fs::metadata(path)).permissions();
+    let mode = permissions.mode();
+    permissions.set_mode(mode | 0o111);
+    fs::set_permissions(path, permissions)
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests {
+
+    use ::fetcher::*;
+    use ::proto::mesos as pb;
+    use bzip2::Compression as BzCompression;
+    use bzip2::write::BzEncoder;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::process;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use tar;
+    use zip;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "mesos-rust-fetcher-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("source")).unwrap();
+        fs::create_dir_all(dir.join("sandbox")).unwrap();
+        dir
+    }
+
+    fn uri(value: &str) -> pb::CommandInfo_URI {
+        let mut uri = pb::CommandInfo_URI::new();
+        uri.set_value(value.to_string());
+        uri
+    }
+
+    fn write(path: &Path, contents: &[u8]) {
+        File::create(path).unwrap().write_all(contents).unwrap();
+    }
+
+    fn read(path: &Path) -> String {
+        let mut contents = String::new();
+        File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    fn tar<W: Write>(out: W) -> W {
+        let mut builder = tar::Builder::new(out);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(6);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "app/hello", &b"hello\n"[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    /// Serves `body` for `/path` and 404 for anything else, counting
+    /// requests.
+    fn serve(body: &'static [u8]) -> (String, Arc<AtomicUsize>) {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for stream in server.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let lines = BufReader::new(&stream).lines()
+                    .map(|line| line.unwrap())
+                    .take_while(|line| !line.is_empty())
+                    .collect::<Vec<_>>();
+
+                if lines[0] == "GET /redirect HTTP/1.0" {
+                    write!(stream, "HTTP/1.0 302 Found\r\n\
+                                    Location: /files/tool.sh\r\n\r\n")
+                        .unwrap();
+                } else if lines[0] == "GET /files/latest?v=1 HTTP/1.0" {
+                    write!(stream, "HTTP/1.0 302 Found\r\n\
+                                    Location: tool.sh\r\n\r\n")
+                        .unwrap();
+                } else if lines[0] == "GET /files/tool.sh HTTP/1.0" {
+                    write!(stream, "HTTP/1.0 200 OK\r\n\
+                                    Content-Length: {}\r\n\r\n",
+                           body.len()).unwrap();
+                    stream.write_all(body).unwrap();
+                } else {
+                    write!(stream, "HTTP/1.0 404 Not Found\r\n\r\n")
+                        .unwrap();
+                }
+            }
+        });
+
+        (base, requests)
+    }
+
+    #[test]
+    fn fetches_local_files_and_sets_executable_bit() {
+        let dir = scratch("local");
+        let source = dir.join("source").join("run.sh");
+        write(&source, b"#!/bin/sh\n");
+
+        let mut script = uri(&format!("file://{}", source.display()));
+        script.set_executable(true);
+        let path = Fetcher::new().fetch(&script, &dir.join("sandbox"))
+            .unwrap();
+
+        assert_eq!(path, dir.join("sandbox").join("run.sh"));
+        assert_eq!(read(&path), "#!/bin/sh\n");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+
+        match Fetcher::new().fetch(&uri("ftp://host/file"), &dir) {
+            Err(FetchError::UnsupportedUri { .. }) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn extracts_archives() {
+        let dir = scratch("extract");
+        let source = dir.join("source");
+        let sandbox = dir.join("sandbox");
+
+        let gz = tar(GzEncoder::new(Vec::new(), Compression::default()))
+            .finish().unwrap();
+        write(&source.join("app.tgz"), &gz);
+
+        let bz = tar(BzEncoder::new(Vec::new(), BzCompression::Default))
+            .finish().unwrap();
+        write(&source.join("app.tar.bz2"), &bz);
+
+        let mut zip = zip::ZipWriter::new(
+            File::create(source.join("app.zip")).unwrap());
+        zip.start_file("app/zipped",
+                       zip::write::FileOptions::default()
+                           .compression_method(
+                               zip::CompressionMethod::Stored)
+                           .unix_permissions(0o755))
+            .unwrap();
+        zip.write_all(b"zipped\n").unwrap();
+        zip.finish().unwrap();
+
+        let fetcher = Fetcher::new();
+        for name in &["app.tgz", "app.tar.bz2", "app.zip"] {
+            let path = source.join(name);
+            fetcher.fetch(&uri(path.to_str().unwrap()), &sandbox).unwrap();
+            assert!(sandbox.join(name).exists());
+        }
+
+        assert_eq!(read(&sandbox.join("app").join("hello")), "hello\n");
+        assert_eq!(read(&sandbox.join("app").join("zipped")), "zipped\n");
+        let mode = fs::metadata(sandbox.join("app").join("zipped"))
+            .unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        // Archives are left alone unless extraction is requested.
+        fs::remove_dir_all(sandbox.join("app")).unwrap();
+        let mut archive = uri(source.join("app.tgz").to_str().unwrap());
+        archive.set_extract(false);
+        fetcher.fetch(&archive, &sandbox).unwrap();
+        assert!(!sandbox.join("app").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fetches_over_http_through_cache() {
+        let dir = scratch("http");
+        let (base, requests) = serve(b"echo hi\n");
+        let fetcher = Fetcher::with_cache(dir.join("cache"));
+
+        let mut tool = uri(&format!("{}/redirect", base));
+        tool.set_cache(true);
+
+        let sandbox = dir.join("sandbox");
+        let path = fetcher.fetch(&tool, &sandbox).unwrap();
+        assert_eq!(path, sandbox.join("redirect"));
+        assert_eq!(read(&path), "echo hi\n");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Served from the cache the second time around.
+        let other = dir.join("other");
+        fs::create_dir_all(&other).unwrap();
+        assert_eq!(read(&fetcher.fetch(&tool, &other).unwrap()),
+                   "echo hi\n");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Without `cache` the URI is always downloaded.
+        tool.set_cache(false);
+        fetcher.fetch(&tool, &other).unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+
+        match fetcher.fetch(&uri(&format!("{}/missing", base)), &sandbox) {
+            Err(FetchError::Http { status: 404, .. }) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(!sandbox.join("missing").exists());
+        assert!(!sandbox.join("missing.partial").exists());
+
+        // Relative redirects resolve against the directory of the path.
+        let latest = uri(&format!("{}/files/latest?v=1", base));
+        assert_eq!(read(&fetcher.fetch(&latest, &other).unwrap()),
+                   "echo hi\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cache_keys_are_stable() {
+        assert_eq!(cache_key("http://example.com/tool.sh", "tool.sh"),
+                   "570ccf0f2934b35c-tool.sh");
+        assert!(cache_key("http://example.com/a/tool.sh", "tool.sh") !=
+                cache_key("http://example.com/b/tool.sh", "tool.sh"));
+    }
+}
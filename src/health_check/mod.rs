@@ -0,0 +1,237 @@
+//! # Task health checks.
+//!
+//! A `HealthChecker` evaluates a `pb::HealthCheck` against a running task,
+//! either by running its command (healthy if it exits with status zero) or
+//! by issuing `GET path` against the HTTP port on the local host (healthy
+//! if the response status is one of `statuses`, or in 200-399 if none are
+//! given).  Checks start after `delay_seconds` and are repeated every
+//! `interval_seconds`, each one being abandoned as failed after
+//! `timeout_seconds`.
+//!
+//! The outcome is reported to a `HealthListener`.  Failures within
+//! `grace_period_seconds` of starting are ignored until the task has passed
+//! a check for the first time, and once `consecutive_failures` checks have
+//! failed in a row the listener is asked to kill the task.  With
+//! `consecutive_failures` set to zero, failures are reported but the task is
+//! never killed.
+//! `command_executor::CommandExecutor` uses this to send `TASK_RUNNING`
+//! updates with `healthy` set; other executors can implement the listener
+//! to do the same.
+
+mod tests;
+
+use builder::validation::{self, ValidationError};
+use command_executor;
+use libc;
+use proto::mesos as pb;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Receives the outcome of health checks, on the checker's thread.
+pub trait HealthListener: Send + 'static {
+
+    /// Invoked with the first outcome, and whenever an outcome differs from
+    /// the previous one.
+    fn health_changed(&self, healthy: bool, message: &str);
+
+    /// Invoked once `consecutive_failures` checks have failed in a row, at
+    /// which point the task should be killed.  No further checks are made.
+    /// Never invoked if `consecutive_failures` is zero.
+    fn unhealthy(&self, message: &str);
+}
+
+/// Runs a health check on a thread of its own until it is dropped.
+pub struct HealthChecker {
+    // Dropping the sender wakes the checker up and stops it.
+    _stop: Sender<()>,
+}
+
+impl HealthChecker {
+
+    /// Starts checking, or fails if `check` does not pass
+    /// `builder::validation::health_check`.
+    pub fn start<L: HealthListener>(
+        check: &pb::HealthCheck,
+        listener: L
+    ) -> Result<HealthChecker, ValidationError> {
+        try!(validation::health_check(check));
+
+        let (stop, stopped) = mpsc::channel();
+        let check = check.clone();
+        thread::spawn(move || run(check, listener, stopped));
+
+        Ok(HealthChecker { _stop: stop })
+    }
+}
+
+fn run<L: HealthListener>(
+    check: pb::HealthCheck,
+    listener: L,
+    stopped: Receiver<()>) {
+
+    let started = Instant::now();
+    let grace_period = seconds(check.get_grace_period_seconds());
+    let interval = seconds(check.get_interval_seconds());
+    let timeout = seconds(check.get_timeout_seconds());
+    let max_failures = check.get_consecutive_failures();
+
+    let mut healthy = None;
+    let mut failures = 0;
+
+    if !sleep(&stopped, seconds(check.get_delay_seconds())) {
+        return;
+    }
+
+    loop {
+        let result = perform(&check, timeout);
+
+        // The task may have gone away while it was being checked.
+        if let Err(TryRecvError::Disconnected) = stopped.try_recv() {
+            return;
+        }
+
+        match result {
+            Ok(()) => {
+                failures = 0;
+                if healthy != Some(true) {
+                    listener.health_changed(true, "Health check passed");
+                }
+                healthy = Some(true);
+            },
+            Err(ref message) if healthy.is_none()
+                                && started.elapsed() < grace_period => {
+                debug!("Ignoring failed health check during grace period: \
+                        {}", message);
+            },
+            Err(message) => {
+                failures += 1;
+                if healthy != Some(false) {
+                    listener.health_changed(false, &message);
+                }
+                healthy = Some(false);
+
+                if max_failures > 0 && failures >= max_failures {
+                    listener.unhealthy(&format!(
+                        "{} consecutive health checks failed, the last \
+                         with: {}", failures, message));
+                    return;
+                }
+            },
+        }
+
+        if !sleep(&stopped, interval) {
+            return;
+        }
+    }
+}
+
+/// Returns false if the checker was stopped while sleeping.
+fn sleep(stopped: &Receiver<()>, duration: Duration) -> bool {
+    match stopped.recv_timeout(duration) {
+        Err(RecvTimeoutError::Timeout) => true,
+        _ => false,
+    }
+}
+
+fn seconds(seconds: f64) -> Duration {
+    let seconds = seconds.max(0.0);
+    Duration::new(seconds.trunc() as u64,
+                  (seconds.fract() * 1e9) as u32)
+}
+
+fn perform(check: &pb::HealthCheck, timeout: Duration) -> Result<(), String> {
+    if check.has_command() {
+        check_command(check.get_command(), timeout)
+    } else {
+        check_http(check.get_http(), timeout)
+    }
+}
+
+fn check_command(
+    command: &pb::CommandInfo,
+    timeout: Duration
+) -> Result<(), String> {
+    let mut process = try!(command_executor::command(command).map_err(|e| {
+        format!("Failed to prepare health check command: {}", e)
+    }));
+
+    // In a process group of its own, so that a check that times out is
+    // killed along with the processes it started.
+    let mut child = try!(command_executor::new_process_group(&mut process)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run health check command: {}", e)));
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!(
+                "Health check command failed with {}", status)),
+            Ok(None) if Instant::now() >= deadline => {
+                unsafe {
+                    libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+                }
+                let _ = child.wait();
+                return Err(format!(
+                    "Health check command timed out after {:?}", timeout));
+            },
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(error) => return Err(format!(
+                "Failed to wait for health check command: {}", error)),
+        }
+    }
+}
+
+fn check_http(
+    http: &pb::HealthCheck_HTTP,
+    timeout: Duration
+) -> Result<(), String> {
+    let port = http.get_port() as u16;
+    let path = if http.has_path() { http.get_path() } else { "/" };
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let request = |timeout: Duration| -> ::std::io::Result<String> {
+        let mut stream = try!(TcpStream::connect_timeout(&address, timeout));
+        try!(stream.set_read_timeout(Some(timeout)));
+        try!(stream.set_write_timeout(Some(timeout)));
+        try!(write!(stream,
+                    "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                    path, address));
+
+        let mut status_line = String::new();
+        try!(BufReader::new(stream).read_line(&mut status_line));
+        Ok(status_line)
+    };
+
+    let status_line = try!(request(timeout).map_err(|e| {
+        format!("HTTP health check of {}{} failed: {}", address, path, e)
+    }));
+
+    // E.g. "HTTP/1.1 200 OK".
+    let status = try!(status_line.split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u32>().ok())
+        .ok_or(format!("Malformed HTTP response from {}{}: {:?}",
+                       address, path, status_line.trim_end())));
+
+    let healthy = if http.get_statuses().is_empty() {
+        status >= 200 && status < 400
+    } else {
+        http.get_statuses().contains(&status)
+    };
+
+    if healthy {
+        Ok(())
+    } else {
+        Err(format!("HTTP health check of {}{} returned status {}",
+                    address, path, status))
+    }
+}
@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+
+    use ::health_check::*;
+    use ::proto::mesos as pb;
+    use std::env;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::process;
+    use std::sync::Mutex;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[derive(Debug, PartialEq)]
+    enum Outcome {
+        Changed(bool),
+        Unhealthy,
+    }
+
+    struct Recorder {
+        outcomes: Mutex<Sender<Outcome>>,
+    }
+
+    impl HealthListener for Recorder {
+        fn health_changed(&self, healthy: bool, _: &str) {
+            let _ = self.outcomes.lock().unwrap()
+                .send(Outcome::Changed(healthy));
+        }
+
+        fn unhealthy(&self, _: &str) {
+            let _ = self.outcomes.lock().unwrap().send(Outcome::Unhealthy);
+        }
+    }
+
+    fn recorder() -> (Recorder, Receiver<Outcome>) {
+        let (sender, receiver) = mpsc::channel();
+        (Recorder { outcomes: Mutex::new(sender) }, receiver)
+    }
+
+    fn check(grace_period_seconds: f64) -> pb::HealthCheck {
+        let mut check = pb::HealthCheck::new();
+        check.set_delay_seconds(0.0);
+        check.set_interval_seconds(0.01);
+        check.set_timeout_seconds(1.0);
+        check.set_grace_period_seconds(grace_period_seconds);
+        check.set_consecutive_failures(2);
+        check
+    }
+
+    fn shell(value: &str) -> pb::CommandInfo {
+        let mut command = pb::CommandInfo::new();
+        command.set_value(value.to_string());
+        command
+    }
+
+    fn next(outcomes: &Receiver<Outcome>) -> Outcome {
+        outcomes.recv_timeout(Duration::from_secs(10)).unwrap()
+    }
+
+    #[test]
+    fn failing_command_is_unhealthy_after_consecutive_failures() {
+        let (listener, outcomes) = recorder();
+        let mut check = check(0.0);
+        check.set_command(shell("exit 1"));
+
+        let _checker = HealthChecker::start(&check, listener).unwrap();
+
+        assert_eq!(next(&outcomes), Outcome::Changed(false));
+        assert_eq!(next(&outcomes), Outcome::Unhealthy);
+
+        // Checking stops once the task is unhealthy.
+        assert!(outcomes.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn zero_consecutive_failures_never_kills() {
+        let (listener, outcomes) = recorder();
+        let mut check = check(0.0);
+        check.set_consecutive_failures(0);
+        check.set_command(shell("exit 1"));
+
+        let checker = HealthChecker::start(&check, listener).unwrap();
+
+        assert_eq!(next(&outcomes), Outcome::Changed(false));
+        assert!(outcomes.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(checker);
+    }
+
+    #[test]
+    fn timed_out_commands_are_killed_with_their_children() {
+        let pid_file = env::temp_dir().join(format!(
+            "mesos-rust-health-check-{}", process::id()));
+        let (listener, outcomes) = recorder();
+        let mut check = check(0.0);
+        check.set_timeout_seconds(0.2);
+        check.set_consecutive_failures(1);
+        check.set_command(shell(&format!("sleep 30 & echo $! > {}; wait",
+                                         pid_file.display())));
+
+        let _checker = HealthChecker::start(&check, listener).unwrap();
+        assert_eq!(next(&outcomes), Outcome::Changed(false));
+        assert_eq!(next(&outcomes), Outcome::Unhealthy);
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        fs::remove_file(&pid_file).unwrap();
+
+        // Killed, if possibly not reaped yet.
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let alive = || fs::read_to_string(&stat)
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while alive() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!alive());
+    }
+
+    #[test]
+    fn failures_are_ignored_during_grace_period() {
+        let (listener, outcomes) = recorder();
+        let mut check = check(60.0);
+        check.set_command(shell("exit 1"));
+
+        let checker = HealthChecker::start(&check, listener).unwrap();
+        assert!(outcomes.recv_timeout(Duration::from_millis(100)).is_err());
+
+        drop(checker);
+        assert!(outcomes.recv_timeout(Duration::from_secs(10)).is_err());
+    }
+
+    #[test]
+    fn http_check_follows_response_status() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        // Healthy once, then failing.
+        thread::spawn(move || {
+            let statuses = ["200 OK", "503 Service Unavailable"];
+            for (i, stream) in server.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines()
+                    .map(|line| line.unwrap())
+                    .take_while(|line| !line.is_empty())
+                    .collect::<Vec<_>>();
+                assert_eq!(lines.remove(0), "GET /health HTTP/1.0");
+                write!(stream, "HTTP/1.0 {}\r\n\r\n",
+                       statuses[if i == 0 { 0 } else { 1 }]).unwrap();
+            }
+        });
+
+        let (listener, outcomes) = recorder();
+        let mut check = check(0.0);
+        check.mut_http().set_port(port as u32);
+        check.mut_http().set_path("/health".to_string());
+
+        let _checker = HealthChecker::start(&check, listener).unwrap();
+
+        assert_eq!(next(&outcomes), Outcome::Changed(true));
+        assert_eq!(next(&outcomes), Outcome::Changed(false));
+        assert_eq!(next(&outcomes), Outcome::Unhealthy);
+    }
+
+    #[test]
+    fn invalid_checks_are_rejected() {
+        let (listener, _) = recorder();
+        assert!(HealthChecker::start(&check(0.0), listener).is_err());
+
+        let (listener, _) = recorder();
+        let mut check = check(0.0);
+        check.set_command(shell("true"));
+        check.set_timeout_seconds(0.0);
+        let error = HealthChecker::start(&check, listener).err().unwrap();
+        assert_eq!(error.to_string(), "1 violation(s): 'timeout_seconds' \
+                                       is invalid: must be positive");
+    }
+}
@@ -0,0 +1,172 @@
+//! # JSON encoding of Mesos messages.
+//!
+//! Mesos' HTTP endpoints, and most tooling around them, use a JSON form of
+//! the messages in `mesos.proto`:
+//!
+//! ```text
+//! {"name":"cpus","type":"SCALAR","scalar":{"value":1.0},"role":"*"}
+//! ```
+//!
+//! Fields are named as in the `.proto` file, enums are written as their
+//! value names, `bytes` fields as base64 encoded strings and repeated
+//! fields as arrays; only fields that are set are written.  Enum values
+//! this crate does not know are written as numbers.  When reading JSON,
+//! members that are not fields of the message are ignored, so that
+//! documents from newer masters can still be read, integers may also be
+//! given as strings, and enums as numbers.
+//!
+//! Every message in `proto::mesos` implements `JsonMessage`, for use with
+//! `to_value`, `to_string`, `from_value` and `from_str`.  `Json` wraps a
+//! message so that it can be (de)serialized with serde as part of a larger
+//! document.
+//!
+//! This module is only available with the `json` feature.
+
+mod tests;
+mod transcode;
+
+use proto::mesos as pb;
+use protobuf::{self, Message, ProtobufError};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{self, Value};
+use std::error::Error;
+use std::fmt;
+
+use self::transcode::Descriptors;
+
+/// A message of `mesos.proto` with a JSON form.
+pub trait JsonMessage: Message {
+
+    /// The name of the message in `mesos.proto`, e.g. `Resource.DiskInfo`.
+    fn proto_name() -> String;
+}
+
+// The generated types are named after the message and the messages it is
+// nested in, joined by underscores.
+macro_rules! json_messages {
+    ($($message:ident),*) => {
+        $(
+            impl JsonMessage for pb::$message {
+                fn proto_name() -> String {
+                    stringify!($message).replace('_', ".")
+                }
+            }
+        )*
+    }
+}
+
+json_messages!(
+    FrameworkID, OfferID, SlaveID, TaskID, ExecutorID, ContainerID,
+    TimeInfo, DurationInfo, Address, URL, Unavailability, MachineID,
+    MachineInfo, FrameworkInfo, FrameworkInfo_Capability, HealthCheck,
+    HealthCheck_HTTP, CommandInfo, CommandInfo_URI, ExecutorInfo,
+    MasterInfo, SlaveInfo, Value, Value_Scalar, Value_Range, Value_Ranges,
+    Value_Set, Value_Text, Attribute, Resource, Resource_ReservationInfo,
+    Resource_DiskInfo, Resource_DiskInfo_Persistence,
+    Resource_DiskInfo_Source, Resource_DiskInfo_Source_Path,
+    Resource_DiskInfo_Source_Mount, Resource_RevocableInfo,
+    TrafficControlStatistics, IpStatistics, IcmpStatistics, TcpStatistics,
+    UdpStatistics, SNMPStatistics, ResourceStatistics, ResourceUsage,
+    ResourceUsage_Executor, PerfStatistics, Request, Offer, Offer_Operation,
+    Offer_Operation_Launch, Offer_Operation_Reserve,
+    Offer_Operation_Unreserve, Offer_Operation_Create,
+    Offer_Operation_Destroy, InverseOffer, TaskInfo, TaskStatus, Filters,
+    Environment, Environment_Variable, Parameter, Parameters, Credential,
+    Credentials, RateLimit, RateLimits, Image, Image_Appc, Image_Docker,
+    Volume, NetworkInfo, NetworkInfo_IPAddress, ContainerInfo,
+    ContainerInfo_DockerInfo, ContainerInfo_DockerInfo_PortMapping,
+    ContainerInfo_MesosInfo, ContainerStatus, CgroupInfo, CgroupInfo_NetCls,
+    Labels, Label, Port, Ports, DiscoveryInfo, WeightInfo
+);
+
+/// The reasons a message could not be converted to or from JSON.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The input is not valid JSON.
+    Syntax(serde_json::Error),
+
+    /// The message type is not part of `mesos.proto`.
+    UnknownType { name: String },
+
+    /// A field has a value of the wrong type, e.g. an enum value that
+    /// does not exist.  `field` is a path such as `resources[0].type`.
+    Field { field: String, reason: String },
+
+    /// The message could not be encoded or decoded, e.g. because required
+    /// fields are missing.
+    Protobuf { name: String, error: ProtobufError },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonError::Syntax(ref error) =>
+                write!(f, "Invalid JSON: {}", error),
+            JsonError::UnknownType { ref name } =>
+                write!(f, "Unknown message type '{}'", name),
+            JsonError::Field { ref field, ref reason } =>
+                write!(f, "Invalid field '{}': {}", field, reason),
+            JsonError::Protobuf { ref name, ref error } =>
+                write!(f, "Invalid {} message: {}", name, error),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+/// Converts a message to its JSON form.
+pub fn to_value<M: JsonMessage>(message: &M) -> Result<Value, JsonError> {
+    let descriptors = Descriptors::mesos();
+    let descriptor = try!(descriptors.message(&M::proto_name()));
+    let bytes = try!(message.write_to_bytes().map_err(|error| {
+        JsonError::Protobuf { name: M::proto_name(), error: error }
+    }));
+    descriptors.to_json(descriptor, &bytes, "")
+}
+
+/// Converts a message to a compact JSON string.
+pub fn to_string<M: JsonMessage>(message: &M) -> Result<String, JsonError> {
+    let value = try!(to_value(message));
+    serde_json::to_string(&value).map_err(JsonError::Syntax)
+}
+
+/// Reads a message from its JSON form.
+pub fn from_value<M: JsonMessage>(value: &Value) -> Result<M, JsonError> {
+    let descriptors = Descriptors::mesos();
+    let descriptor = try!(descriptors.message(&M::proto_name()));
+    let mut bytes = vec![];
+    try!(descriptors.from_json(descriptor, value, "", &mut bytes));
+    protobuf::parse_from_bytes::<M>(&bytes).map_err(|error| {
+        JsonError::Protobuf { name: M::proto_name(), error: error }
+    })
+}
+
+/// Reads a message from a JSON string.
+pub fn from_str<M: JsonMessage>(json: &str) -> Result<M, JsonError> {
+    let value = try!(serde_json::from_str(json).map_err(JsonError::Syntax));
+    from_value(&value)
+}
+
+/// Wraps a message so that it is (de)serialized in its JSON form by serde,
+/// e.g. as a member of a configuration struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Json<M>(pub M);
+
+impl<M: JsonMessage> Serialize for Json<M> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        let value = try!(to_value(&self.0).map_err(ser::Error::custom));
+        value.serialize(serializer)
+    }
+}
+
+impl<'de, M: JsonMessage> Deserialize<'de> for Json<M> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Json<M>, D::Error> {
+        let value = try!(Value::deserialize(deserializer));
+        from_value(&value).map(Json).map_err(de::Error::custom)
+    }
+}
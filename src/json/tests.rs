@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+
+    use ::json::*;
+    use ::json::transcode::Descriptors;
+    use ::proto::mesos as pb;
+    use serde_json::{self, Value};
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/fixtures/json")
+            .join(name);
+        let mut contents = String::new();
+        File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    // Decodes a fixture and checks that it encodes back to the same JSON.
+    fn round_trip<M: JsonMessage>(name: &str) -> M {
+        let json = fixture(name);
+        let message: M = from_str(&json).unwrap();
+        let expected: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_value(&message).unwrap(), expected);
+        message
+    }
+
+    #[test]
+    fn round_trips_offer() {
+        let offer: pb::Offer = round_trip("offer.json");
+
+        assert_eq!(offer.get_hostname(), "agent-3.example.com");
+        assert_eq!(offer.get_url().get_address().get_port(), 5051);
+        assert_eq!(offer.get_resources().len(), 4);
+
+        let ports = &offer.get_resources()[2];
+        assert_eq!(ports.get_field_type(), pb::Value_Type::RANGES);
+        assert_eq!(ports.get_ranges().get_range()[1].get_end(), 32000);
+
+        let disk = offer.get_resources()[3].get_disk();
+        assert_eq!(disk.get_persistence().get_id(), "pg-data");
+        assert_eq!(disk.get_volume().get_mode(), pb::Volume_Mode::RW);
+        assert_eq!(disk.get_source().get_field_type(),
+                   pb::Resource_DiskInfo_Source_Type::MOUNT);
+        assert_eq!(offer.get_unavailability().get_start().get_nanoseconds(),
+                   1476000000000000000);
+    }
+
+    #[test]
+    fn round_trips_task_status() {
+        let status: pb::TaskStatus = round_trip("task_status.json");
+
+        assert_eq!(status.get_state(), pb::TaskState::TASK_FAILED);
+        assert_eq!(status.get_source(),
+                   pb::TaskStatus_Source::SOURCE_EXECUTOR);
+        assert_eq!(status.get_data(), b"hello, world");
+        assert_eq!(status.get_uuid().len(), 16);
+        assert!(status.has_healthy() && !status.get_healthy());
+        assert_eq!(status.get_timestamp(), 1476281234.56789);
+    }
+
+    #[test]
+    fn round_trips_framework_and_task_info() {
+        let framework: pb::FrameworkInfo = round_trip("framework_info.json");
+        assert_eq!(framework.get_capabilities()[1].get_field_type(),
+                   pb::FrameworkInfo_Capability_Type::TASK_KILLING_STATE);
+
+        let task: pb::TaskInfo = round_trip("task_info.json");
+        assert_eq!(task.get_container().get_docker().get_network(),
+                   pb::ContainerInfo_DockerInfo_Network::BRIDGE);
+        assert_eq!(task.get_health_check().get_http().get_statuses(),
+                   &[200, 204]);
+        assert_eq!(task.get_data(), b"{\"version\": 42}");
+    }
+
+    #[test]
+    fn writes_only_fields_that_are_set() {
+        let mut resource = pb::Resource::new();
+        resource.set_name("cpus".to_string());
+        resource.set_field_type(pb::Value_Type::SCALAR);
+        resource.mut_scalar().set_value(1.0);
+
+        assert_eq!(
+            to_string(&resource).unwrap(),
+            r#"{"name":"cpus","scalar":{"value":1.0},"type":"SCALAR"}"#);
+    }
+
+    #[test]
+    fn reads_lenient_json() {
+        let resource: pb::Resource = from_str(r#"{
+            "name": "ports",
+            "type": "RANGES",
+            "ranges": {"range": [{"begin": "31000", "end": 31001}]},
+            "allocation_info": {"role": "web"},
+            "role": null
+        }"#).unwrap();
+
+        let range = &resource.get_ranges().get_range()[0];
+        assert_eq!((range.get_begin(), range.get_end()), (31000, 31001));
+        assert!(!resource.has_role());
+    }
+
+    #[test]
+    fn reports_invalid_fields() {
+        fn error<M: JsonMessage>(json: &str) -> String {
+            format!("{}", from_str::<M>(json).unwrap_err())
+        }
+
+        assert_eq!(error::<pb::Resource>(r#"{"name": "a", "type": "BOGUS"}"#),
+                   "Invalid field 'type': unknown Type value 'BOGUS'");
+        assert_eq!(error::<pb::Address>(r#"{"port": 70000000000}"#),
+                   "Invalid field 'port': integer out of range");
+        assert_eq!(error::<pb::Labels>(r#"{"labels": {"key": "a"}}"#),
+                   "Invalid field 'labels': expected an array");
+        assert_eq!(error::<pb::Offer>(r#"{"hostname": "agent"}"#),
+                   "Invalid field 'id': required field is missing");
+
+        let mut offer: Value = serde_json::from_str(&fixture("offer.json"))
+            .unwrap();
+        offer["resources"][1]["scalar"] = Value::from("lots");
+        match from_value::<pb::Offer>(&offer) {
+            Err(JsonError::Field { ref field, .. }) =>
+                assert_eq!(field, "resources[1].scalar"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        match from_str::<pb::Offer>("{") {
+            Err(JsonError::Syntax(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn serde_wrapper() {
+        let resources: Vec<Json<pb::Resource>> = serde_json::from_str(r#"[
+            {"name": "cpus", "type": "SCALAR", "scalar": {"value": 2}},
+            {"name": "mem", "type": "SCALAR", "scalar": {"value": 256}}
+        ]"#).unwrap();
+        assert_eq!(resources[1].0.get_scalar().get_value(), 256.0);
+
+        assert_eq!(
+            serde_json::to_string(&resources[0]).unwrap(),
+            r#"{"name":"cpus","scalar":{"value":2.0},"type":"SCALAR"}"#);
+
+        let error = serde_json::from_str::<Json<pb::Resource>>(
+            r#"{"name": "cpus", "type": true}"#).unwrap_err();
+        assert!(format!("{}", error).contains("expected an enum name"));
+    }
+
+    #[test]
+    fn enums_may_be_numbers() {
+        let ports: pb::Resource = from_str(
+            r#"{"name": "ports", "type": 1}"#).unwrap();
+        assert_eq!(ports.get_field_type(), pb::Value_Type::RANGES);
+
+        // Values added to the enum after this crate was built are kept as
+        // numbers, and read back as such.
+        let descriptors = Descriptors::mesos();
+        let resource = descriptors.message("Resource").unwrap();
+        let gpus: Value = serde_json::from_str(
+            r#"{"name": "gpus", "type": 42}"#).unwrap();
+        let mut bytes = vec![];
+        descriptors.from_json(resource, &gpus, "", &mut bytes).unwrap();
+        assert_eq!(descriptors.to_json(resource, &bytes, "").unwrap(), gpus);
+    }
+}
@@ -0,0 +1,535 @@
+//! Conversion between the protobuf wire format and JSON, driven by the
+//! descriptors that are compiled into `proto::mesos`.
+
+use base64;
+use json::JsonError;
+use proto::mesos;
+use protobuf::lazy::{self, Lazy};
+use protobuf::descriptor::{DescriptorProto, EnumDescriptorProto,
+                           FieldDescriptorProto};
+use protobuf::descriptor::FieldDescriptorProto_Label as Label;
+use protobuf::descriptor::FieldDescriptorProto_Type as Type;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+/// The messages and enums of `mesos.proto`, by fully qualified name
+/// (e.g. `.mesos.Resource.DiskInfo`).
+pub struct Descriptors {
+    package: String,
+    messages: HashMap<String, &'static DescriptorProto>,
+    enums: HashMap<String, &'static EnumDescriptorProto>,
+}
+
+impl Descriptors {
+
+    /// The descriptors of `mesos.proto`, indexed on first use.
+    pub fn mesos() -> &'static Descriptors {
+        static mut DESCRIPTORS: Lazy<Descriptors> = Lazy {
+            lock: lazy::ONCE_INIT,
+            ptr: 0 as *const Descriptors,
+        };
+        unsafe { DESCRIPTORS.get(Descriptors::index_mesos) }
+    }
+
+    fn index_mesos() -> Descriptors {
+        let file = mesos::file_descriptor_proto();
+        let package = format!(".{}", file.get_package());
+
+        let mut descriptors = Descriptors {
+            package: package.clone(),
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+        };
+        for enum_type in file.get_enum_type() {
+            descriptors.enums.insert(
+                format!("{}.{}", package, enum_type.get_name()), enum_type);
+        }
+        for message in file.get_message_type() {
+            descriptors.add(&package, message);
+        }
+        descriptors
+    }
+
+    fn add(&mut self, scope: &str, message: &'static DescriptorProto) {
+        let name = format!("{}.{}", scope, message.get_name());
+        for enum_type in message.get_enum_type() {
+            self.enums.insert(
+                format!("{}.{}", name, enum_type.get_name()), enum_type);
+        }
+        for nested in message.get_nested_type() {
+            self.add(&name, nested);
+        }
+        self.messages.insert(name, message);
+    }
+
+    /// Looks up a message by its name relative to the package, e.g.
+    /// `Resource.DiskInfo`.
+    pub fn message(
+        &self,
+        name: &str
+    ) -> Result<&'static DescriptorProto, JsonError> {
+        self.message_type(&format!("{}.{}", self.package, name))
+    }
+
+    fn message_type(
+        &self,
+        type_name: &str
+    ) -> Result<&'static DescriptorProto, JsonError> {
+        self.messages.get(type_name).cloned().ok_or_else(|| {
+            JsonError::UnknownType { name: type_name.to_string() }
+        })
+    }
+
+    fn enum_type(
+        &self,
+        type_name: &str
+    ) -> Result<&'static EnumDescriptorProto, JsonError> {
+        self.enums.get(type_name).cloned().ok_or_else(|| {
+            JsonError::UnknownType { name: type_name.to_string() }
+        })
+    }
+
+    /// Converts a serialized message to a JSON object.  Unknown fields are
+    /// dropped.
+    pub fn to_json(
+        &self,
+        message: &DescriptorProto,
+        bytes: &[u8],
+        path: &str
+    ) -> Result<Value, JsonError> {
+        let mut values: HashMap<i32, Vec<Value>> = HashMap::new();
+        let mut input = Reader { bytes: bytes };
+
+        while !input.bytes.is_empty() {
+            let key = try!(input.varint().map_err(|e| invalid(path, e)));
+            let number = (key >> 3) as i32;
+            let wire_type = key & 7;
+
+            let field = match message.get_field().iter()
+                .find(|field| field.get_number() == number) {
+                Some(field) => field,
+                None => {
+                    try!(input.skip(wire_type)
+                         .map_err(|e| invalid(path, e)));
+                    continue;
+                },
+            };
+
+            let path = child(path, field.get_name());
+            let values = values.entry(number).or_insert(vec![]);
+            let expected = wire_type_of(field.get_field_type());
+
+            if wire_type == expected {
+                values.push(try!(self.read(field, &mut input, &path)));
+            } else if wire_type == LENGTH_DELIMITED {
+                // A packed repeated field.
+                let bytes = try!(input.length_delimited()
+                                 .map_err(|e| invalid(&path, e)));
+                let mut packed = Reader { bytes: bytes };
+                while !packed.bytes.is_empty() {
+                    values.push(try!(self.read(field, &mut packed, &path)));
+                }
+            } else {
+                return Err(invalid(&path, "unexpected wire type"));
+            }
+        }
+
+        let mut object = Map::new();
+        for field in message.get_field() {
+            if let Some(mut values) = values.remove(&field.get_number()) {
+                let value = if field.get_label() == Label::LABEL_REPEATED {
+                    Value::Array(values)
+                } else {
+                    // The last occurrence of a singular field wins.
+                    values.pop().unwrap()
+                };
+                object.insert(field.get_name().to_string(), value);
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn read(
+        &self,
+        field: &FieldDescriptorProto,
+        input: &mut Reader,
+        path: &str
+    ) -> Result<Value, JsonError> {
+        match field.get_field_type() {
+            Type::TYPE_MESSAGE => {
+                let message = try!(self.message_type(field.get_type_name()));
+                let bytes = try!(input.length_delimited()
+                                 .map_err(|e| invalid(path, e)));
+                self.to_json(message, bytes, path)
+            },
+            Type::TYPE_ENUM => {
+                let enum_type = try!(self.enum_type(field.get_type_name()));
+                let number = try!(input.varint()
+                                  .map_err(|e| invalid(path, e))) as i32;
+                // Values added to the enum after this crate was built are
+                // kept as numbers.
+                Ok(match enum_type.get_value().iter()
+                    .find(|value| value.get_number() == number) {
+                    Some(value) => Value::from(value.get_name()),
+                    None => Value::from(number),
+                })
+            },
+            field_type => input.scalar(field_type)
+                .map_err(|e| invalid(path, e)),
+        }
+    }
+
+    /// Serializes a JSON object as `message`.  Object members that are not
+    /// fields of the message, and `null` members, are ignored; required
+    /// fields must be present.
+    pub fn from_json(
+        &self,
+        message: &DescriptorProto,
+        value: &Value,
+        path: &str,
+        output: &mut Vec<u8>
+    ) -> Result<(), JsonError> {
+        let object = match *value {
+            Value::Object(ref object) => object,
+            _ => return Err(invalid(path, "expected an object")),
+        };
+
+        for field in message.get_field() {
+            let path = child(path, field.get_name());
+            let value = match object.get(field.get_name()) {
+                None | Some(&Value::Null) => {
+                    if field.get_label() == Label::LABEL_REQUIRED {
+                        return Err(invalid(&path,
+                                           "required field is missing"));
+                    }
+                    continue;
+                },
+                Some(value) => value,
+            };
+
+            if field.get_label() == Label::LABEL_REPEATED {
+                let values = match *value {
+                    Value::Array(ref values) => values,
+                    _ => return Err(invalid(&path, "expected an array")),
+                };
+                for (i, value) in values.iter().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    try!(self.write(field, value, &path, output));
+                }
+            } else {
+                try!(self.write(field, value, &path, output));
+            }
+        }
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        path: &str,
+        output: &mut Vec<u8>
+    ) -> Result<(), JsonError> {
+        let field_type = field.get_field_type();
+        let key = (field.get_number() as u64) << 3
+            | wire_type_of(field_type);
+        write_varint(key, output);
+
+        match field_type {
+            Type::TYPE_DOUBLE => {
+                let value = try!(float(value, path));
+                write_fixed64(value.to_bits(), output);
+            },
+            Type::TYPE_FLOAT => {
+                let value = try!(float(value, path)) as f32;
+                write_fixed32(value.to_bits(), output);
+            },
+            Type::TYPE_INT64 => {
+                let value = try!(signed(value, path, i64::min_value(),
+                                        i64::max_value()));
+                write_varint(value as u64, output);
+            },
+            Type::TYPE_INT32 => {
+                let value = try!(signed(value, path, i32::min_value() as i64,
+                                        i32::max_value() as i64));
+                write_varint(value as u64, output);
+            },
+            Type::TYPE_SINT64 | Type::TYPE_SINT32 => {
+                let (min, max) = if field_type == Type::TYPE_SINT32 {
+                    (i32::min_value() as i64, i32::max_value() as i64)
+                } else {
+                    (i64::min_value(), i64::max_value())
+                };
+                let value = try!(signed(value, path, min, max));
+                write_varint(((value << 1) ^ (value >> 63)) as u64, output);
+            },
+            Type::TYPE_UINT64 => {
+                let value = try!(unsigned(value, path, u64::max_value()));
+                write_varint(value, output);
+            },
+            Type::TYPE_UINT32 => {
+                let value = try!(unsigned(value, path,
+                                          u32::max_value() as u64));
+                write_varint(value, output);
+            },
+            Type::TYPE_FIXED64 => {
+                let value = try!(unsigned(value, path, u64::max_value()));
+                write_fixed64(value, output);
+            },
+            Type::TYPE_SFIXED64 => {
+                let value = try!(signed(value, path, i64::min_value(),
+                                        i64::max_value()));
+                write_fixed64(value as u64, output);
+            },
+            Type::TYPE_FIXED32 => {
+                let value = try!(unsigned(value, path,
+                                          u32::max_value() as u64));
+                write_fixed32(value as u32, output);
+            },
+            Type::TYPE_SFIXED32 => {
+                let value = try!(signed(value, path, i32::min_value() as i64,
+                                        i32::max_value() as i64));
+                write_fixed32(value as i32 as u32, output);
+            },
+            Type::TYPE_BOOL => match *value {
+                Value::Bool(value) => write_varint(value as u64, output),
+                _ => return Err(invalid(path, "expected a boolean")),
+            },
+            Type::TYPE_ENUM => {
+                let enum_type = try!(self.enum_type(field.get_type_name()));
+                let number = match *value {
+                    Value::String(ref name) => {
+                        match enum_type.get_value().iter()
+                            .find(|value| value.get_name() == name) {
+                            Some(value) => value.get_number() as i64,
+                            None => return Err(invalid(
+                                path,
+                                &format!("unknown {} value '{}'",
+                                         enum_type.get_name(), name))),
+                        }
+                    },
+                    // As written for values unknown to this crate.
+                    Value::Number(_) =>
+                        try!(signed(value, path, i32::min_value() as i64,
+                                    i32::max_value() as i64)),
+                    _ => return Err(invalid(
+                        path, "expected an enum name or number")),
+                };
+                write_varint(number as u64, output);
+            },
+            Type::TYPE_STRING => match *value {
+                Value::String(ref value) =>
+                    write_bytes(value.as_bytes(), output),
+                _ => return Err(invalid(path, "expected a string")),
+            },
+            Type::TYPE_BYTES => {
+                let decoded = match *value {
+                    Value::String(ref value) => base64::decode(value).ok(),
+                    _ => None,
+                };
+                match decoded {
+                    Some(bytes) => write_bytes(&bytes, output),
+                    None => return Err(invalid(path, "expected a base64 \
+                                                      encoded string")),
+                }
+            },
+            Type::TYPE_MESSAGE => {
+                let message = try!(self.message_type(field.get_type_name()));
+                let mut bytes = vec![];
+                try!(self.from_json(message, value, path, &mut bytes));
+                write_bytes(&bytes, output);
+            },
+            Type::TYPE_GROUP =>
+                return Err(invalid(path, "groups are not supported")),
+        }
+        Ok(())
+    }
+}
+
+// A cursor over serialized fields.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], &'static str> {
+        if length > self.bytes.len() {
+            return Err("truncated message");
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64, &'static str> {
+        let mut value = 0;
+        for shift in 0..10 {
+            let byte = try!(self.take(1))[0];
+            value |= ((byte & 0x7f) as u64) << (shift * 7);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("malformed varint")
+    }
+
+    fn fixed64(&mut self) -> Result<u64, &'static str> {
+        let bytes = try!(self.take(8));
+        Ok(bytes.iter().rev().fold(0, |value, &b| value << 8 | b as u64))
+    }
+
+    fn fixed32(&mut self) -> Result<u32, &'static str> {
+        let bytes = try!(self.take(4));
+        Ok(bytes.iter().rev().fold(0, |value, &b| value << 8 | b as u32))
+    }
+
+    fn length_delimited(&mut self) -> Result<&'a [u8], &'static str> {
+        let length = try!(self.varint());
+        self.take(length as usize)
+    }
+
+    fn skip(&mut self, wire_type: u64) -> Result<(), &'static str> {
+        match wire_type {
+            VARINT => self.varint().map(|_| ()),
+            FIXED64 => self.take(8).map(|_| ()),
+            LENGTH_DELIMITED => self.length_delimited().map(|_| ()),
+            FIXED32 => self.take(4).map(|_| ()),
+            _ => Err("unsupported wire type"),
+        }
+    }
+
+    // Reads a field of any type but messages and enums.
+    fn scalar(&mut self, field_type: Type) -> Result<Value, &'static str> {
+        let value = match field_type {
+            Type::TYPE_DOUBLE => double(f64::from_bits(try!(self.fixed64()))),
+            Type::TYPE_FLOAT =>
+                double(f32::from_bits(try!(self.fixed32())) as f64),
+            Type::TYPE_INT64 => Value::from(try!(self.varint()) as i64),
+            Type::TYPE_UINT64 => Value::from(try!(self.varint())),
+            Type::TYPE_INT32 => Value::from(try!(self.varint()) as i32),
+            Type::TYPE_UINT32 => Value::from(try!(self.varint()) as u32),
+            Type::TYPE_SINT32 | Type::TYPE_SINT64 => {
+                let value = try!(self.varint());
+                Value::from((value >> 1) as i64 ^ -((value & 1) as i64))
+            },
+            Type::TYPE_FIXED64 => Value::from(try!(self.fixed64())),
+            Type::TYPE_SFIXED64 => Value::from(try!(self.fixed64()) as i64),
+            Type::TYPE_FIXED32 => Value::from(try!(self.fixed32())),
+            Type::TYPE_SFIXED32 => Value::from(try!(self.fixed32()) as i32),
+            Type::TYPE_BOOL => Value::Bool(try!(self.varint()) != 0),
+            Type::TYPE_STRING => {
+                let bytes = try!(self.length_delimited());
+                match String::from_utf8(bytes.to_vec()) {
+                    Ok(string) => Value::String(string),
+                    Err(_) => return Err("invalid UTF-8"),
+                }
+            },
+            Type::TYPE_BYTES =>
+                Value::String(base64::encode(try!(self.length_delimited()))),
+            _ => return Err("unsupported field type"),
+        };
+        Ok(value)
+    }
+}
+
+fn wire_type_of(field_type: Type) -> u64 {
+    match field_type {
+        Type::TYPE_DOUBLE | Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 =>
+            FIXED64,
+        Type::TYPE_FLOAT | Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 =>
+            FIXED32,
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE |
+        Type::TYPE_GROUP =>
+            LENGTH_DELIMITED,
+        _ => VARINT,
+    }
+}
+
+fn child(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn invalid(path: &str, reason: &str) -> JsonError {
+    JsonError::Field { field: path.to_string(), reason: reason.to_string() }
+}
+
+// JSON has no representation for NaN or infinities.
+fn double(value: f64) -> Value {
+    Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+}
+
+fn float(value: &Value, path: &str) -> Result<f64, JsonError> {
+    let parsed = match *value {
+        Value::Number(ref number) => number.as_f64(),
+        Value::String(ref string) => string.parse().ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| invalid(path, "expected a number"))
+}
+
+// Integers may also be given as strings, since not every JSON
+// implementation can represent 64 bit integers as numbers.
+fn signed(
+    value: &Value,
+    path: &str,
+    min: i64,
+    max: i64
+) -> Result<i64, JsonError> {
+    let parsed = match *value {
+        Value::Number(ref number) => number.as_i64(),
+        Value::String(ref string) => string.parse().ok(),
+        _ => None,
+    };
+    match parsed {
+        Some(value) if value >= min && value <= max => Ok(value),
+        Some(_) => Err(invalid(path, "integer out of range")),
+        None => Err(invalid(path, "expected an integer")),
+    }
+}
+
+fn unsigned(value: &Value, path: &str, max: u64) -> Result<u64, JsonError> {
+    let parsed = match *value {
+        Value::Number(ref number) => number.as_u64(),
+        Value::String(ref string) => string.parse().ok(),
+        _ => None,
+    };
+    match parsed {
+        Some(value) if value <= max => Ok(value),
+        Some(_) => Err(invalid(path, "integer out of range")),
+        None => Err(invalid(path, "expected a non-negative integer")),
+    }
+}
+
+fn write_varint(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_fixed64(value: u64, output: &mut Vec<u8>) {
+    for i in 0..8 {
+        output.push((value >> (i * 8)) as u8);
+    }
+}
+
+fn write_fixed32(value: u32, output: &mut Vec<u8>) {
+    for i in 0..4 {
+        output.push((value >> (i * 8)) as u8);
+    }
+}
+
+fn write_bytes(bytes: &[u8], output: &mut Vec<u8>) {
+    write_varint(bytes.len() as u64, output);
+    output.extend_from_slice(bytes);
+}
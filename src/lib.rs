@@ -1,10 +1,54 @@
 extern crate protobuf;
 extern crate libc;
+#[macro_use]
+extern crate log;
+#[cfg(feature = "json")]
+extern crate base64;
+#[cfg(feature = "async")]
+extern crate futures;
+#[cfg(feature = "fetcher")]
+extern crate bzip2;
+#[cfg(feature = "fetcher")]
+extern crate flate2;
+#[cfg(feature = "json")]
+extern crate serde;
+#[cfg(feature = "spec")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "spec")]
+extern crate serde_yaml;
+#[cfg(feature = "fetcher")]
+extern crate tar;
+#[cfg(feature = "fetcher")]
+extern crate zip;
 
 // pub mod http;
+#[cfg(feature = "async")]
+pub mod async_scheduler;
+pub mod builder;
+pub mod call_queue;
+pub mod channel_scheduler;
+pub mod command_executor;
+pub mod event;
 pub mod executor;
+#[cfg(feature = "fetcher")]
+pub mod fetcher;
+pub mod health_check;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod proto;
+pub mod reconciler;
+pub mod recording;
 pub mod messages;
+#[cfg(any(feature = "native", feature = "dlopen"))]
 pub mod native;
 // pub mod pure;
 pub mod scheduler;
+#[cfg(feature = "spec")]
+pub mod spec;
+pub mod state;
+pub mod statistics;
+pub mod task_registry;
+pub mod testing;
@@ -0,0 +1,112 @@
+//! Framing of protobuf message vectors passed to the native shim.
+//!
+//! A vector is encoded as the concatenation of its messages, each preceded
+//! by its length in bytes as a little-endian `u64`:
+//!
+//! ```text
+//! +----------------+-----------+----------------+-----------+---
+//! | length 0 (LE)  | message 0 | length 1 (LE)  | message 1 | ...
+//! +----------------+-----------+----------------+-----------+---
+//! ```
+//!
+//! An empty vector is an empty buffer.  `utils::decodeFrames` in
+//! `resources/mesos-c/utils.hpp` decodes the same format for the shim, and
+//! must be kept in sync with this module; `decode` is the reference the
+//! tests hold it to, through `resources/mesos-c/framing_check.cpp`.
+
+use protobuf::{self, Message};
+use std::error::Error;
+use std::fmt;
+
+/// The size of the length prefix of each message.
+pub const LENGTH_SIZE: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FramingError {
+    /// A message could not be serialized, e.g. for a missing required
+    /// field.
+    Encode { index: usize, error: String },
+    /// The buffer ends inside the length prefix at `offset`.
+    TruncatedLength { offset: usize },
+    /// The length prefix at `offset` runs past the end of the buffer.
+    TruncatedMessage { offset: usize, length: u64 },
+    /// A message could not be parsed.
+    Decode { index: usize, error: String },
+}
+
+impl fmt::Display for FramingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FramingError::Encode { index, ref error } =>
+                write!(f, "Failed to encode message {}: {}", index, error),
+            FramingError::TruncatedLength { offset } =>
+                write!(f, "Truncated length prefix at offset {}", offset),
+            FramingError::TruncatedMessage { offset, length } =>
+                write!(f, "Message of {} bytes at offset {} runs past the \
+                           end of the buffer", length, offset),
+            FramingError::Decode { index, ref error } =>
+                write!(f, "Failed to decode message {}: {}", index, error),
+        }
+    }
+}
+
+impl Error for FramingError {}
+
+/// Encodes `messages` into a single length-prefixed buffer.
+pub fn encode<M: Message>(messages: &[&M]) -> Result<Vec<u8>, FramingError> {
+    let mut data = vec![];
+    for (index, message) in messages.iter().enumerate() {
+        let bytes = try!(message.write_to_bytes().map_err(|e| {
+            FramingError::Encode { index: index, error: e.to_string() }
+        }));
+        data.extend_from_slice(&encode_length(bytes.len() as u64));
+        data.extend_from_slice(&bytes);
+    }
+    Ok(data)
+}
+
+/// Decodes a buffer produced by `encode`, checking every length prefix
+/// against the bytes that remain.
+pub fn decode<M: Message>(data: &[u8]) -> Result<Vec<M>, FramingError> {
+    let mut messages = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        let remaining = &data[offset..];
+        if remaining.len() < LENGTH_SIZE {
+            return Err(FramingError::TruncatedLength { offset: offset });
+        }
+
+        let length = decode_length(&remaining[..LENGTH_SIZE]);
+        let body = &remaining[LENGTH_SIZE..];
+        if length > body.len() as u64 {
+            return Err(FramingError::TruncatedMessage {
+                offset: offset,
+                length: length,
+            });
+        }
+
+        let length = length as usize;
+        let message = try!(protobuf::parse_from_bytes::<M>(&body[..length])
+            .map_err(|e| FramingError::Decode {
+                index: messages.len(),
+                error: e.to_string(),
+            }));
+        messages.push(message);
+        offset += LENGTH_SIZE + length;
+    }
+    Ok(messages)
+}
+
+fn encode_length(length: u64) -> [u8; LENGTH_SIZE] {
+    let mut bytes = [0; LENGTH_SIZE];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (length >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+fn decode_length(bytes: &[u8]) -> u64 {
+    bytes.iter()
+        .enumerate()
+        .fold(0, |length, (i, &byte)| length | (byte as u64) << (8 * i))
+}
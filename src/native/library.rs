@@ -0,0 +1,176 @@
+//! Loading of the native shim at runtime, for deployments where the
+//! version and location of Mesos are only known when the program runs.
+//!
+//! The shim in `resources/mesos-c` is built as a shared library by the
+//! build script when the `dlopen` feature is enabled and Mesos is found, and
+//! placed next to the binaries, e.g. `target/release/libmesos-c.so`.  Only
+//! the shim is loaded here: `libmesos` is its dependency, and is found by
+//! the dynamic linker wherever the machine running the program has it.
+
+use libc::{self, c_void};
+use native::mesos_c::SchedulerShim;
+use std::env;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// The environment variable naming the library loaded by
+/// `Library::open_default`.
+pub const LIBRARY_PATH_VAR: &'static str = "MESOS_C_LIBRARY";
+
+/// The library loaded by `Library::open_default` when `MESOS_C_LIBRARY` is
+/// not set, from the directory of the executable or else from the dynamic
+/// linker's search path.
+pub const DEFAULT_LIBRARY: &'static str = "libmesos-c.so";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    /// The library, or one of its dependencies such as `libmesos`, could
+    /// not be loaded.
+    Library { path: PathBuf, reason: String },
+    /// The library does not export a function of the shim.
+    Symbol { path: PathBuf, symbol: &'static str, reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Library { ref path, ref reason } =>
+                write!(f, "Failed to load '{}': {}", path.display(), reason),
+            LoadError::Symbol { ref path, symbol, ref reason } =>
+                write!(f, "Failed to find '{}' in '{}': {}",
+                       symbol, path.display(), reason),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// A native shim loaded with `dlopen`.
+///
+/// The library is never unloaded: `libmesos` starts threads that outlive
+/// the drivers using it.
+#[derive(Clone)]
+pub struct Library {
+    path: PathBuf,
+    shim: SchedulerShim,
+}
+
+impl Library {
+
+    /// Loads the library at `path` and resolves the functions of the shim.
+    /// A path without a slash is looked up like the dynamic linker does,
+    /// e.g. in `LD_LIBRARY_PATH`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Library, LoadError> {
+        let path = path.as_ref().to_path_buf();
+        let handle = try!(dlopen(&path));
+
+        let shim = SchedulerShim {
+            init: try!(symbol(handle, &path, "scheduler_init")),
+            destroy: try!(symbol(handle, &path, "scheduler_destroy")),
+            start: try!(symbol(handle, &path, "scheduler_start")),
+            stop: try!(symbol(handle, &path, "scheduler_stop")),
+            abort: try!(symbol(handle, &path, "scheduler_abort")),
+            join: try!(symbol(handle, &path, "scheduler_join")),
+            request_resources:
+                try!(symbol(handle, &path, "scheduler_requestResources")),
+            launch_tasks:
+                try!(symbol(handle, &path, "scheduler_launchTasks")),
+            decline_offer:
+                try!(symbol(handle, &path, "scheduler_declineOffer")),
+            revive_offers:
+                try!(symbol(handle, &path, "scheduler_reviveOffers")),
+            kill_task: try!(symbol(handle, &path, "scheduler_killTask")),
+            reconcile_tasks:
+                try!(symbol(handle, &path, "scheduler_reconcileTasks")),
+            send_framework_message:
+                try!(symbol(handle, &path, "scheduler_sendFrameworkMessage")),
+        };
+
+        Ok(Library { path: path, shim: shim })
+    }
+
+    /// Loads the library named by `MESOS_C_LIBRARY`, or `libmesos-c.so`
+    /// from the directory of the executable if it is there, or else from the
+    /// dynamic linker's search path.
+    pub fn open_default() -> Result<Library, LoadError> {
+        if let Some(path) = env::var_os(LIBRARY_PATH_VAR) {
+            return Library::open(path);
+        }
+
+        let beside_executable = env::current_exe().ok()
+            .and_then(|executable| {
+                executable.parent().map(|dir| dir.join(DEFAULT_LIBRARY))
+            })
+            .and_then(|path| if path.is_file() { Some(path) } else { None });
+        match beside_executable {
+            Some(path) => Library::open(path),
+            None => Library::open(DEFAULT_LIBRARY),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn shim(&self) -> SchedulerShim {
+        self.shim
+    }
+}
+
+fn dlopen(path: &Path) -> Result<*mut c_void, LoadError> {
+    let native_path = try!(CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| LoadError::Library {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        }));
+
+    // Global, so that libraries loaded later can use the symbols of
+    // libmesos too.
+    let handle = unsafe {
+        libc::dlopen(native_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL)
+    };
+    if handle.is_null() {
+        return Err(LoadError::Library {
+            path: path.to_path_buf(),
+            reason: dlerror(),
+        });
+    }
+    Ok(handle)
+}
+
+// Resolves a function of the shim.  `F` must be the function pointer type
+// of the symbol.
+fn symbol<F: Copy>(
+    handle: *mut c_void,
+    path: &Path,
+    name: &'static str
+) -> Result<F, LoadError> {
+    assert_eq!(mem::size_of::<F>(), mem::size_of::<*mut c_void>());
+
+    let native_name = CString::new(name).unwrap();
+
+    // Clear any earlier error, so that the one reported is from `dlsym`.
+    unsafe { libc::dlerror() };
+    let address = unsafe { libc::dlsym(handle, native_name.as_ptr()) };
+    if address.is_null() {
+        return Err(LoadError::Symbol {
+            path: path.to_path_buf(),
+            symbol: name,
+            reason: dlerror(),
+        });
+    }
+    Ok(unsafe { mem::transmute_copy(&address) })
+}
+
+fn dlerror() -> String {
+    let message = unsafe { libc::dlerror() };
+    if message.is_null() {
+        "unknown error".to_string()
+    } else {
+        unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+    }
+}
@@ -2,6 +2,7 @@
 #![allow(non_camel_case_types)]
 
 use libc::{c_void, size_t};
+use scheduler::DecodeError;
 use std::option::Option;
 use std::slice;
 use protobuf;
@@ -41,14 +42,41 @@ impl ProtobufObj {
         unsafe { *raw }
     }
 
-    pub fn merge(raw_ptr: *mut ProtobufObj, proto: &mut protobuf::Message) {
-        let native = ProtobufObj::from_raw_ptr(raw_ptr);
-        proto.merge_from_bytes(
-            native.to_bytes())
-            .unwrap();
+    /// Merges the message at `raw_ptr` into `proto`; `message_type` names
+    /// the message in the error, e.g. `Offer`.
+    pub fn merge(
+        raw_ptr: *mut ProtobufObj,
+        proto: &mut protobuf::Message,
+        message_type: &'static str
+    ) -> Result<(), DecodeError> {
+        if raw_ptr.is_null() {
+            return Err(DecodeError {
+                message_type: message_type.to_string(),
+                bytes: vec![],
+                reason: "no data".to_string(),
+            });
+        }
+        ProtobufObj::from_raw_ptr(raw_ptr).merge_into(proto, message_type)
+    }
+
+    /// Merges the message in this buffer into `proto`.
+    pub fn merge_into(
+        &self,
+        proto: &mut protobuf::Message,
+        message_type: &'static str
+    ) -> Result<(), DecodeError> {
+        let bytes = self.to_bytes();
+        proto.merge_from_bytes(bytes).map_err(|e| DecodeError {
+            message_type: message_type.to_string(),
+            bytes: bytes.to_vec(),
+            reason: e.to_string(),
+        })
     }
 
     pub fn to_bytes(&self) -> &[u8] {
+        if self.data.is_null() {
+            return &[];
+        }
         unsafe {
             slice::from_raw_parts(
                 self.data as *const u8,
@@ -89,7 +117,7 @@ pub type scheduler_frameworkMessageCallBack_t =
     Option<extern "C" fn(arg1: SchedulerDriverPtr,
                                         arg2: *mut ProtobufObj,
                                         arg3: *mut ProtobufObj,
-                                        arg4: *const ::libc::c_char) -> ()>;
+                                        arg4: *mut ProtobufObj) -> ()>;
 
 pub type scheduler_slaveLostCallBack_t =
     Option<extern "C" fn(arg1: SchedulerDriverPtr,
@@ -103,7 +131,7 @@ pub type scheduler_executorLostCallBack_t =
 
 pub type scheduler_errorCallBack_t =
     Option<extern "C" fn(arg1: SchedulerDriverPtr,
-                                        arg2: *const ::libc::c_char) -> ()>;
+                                        arg2: *mut ProtobufObj) -> ()>;
 
 #[repr(C)]
 #[derive(Copy)]
@@ -222,6 +250,7 @@ pub type SchedulerDriverStatus = ::libc::c_int;
 pub type ExecutorDriverStatus = ::libc::c_int;
 
 // #[link(name = "mesos")]
+#[cfg(feature = "native")]
 extern "C" {
     pub fn scheduler_launchTasks(
         driver: SchedulerDriverPtr,
@@ -267,6 +296,10 @@ extern "C" {
         slaveId: *mut ProtobufObj,
         data: *const ::libc::c_char) -> SchedulerDriverStatus;
 
+    pub fn scheduler_reconcileTasks(
+        driver: SchedulerDriverPtr,
+        statuses: *mut ProtobufObj) -> SchedulerDriverStatus;
+
     pub fn scheduler_init(
         callbacks: *mut SchedulerCallBacks,
         payload: *mut c_void,
@@ -308,3 +341,73 @@ extern "C" {
         driver: *mut c_void,
         executor: *mut c_void) -> ();
 }
+
+/// The entry points of the native shim used by the scheduler driver,
+/// either linked in or resolved from a library loaded at runtime.
+#[derive(Clone, Copy)]
+pub struct SchedulerShim {
+    pub init: unsafe extern "C" fn(
+        callbacks: *mut SchedulerCallBacks,
+        payload: *mut c_void,
+        framework: *mut ProtobufObj,
+        master: *const ::libc::c_char) -> SchedulerPtrPair,
+    pub destroy: unsafe extern "C" fn(
+        driver: *mut c_void,
+        scheduler: *mut c_void) -> (),
+    pub start: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr) -> SchedulerDriverStatus,
+    pub stop: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        failover: ::libc::c_int) -> SchedulerDriverStatus,
+    pub abort: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr) -> SchedulerDriverStatus,
+    pub join: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr) -> SchedulerDriverStatus,
+    pub request_resources: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        requestsData: *mut ProtobufObj) -> SchedulerDriverStatus,
+    pub launch_tasks: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        offerId: *mut ProtobufObj,
+        tasks: *mut ProtobufObj,
+        filters: *mut ProtobufObj) -> SchedulerDriverStatus,
+    pub decline_offer: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        offerId: *mut ProtobufObj,
+        filters: *mut ProtobufObj) -> SchedulerDriverStatus,
+    pub revive_offers: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr) -> SchedulerDriverStatus,
+    pub kill_task: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        taskId: *mut ProtobufObj) -> SchedulerDriverStatus,
+    pub reconcile_tasks: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        statuses: *mut ProtobufObj) -> SchedulerDriverStatus,
+    pub send_framework_message: unsafe extern "C" fn(
+        driver: SchedulerDriverPtr,
+        executor: *mut ProtobufObj,
+        slaveId: *mut ProtobufObj,
+        data: *const ::libc::c_char) -> SchedulerDriverStatus,
+}
+
+#[cfg(feature = "native")]
+impl SchedulerShim {
+    /// The shim compiled into this crate by `build.rs`.
+    pub fn linked() -> SchedulerShim {
+        SchedulerShim {
+            init: scheduler_init,
+            destroy: scheduler_destroy,
+            start: scheduler_start,
+            stop: scheduler_stop,
+            abort: scheduler_abort,
+            join: scheduler_join,
+            request_resources: scheduler_requestResources,
+            launch_tasks: scheduler_launchTasks,
+            decline_offer: scheduler_declineOffer,
+            revive_offers: scheduler_reviveOffers,
+            kill_task: scheduler_killTask,
+            reconcile_tasks: scheduler_reconcileTasks,
+            send_framework_message: scheduler_sendFrameworkMessage,
+        }
+    }
+}
@@ -9,45 +9,170 @@
 //! Additionally, this module provides a native `SchedulerDriver` that manages
 //! the backing native state and hides the required function delegate
 //! wiring.
-
+//!
+//! This module is built with the `native` cargo feature, which is on by
+//! default and links the driver against `libmesos` at build time, or with
+//! the `dlopen` feature, which instead loads `libmesos` when the program
+//! runs:
+//!
+//! ```ignore
+//! let library = try!(Library::open("/opt/mesos/lib/libmesos-c.so"));
+//! let mut driver = MesosSchedulerDriver::with_library(
+//!     &scheduler, &framework_info, master, &library);
+//! ```
+
+mod framing;
+#[cfg(feature = "dlopen")]
+mod library;
 mod mesos_c;
 mod tests;
 
+#[cfg(feature = "dlopen")]
+pub use self::library::{Library, LoadError};
+
 use libc::{c_char, c_int, c_void, size_t};
+use native::framing::FramingError;
 use proto::mesos as pb;
-use scheduler::{Scheduler, SchedulerDriver};
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+use state::{self, StateStore};
+use std::borrow::Cow;
 use std::boxed::Box;
-use std::ffi::{CStr, CString};
+use std::ffi::CString;
+use std::iter;
 use std::mem;
 use std::option::Option;
 use std::slice;
-use std::str;
+use std::sync::{Arc, RwLock};
+
+/// What the driver does after a message from Mesos fails to decode, once
+/// the scheduler has been told through `Scheduler::decode_error`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodePolicy {
+    /// Drops the message and keeps running.  An undecodable offer is
+    /// dropped from the offers delivered with it.
+    Continue,
+    /// Aborts the driver, so that `run` returns `DRIVER_ABORTED`.
+    Abort,
+}
+
+impl Default for DecodePolicy {
+    fn default() -> DecodePolicy {
+        DecodePolicy::Abort
+    }
+}
+
+// Merges a native message into `$proto` for a callback, or reports the
+// failure and returns from the callback.
+macro_rules! decode {
+    ($driver:expr, $native:expr, $proto:expr, $message_type:expr) => (
+        if let Err(error) =
+            mesos_c::ProtobufObj::merge($native, $proto, $message_type) {
+            $driver.decode_failed(error);
+            return;
+        }
+    )
+}
 
 #[derive(Clone)]
 pub struct MesosSchedulerDriver<'a> {
     scheduler: &'a Scheduler,
     framework_info: &'a pb::FrameworkInfo,
     master: String,
+    state_store: Option<&'a StateStore>,
+    decode_policy: DecodePolicy,
+    shim: mesos_c::SchedulerShim,
     native_ptr_pair: Option<mesos_c::SchedulerPtrPair>,
+    handle: SchedulerHandle,
 }
 
 impl<'a> MesosSchedulerDriver<'a> {
 
+    /// A driver for the native shim linked into this crate.
+    #[cfg(feature = "native")]
     pub fn new<'d>(
         scheduler: &'d Scheduler,
         framework_info: &'d pb::FrameworkInfo,
         master: String
+    ) -> Box<MesosSchedulerDriver<'d>> {
+        MesosSchedulerDriver::with_shim(scheduler,
+                                        framework_info,
+                                        master,
+                                        mesos_c::SchedulerShim::linked())
+    }
+
+    /// A driver for the native shim in `library`, loaded at runtime.
+    #[cfg(feature = "dlopen")]
+    pub fn with_library<'d>(
+        scheduler: &'d Scheduler,
+        framework_info: &'d pb::FrameworkInfo,
+        master: String,
+        library: &Library
+    ) -> Box<MesosSchedulerDriver<'d>> {
+        MesosSchedulerDriver::with_shim(scheduler,
+                                        framework_info,
+                                        master,
+                                        library.shim())
+    }
+
+    fn with_shim<'d>(
+        scheduler: &'d Scheduler,
+        framework_info: &'d pb::FrameworkInfo,
+        master: String,
+        shim: mesos_c::SchedulerShim
     ) -> Box<MesosSchedulerDriver<'d>> {
         Box::new(
             MesosSchedulerDriver {
                 scheduler: scheduler,
                 framework_info: framework_info,
                 master: master,
+                state_store: None,
+                decode_policy: DecodePolicy::default(),
+                shim: shim,
                 native_ptr_pair: None,
+                handle: SchedulerHandle {
+                    native: Arc::new(RwLock::new(None)),
+                },
             }
         )
     }
 
+    /// Registers with the framework id persisted in `store`, if there is
+    /// one, when the driver is run.  Wrap the scheduler in a
+    /// `state::PersistentScheduler` to persist the id in the first place.
+    pub fn set_state_store(&mut self, store: &'a StateStore) {
+        self.state_store = Some(store);
+    }
+
+    /// A handle for making driver calls from other threads, once the
+    /// driver runs.
+    pub fn handle(&self) -> SchedulerHandle {
+        self.handle.clone()
+    }
+
+    /// Sets what the driver does when a message from Mesos fails to
+    /// decode; by default it aborts.
+    pub fn set_decode_policy(&mut self, policy: DecodePolicy) {
+        self.decode_policy = policy;
+    }
+
+    // Reports a message that failed to decode to the scheduler, then
+    // aborts the driver if the policy says so.  Returns whether the
+    // driver keeps running.
+    fn decode_failed(&self, error: DecodeError) -> bool {
+        self.scheduler.decode_error(self, &error);
+        match self.decode_policy {
+            DecodePolicy::Continue => true,
+            DecodePolicy::Abort => {
+                if let Some(native_ptr_pair) = self.native_ptr_pair {
+                    unsafe {
+                        (self.shim.abort)(native_ptr_pair.driver);
+                    }
+                }
+                false
+            },
+        }
+    }
+
     // Returns a C struct containing nullable C function pointers, where
     // each such pointer refers to a wrapper function that unmarshals native
     // data structures and delegates to this driver's (Rust) scheduler
@@ -67,10 +192,10 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let framework_id = &mut pb::FrameworkID::new();
-            mesos_c::ProtobufObj::merge(native_framework_id, framework_id);
+            decode!(driver, native_framework_id, framework_id, "FrameworkID");
 
             let master_info = &mut pb::MasterInfo::new();
-            mesos_c::ProtobufObj::merge(native_master_info, master_info);
+            decode!(driver, native_master_info, master_info, "MasterInfo");
 
             driver.scheduler.registered(driver, &framework_id, master_info);
         }
@@ -84,7 +209,7 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let master_info = &mut pb::MasterInfo::new();
-            mesos_c::ProtobufObj::merge(native_master_info, master_info);
+            decode!(driver, native_master_info, master_info, "MasterInfo");
 
             driver.scheduler.reregistered(driver, master_info);
         }
@@ -96,24 +221,31 @@ impl<'a> MesosSchedulerDriver<'a> {
         ) -> () {
             let driver: &MesosSchedulerDriver = unsafe {
                 mem::transmute(native_scheduler_driver)
-            }; 
-            let num_offers = native_num_offers as usize;
-
-            let pbs = unsafe {
-                slice::from_raw_parts(
-                    native_offers as *const mesos_c::ProtobufObj,
-                    num_offers as usize).to_vec()
             };
 
-            let mut offers = vec![];
-
-            for mut pb in pbs {
-                let mut offer = pb::Offer::new();
-                mesos_c::ProtobufObj::merge(&mut pb, &mut offer);
-                offers.push(offer);
+            // The native buffers are only borrowed for the duration of
+            // the callback; each offer is decoded from them exactly once.
+            let native_offers: &[mesos_c::ProtobufObj] =
+                if native_offers.is_null() || native_num_offers == 0 {
+                    &[]
+                } else {
+                    unsafe {
+                        slice::from_raw_parts(native_offers,
+                                              native_num_offers as usize)
+                    }
+                };
+
+            let mut offers = Vec::with_capacity(native_offers.len());
+            for offer in decode_offers(native_offers) {
+                match offer {
+                    Ok(offer) => offers.push(offer),
+                    Err(error) => if !driver.decode_failed(error) {
+                        return;
+                    },
+                }
             }
 
-            driver.scheduler.resource_offers(driver, offers.clone());
+            driver.scheduler.resource_offers(driver, offers);
         }
 
         extern "C" fn wrapped_status_update_callback(
@@ -125,7 +257,7 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let task_status = &mut pb::TaskStatus::new();
-            mesos_c::ProtobufObj::merge(native_task_status, task_status);
+            decode!(driver, native_task_status, task_status, "TaskStatus");
 
             driver.scheduler.status_update(driver, task_status);
         }
@@ -149,7 +281,7 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let offer_id = &mut pb::OfferID::new();
-            mesos_c::ProtobufObj::merge(native_offer_id, offer_id);
+            decode!(driver, native_offer_id, offer_id, "OfferID");
 
             driver.scheduler.offer_rescinded(driver, offer_id);
         }
@@ -158,25 +290,36 @@ impl<'a> MesosSchedulerDriver<'a> {
             native_scheduler_driver: mesos_c::SchedulerDriverPtr,
             native_executor_id: *mut mesos_c::ProtobufObj,
             native_slave_id: *mut mesos_c::ProtobufObj,
-            native_data: *const c_char
+            native_data: *mut mesos_c::ProtobufObj
         ) -> () {
             let driver: &MesosSchedulerDriver = unsafe {
                 mem::transmute(native_scheduler_driver)
             };
 
             let executor_id = &mut pb::ExecutorID::new();
-            mesos_c::ProtobufObj::merge(native_executor_id, executor_id);
+            decode!(driver, native_executor_id, executor_id, "ExecutorID");
 
             let slave_id = &mut pb::SlaveID::new();
-            mesos_c::ProtobufObj::merge(native_slave_id, slave_id);
+            decode!(driver, native_slave_id, slave_id, "SlaveID");
 
-            let data_slice = unsafe {
-                CStr::from_ptr(native_data).to_bytes()
+            let native_data = if native_data.is_null() {
+                mesos_c::ProtobufObj::default()
+            } else {
+                mesos_c::ProtobufObj::from_raw_ptr(native_data)
+            };
+            let data_bytes = native_data.to_bytes();
+
+            // Mesos allows binary data, which is no reason to abort the
+            // driver: it is passed on with invalid UTF-8 replaced.
+            let data = match String::from_utf8_lossy(data_bytes) {
+                Cow::Borrowed(data) => data.to_string(),
+                Cow::Owned(data) => {
+                    warn!("Framework message from executor '{}' is not \
+                           valid UTF-8; invalid sequences were replaced",
+                          executor_id.get_value());
+                    data
+                },
             };
-
-            let data: String = str::from_utf8(data_slice)
-                .unwrap()
-                .to_string();
 
             driver.scheduler.framework_message(driver,
                                                executor_id,
@@ -193,7 +336,7 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let slave_id = &mut pb::SlaveID::new();
-            mesos_c::ProtobufObj::merge(native_slave_id, slave_id);
+            decode!(driver, native_slave_id, slave_id, "SlaveID");
 
             driver.scheduler.slave_lost(driver, slave_id);
         }
@@ -209,10 +352,10 @@ impl<'a> MesosSchedulerDriver<'a> {
             };
 
             let executor_id = &mut pb::ExecutorID::new();
-            mesos_c::ProtobufObj::merge(native_executor_id, executor_id);
+            decode!(driver, native_executor_id, executor_id, "ExecutorID");
 
             let slave_id = &mut pb::SlaveID::new();
-            mesos_c::ProtobufObj::merge(native_slave_id, slave_id);
+            decode!(driver, native_slave_id, slave_id, "SlaveID");
 
             let status = native_status as i32;
 
@@ -222,19 +365,20 @@ impl<'a> MesosSchedulerDriver<'a> {
 
         extern "C" fn wrapped_error_callback(
             native_scheduler_driver: mesos_c::SchedulerDriverPtr,
-            native_message: *const c_char
+            native_message: *mut mesos_c::ProtobufObj
         ) -> () {
             let driver: &MesosSchedulerDriver = unsafe {
                 mem::transmute(native_scheduler_driver)
             };
 
-            let message_slice = unsafe {
-                CStr::from_ptr(native_message).to_bytes()
+            let native_message = if native_message.is_null() {
+                mesos_c::ProtobufObj::default()
+            } else {
+                mesos_c::ProtobufObj::from_raw_ptr(native_message)
             };
+            let message_bytes = native_message.to_bytes();
 
-            let message: String = str::from_utf8(message_slice)
-                .unwrap()
-                .to_string();
+            let message = String::from_utf8_lossy(message_bytes).into_owned();
 
             driver.scheduler.error(driver, &message);
         }
@@ -255,67 +399,43 @@ impl<'a> MesosSchedulerDriver<'a> {
 
 }
 
-impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
-
-    fn run(&mut self) -> i32 {
-
-        let callbacks: *mut mesos_c::SchedulerCallBacks =
-            &mut self.create_callbacks();
+type DecodeOffer =
+    fn(&mesos_c::ProtobufObj) -> Result<pb::Offer, DecodeError>;
+type DecodedOffers<'a> =
+    iter::Map<slice::Iter<'a, mesos_c::ProtobufObj>, DecodeOffer>;
 
-        let native_payload: *mut c_void = unsafe {
-            // Super-unsafe!  This violates Rust's reference aliasing and
-            // memory safety guarantees.  The MesosSchedulerDriver data
-            // structure is opaque to the underlying native code (notice how
-            // it's not annotated with #[repr(C)]; but anyway we promise not
-            // to modify this structure from foreign code).
-            mem::transmute(&mut *self)
-        };
-
-        // The lifetime of `pb_data` must exceed that of
-        // `native_framework_info`.
-        let pb_data = &mut vec![];
-
-        let native_framework_info =
-            &mut mesos_c::ProtobufObj::from_message(
-                self.framework_info,
-                pb_data);
-
-        let native_master = CString::new(self.master.clone()).unwrap();
-
-        self.native_ptr_pair = Some(
-            unsafe {
-                mesos_c::scheduler_init(
-                    callbacks,
-                    native_payload,
-                    native_framework_info as *mut mesos_c::ProtobufObj,
-                    native_master.as_ptr() as *const i8)
-            }
-        );
+// Decodes the offers of a `resourceOffers` callback as they are iterated,
+// reading each one straight from its native buffer.
+fn decode_offers(native_offers: &[mesos_c::ProtobufObj]) -> DecodedOffers {
+    native_offers.iter().map(decode_offer as DecodeOffer)
+}
 
-        let native_ptr_pair = self.native_ptr_pair.unwrap();
+fn decode_offer(
+    native_offer: &mesos_c::ProtobufObj
+) -> Result<pb::Offer, DecodeError> {
+    let mut offer = pb::Offer::new();
+    native_offer.merge_into(&mut offer, "Offer").map(|_| offer)
+}
 
-        println!("Starting scheduler driver");
-        let scheduler_status = unsafe{
-            mesos_c::scheduler_start(native_ptr_pair.driver)
-        };
-        println!("scheduler_status: [{}]", scheduler_status);
+// The native driver created by `run`, and the shim to call it through.
+// libmesos drivers can be called from any thread.
+#[derive(Clone, Copy)]
+struct NativeDriver {
+    shim: mesos_c::SchedulerShim,
+    driver: mesos_c::SchedulerDriverPtr,
+}
 
-        println!("Joining scheduler driver");
-        let scheduler_status = unsafe{
-            mesos_c::scheduler_join(native_ptr_pair.driver)
-        };
-        println!("scheduler_status: [{}]", scheduler_status);
+unsafe impl Send for NativeDriver {}
+unsafe impl Sync for NativeDriver {}
 
-        scheduler_status
-    }
+impl NativeDriver {
 
     fn decline_offer(
         &self,
         offer_id: &pb::OfferID,
         filters: &pb::Filters) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let offer_id_data = &mut vec![];
         let native_offer_id = &mut mesos_c::ProtobufObj::from_message(
@@ -328,7 +448,7 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
             filters_data);
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_declineOffer(
+            (self.shim.decline_offer)(
                 native_driver,
                 native_offer_id as *mut mesos_c::ProtobufObj,
                 native_filters as *mut mesos_c::ProtobufObj)
@@ -337,40 +457,29 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         scheduler_status
     }
 
+    // Aborts the driver when a call cannot be encoded, e.g. for a missing
+    // required field, as the shim does when it cannot decode one.
+    fn encode_failed(&self, what: &str, error: FramingError) -> i32 {
+        error!("Failed to encode {}, aborting the driver: {}", what, error);
+        unsafe { (self.shim.abort)(self.driver) }
+    }
+
     fn request_resources(
         &self,
         requests: &Vec<&pb::Request>) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
-
-        let native_request_data = &mut vec![];
-        for request in requests {
-            let request_data = &mut vec![];
-            mesos_c::ProtobufObj::from_message(*request, request_data);
-
-            // write length of vec as a u64 to native_task_data
-            let length_pointer: *const u8 = unsafe {
-                mem::transmute(&(request_data.len() as u64))
-            };
+        let native_driver = self.driver;
 
-            let length_data: Vec<u8> = unsafe {
-                slice::from_raw_parts(
-                    length_pointer,
-                    8 as usize).to_vec() // 8 bytes in a u64
-            };
-
-            native_request_data.extend(length_data);
-
-            // write vec content to native_task_data
-            native_request_data.extend(request_data.iter().cloned());
-        }
+        let native_request_data = &mut match framing::encode(requests) {
+            Ok(data) => data,
+            Err(e) => return self.encode_failed("requests", e),
+        };
 
         let native_requests =
             &mut mesos_c::ProtobufObj::from_vec(native_request_data);
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_requestResources(
+            (self.shim.request_resources)(
                 native_driver,
                 native_requests as *mut mesos_c::ProtobufObj)
         };
@@ -385,35 +494,17 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         tasks: &Vec<&pb::TaskInfo>,
         filters: &pb::Filters) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let offer_id_data = &mut vec![];
         let native_offer_id = &mut mesos_c::ProtobufObj::from_message(
             offer_id,
             offer_id_data);
 
-        let native_task_data = &mut vec![];
-        for task in tasks {
-            let task_data = &mut vec![];
-            mesos_c::ProtobufObj::from_message(*task, task_data);
-
-            // write length of vec as a u64 to native_task_data
-            let length_pointer: *const u8 = unsafe {
-                mem::transmute(&(task_data.len() as u64))
-            };
-
-            let length_data: Vec<u8> = unsafe {
-                slice::from_raw_parts(
-                    length_pointer,
-                    8 as usize).to_vec() // 8 bytes in a u64
-            };
-
-            native_task_data.extend(length_data);
-
-            // write vec content to native_task_data
-            native_task_data.extend(task_data.iter().cloned());
-        }
+        let native_task_data = &mut match framing::encode(tasks) {
+            Ok(data) => data,
+            Err(e) => return self.encode_failed("tasks", e),
+        };
 
         let native_tasks =
             &mut mesos_c::ProtobufObj::from_vec(native_task_data);
@@ -424,7 +515,7 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
             filters_data);
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_launchTasks(
+            (self.shim.launch_tasks)(
                 native_driver,
                 native_offer_id as *mut mesos_c::ProtobufObj,
                 native_tasks as *mut mesos_c::ProtobufObj,
@@ -436,11 +527,10 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
 
     fn revive_offers(&self) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_reviveOffers(native_driver)
+            (self.shim.revive_offers)(native_driver)
         };
 
         scheduler_status
@@ -450,8 +540,7 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         &self,
         task_id: &pb::TaskID) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let task_id_data = &mut vec![];
         let native_task_id = &mut mesos_c::ProtobufObj::from_message(
@@ -459,7 +548,7 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
             task_id_data);
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_killTask(
+            (self.shim.kill_task)(
                 native_driver,
                 native_task_id as *mut mesos_c::ProtobufObj)
         };
@@ -467,14 +556,36 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         scheduler_status
     }
 
+    fn reconcile_tasks(
+        &self,
+        statuses: &Vec<&pb::TaskStatus>) -> i32 {
+
+        let native_driver = self.driver;
+
+        let native_status_data = &mut match framing::encode(statuses) {
+            Ok(data) => data,
+            Err(e) => return self.encode_failed("statuses", e),
+        };
+
+        let native_statuses =
+            &mut mesos_c::ProtobufObj::from_vec(native_status_data);
+
+        let scheduler_status = unsafe {
+            (self.shim.reconcile_tasks)(
+                native_driver,
+                native_statuses as *mut mesos_c::ProtobufObj)
+        };
+
+        scheduler_status
+    }
+
     fn send_framework_message(
         &self,
         executor_id: &pb::ExecutorID,
         slave_id: &pb::SlaveID,
         data: &Vec<u8>) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let executor_id_data = &mut vec![];
         let native_executor_id = &mut mesos_c::ProtobufObj::from_message(
@@ -489,7 +600,7 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         let native_data = data.as_ptr() as *mut c_char;
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_sendFrameworkMessage(
+            (self.shim.send_framework_message)(
                 native_driver,
                 native_executor_id as *mut mesos_c::ProtobufObj,
                 native_slave_id as *mut mesos_c::ProtobufObj,
@@ -503,29 +614,225 @@ impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
         &self,
         failover: bool) -> i32 {
 
-        assert!(self.native_ptr_pair.is_some());
-        let native_driver = self.native_ptr_pair.unwrap().driver;
+        let native_driver = self.driver;
 
         let scheduler_status = unsafe {
-            mesos_c::scheduler_stop(
+            (self.shim.stop)(
                 native_driver,
                 failover as c_int)
         };
 
         scheduler_status
     }
+}
+
+/// A handle to a `MesosSchedulerDriver` for making driver calls from other
+/// threads, e.g. through a `call_queue::CallQueue`.  Calls return
+/// `DRIVER_NOT_STARTED` until the driver runs, and once it is dropped;
+/// `run` is left to the driver itself and does nothing.
+#[derive(Clone)]
+pub struct SchedulerHandle {
+    native: Arc<RwLock<Option<NativeDriver>>>,
+}
+
+impl SchedulerHandle {
+    fn with_native<F>(&self, call: F) -> i32
+        where F: FnOnce(&NativeDriver) -> i32
+    {
+        match *self.native.read().unwrap() {
+            Some(ref native) => call(native),
+            None => pb::Status::DRIVER_NOT_STARTED as i32,
+        }
+    }
+}
+
+impl SchedulerDriver for SchedulerHandle {
+
+    fn run(&mut self) -> i32 {
+        pb::Status::DRIVER_NOT_STARTED as i32
+    }
+
+    fn stop(&self, failover: bool) -> i32 {
+        self.with_native(|native| native.stop(failover))
+    }
+
+    fn request_resources(&self, requests: &Vec<&pb::Request>) -> i32 {
+        self.with_native(|native| native.request_resources(requests))
+    }
+
+    fn decline_offer(
+        &self,
+        offer_id: &pb::OfferID,
+        filters: &pb::Filters) -> i32 {
+
+        self.with_native(|native| native.decline_offer(offer_id, filters))
+    }
+
+    fn launch_tasks(
+        &self,
+        offer_id: &pb::OfferID,
+        tasks: &Vec<&pb::TaskInfo>,
+        filters: &pb::Filters) -> i32 {
+
+        self.with_native(|native| {
+            native.launch_tasks(offer_id, tasks, filters)
+        })
+    }
+
+    fn revive_offers(&self) -> i32 {
+        self.with_native(|native| native.revive_offers())
+    }
+
+    fn kill_task(&self, task_id: &pb::TaskID) -> i32 {
+        self.with_native(|native| native.kill_task(task_id))
+    }
+
+    fn reconcile_tasks(&self, statuses: &Vec<&pb::TaskStatus>) -> i32 {
+        self.with_native(|native| native.reconcile_tasks(statuses))
+    }
+
+    fn send_framework_message(
+        &self,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &Vec<u8>) -> i32 {
+
+        self.with_native(|native| {
+            native.send_framework_message(executor_id, slave_id, data)
+        })
+    }
+}
+
+impl<'a> SchedulerDriver for MesosSchedulerDriver<'a> {
+
+    fn run(&mut self) -> i32 {
+
+        let callbacks: *mut mesos_c::SchedulerCallBacks =
+            &mut self.create_callbacks();
+
+        let native_payload: *mut c_void = unsafe {
+            // Super-unsafe!  This violates Rust's reference aliasing and
+            // memory safety guarantees.  The MesosSchedulerDriver data
+            // structure is opaque to the underlying native code (notice how
+            // it's not annotated with #[repr(C)]; but anyway we promise not
+            // to modify this structure from foreign code).
+            mem::transmute(&mut *self)
+        };
+
+        let mut framework_info = self.framework_info.clone();
+        if let Some(store) = self.state_store {
+            match state::inject_framework_id(store, &mut framework_info) {
+                Ok(true) => info!("Using persisted framework id: [{}]",
+                                  framework_info.get_id().get_value()),
+                Ok(false) => {},
+                Err(e) => {
+                    error!("Failed to read persisted framework id: {}", e);
+                    return pb::Status::DRIVER_ABORTED as i32;
+                },
+            }
+        }
+
+        // The lifetime of `pb_data` must exceed that of
+        // `native_framework_info`.
+        let pb_data = &mut vec![];
+
+        let native_framework_info =
+            &mut mesos_c::ProtobufObj::from_message(
+                &framework_info,
+                pb_data);
+
+        let native_master = CString::new(self.master.clone()).unwrap();
+
+        self.native_ptr_pair = Some(
+            unsafe {
+                (self.shim.init)(
+                    callbacks,
+                    native_payload,
+                    native_framework_info as *mut mesos_c::ProtobufObj,
+                    native_master.as_ptr() as *const i8)
+            }
+        );
+
+        let native_ptr_pair = self.native_ptr_pair.unwrap();
+        *self.handle.native.write().unwrap() = Some(NativeDriver {
+            shim: self.shim,
+            driver: native_ptr_pair.driver,
+        });
+
+        println!("Starting scheduler driver");
+        let scheduler_status = unsafe{
+            (self.shim.start)(native_ptr_pair.driver)
+        };
+        println!("scheduler_status: [{}]", scheduler_status);
+
+        println!("Joining scheduler driver");
+        let scheduler_status = unsafe{
+            (self.shim.join)(native_ptr_pair.driver)
+        };
+        println!("scheduler_status: [{}]", scheduler_status);
+
+        scheduler_status
+    }
+
+    fn stop(&self, failover: bool) -> i32 {
+        self.handle.stop(failover)
+    }
+
+    fn request_resources(&self, requests: &Vec<&pb::Request>) -> i32 {
+        self.handle.request_resources(requests)
+    }
 
+    fn decline_offer(
+        &self,
+        offer_id: &pb::OfferID,
+        filters: &pb::Filters) -> i32 {
+
+        self.handle.decline_offer(offer_id, filters)
+    }
+
+    fn launch_tasks(
+        &self,
+        offer_id: &pb::OfferID,
+        tasks: &Vec<&pb::TaskInfo>,
+        filters: &pb::Filters) -> i32 {
+
+        self.handle.launch_tasks(offer_id, tasks, filters)
+    }
+
+    fn revive_offers(&self) -> i32 {
+        self.handle.revive_offers()
+    }
+
+    fn kill_task(&self, task_id: &pb::TaskID) -> i32 {
+        self.handle.kill_task(task_id)
+    }
+
+    fn reconcile_tasks(&self, statuses: &Vec<&pb::TaskStatus>) -> i32 {
+        self.handle.reconcile_tasks(statuses)
+    }
+
+    fn send_framework_message(
+        &self,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &Vec<u8>) -> i32 {
+
+        self.handle.send_framework_message(executor_id, slave_id, data)
+    }
 }
 
 // Clean up backing native data structures when a MesosSchedulerDriver
 // instance leaves scope.
 impl<'a> Drop for MesosSchedulerDriver<'a> {
     fn drop(&mut self) {
+        // Handles wait for the calls in progress, and fail from now on.
+        *self.handle.native.write().unwrap() = None;
+
         if self.native_ptr_pair.is_some() {
             let native_driver = self.native_ptr_pair.unwrap().driver;
             let native_scheduler = self.native_ptr_pair.unwrap().scheduler;
             unsafe {
-                mesos_c::scheduler_destroy(native_driver, native_scheduler);
+                (self.shim.destroy)(native_driver, native_scheduler);
             }
         }
     }
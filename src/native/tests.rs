@@ -1,23 +1,399 @@
 #[cfg(test)]
 mod tests {
 
+    use ::builder::resource;
+    use ::native::decode_offers;
+    use ::native::framing::{self, FramingError};
     use ::native::mesos_c::ProtobufObj;
     use ::proto;
+    use ::proto::mesos as pb;
+    use ::scheduler::{DecodeError, Scheduler, SchedulerDriver};
     use protobuf::Message;
+    use std::cell::RefCell;
+    use std::time::Instant;
 
     #[test]
     fn protobufobj_message_translation() {
-        let mut fi = proto::FrameworkInfo::new();
+        let mut fi = proto::mesos::FrameworkInfo::new();
         fi.set_name("foo".to_string());
         fi.set_user("bar".to_string());
 
         let pb_data = &mut vec![];
         let pb = ProtobufObj::from_message(&fi, pb_data);
 
-        let mut fi2 = proto::FrameworkInfo::new();
+        let mut fi2 = proto::mesos::FrameworkInfo::new();
         fi2.merge_from_bytes(pb.to_bytes()).unwrap();
 
         assert_eq!(fi, fi2);
     }
-}
 
+    fn task_status(task_id: &str) -> pb::TaskStatus {
+        let mut status = pb::TaskStatus::new();
+        status.mut_task_id().set_value(task_id.to_string());
+        status.set_state(pb::TaskState::TASK_RUNNING);
+        status
+    }
+
+    // A xorshift generator, so that fuzzing is reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound as u64) as usize
+        }
+    }
+
+    #[test]
+    fn framing_round_trip() {
+        let statuses = vec![task_status("a"), task_status("b")];
+        let data = framing::encode(&statuses.iter().collect::<Vec<_>>())
+            .unwrap();
+
+        // Lengths are little-endian, whatever the host.
+        let length = statuses[0].write_to_bytes().unwrap().len();
+        assert_eq!(data[0], length as u8);
+        assert_eq!(&data[1..framing::LENGTH_SIZE], &[0; 7]);
+
+        assert_eq!(framing::decode::<pb::TaskStatus>(&data), Ok(statuses));
+        assert_eq!(framing::encode::<pb::TaskStatus>(&[]), Ok(vec![]));
+        assert_eq!(framing::decode::<pb::TaskStatus>(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn framing_rejects_malformed_buffers() {
+        let status = task_status("a");
+        let data = framing::encode(&[&status]).unwrap();
+
+        assert_eq!(framing::decode::<pb::TaskStatus>(&data[..5]),
+                   Err(FramingError::TruncatedLength { offset: 0 }));
+        assert_eq!(framing::decode::<pb::TaskStatus>(&data[..10]),
+                   Err(FramingError::TruncatedMessage {
+                       offset: 0,
+                       length: (data.len() - framing::LENGTH_SIZE) as u64,
+                   }));
+
+        let huge = [0xff; 16];
+        assert_eq!(framing::decode::<pb::TaskStatus>(&huge),
+                   Err(FramingError::TruncatedMessage {
+                       offset: 0,
+                       length: u64::max_value(),
+                   }));
+    }
+
+    // Filters has no required fields, so that any well-formed message
+    // decodes.
+    fn framed_filters() -> (Vec<pb::Filters>, Vec<u8>) {
+        let filters: Vec<pb::Filters> = (0..4)
+            .map(|i| {
+                let mut filters = pb::Filters::new();
+                filters.set_refuse_seconds(i as f64);
+                filters
+            })
+            .collect();
+        let valid = framing::encode(&filters.iter().collect::<Vec<_>>())
+            .unwrap();
+        (filters, valid)
+    }
+
+    // Random buffers, and valid ones with bytes flipped, cut or appended,
+    // followed by every proper prefix of the valid buffer.
+    fn fuzz_inputs(valid: &[u8]) -> Vec<Vec<u8>> {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        let mut inputs: Vec<Vec<u8>> = (0..5000)
+            .map(|_| {
+                let mut data = if rng.below(2) == 0 {
+                    (0..rng.below(64)).map(|_| rng.next() as u8).collect()
+                } else {
+                    valid.to_vec()
+                };
+                for _ in 0..rng.below(4) {
+                    if data.is_empty() {
+                        break;
+                    }
+                    let index = rng.below(data.len());
+                    data[index] ^= 1 << rng.below(8);
+                }
+                if rng.below(2) == 0 {
+                    let length = rng.below(data.len() + 1);
+                    data.truncate(length);
+                }
+                if rng.below(4) == 0 {
+                    data.push(rng.next() as u8);
+                }
+                data
+            })
+            .collect();
+        inputs.extend((0..valid.len()).map(|end| valid[..end].to_vec()));
+        inputs
+    }
+
+    #[test]
+    fn framing_fuzz_decode() {
+        let (filters, valid) = framed_filters();
+
+        // No input may panic or read out of bounds.
+        for data in fuzz_inputs(&valid) {
+            if let Ok(decoded) = framing::decode::<pb::Filters>(&data) {
+                let total: usize = decoded.iter()
+                    .map(|filters| filters.compute_size() as usize)
+                    .sum();
+                assert!(total + decoded.len() * framing::LENGTH_SIZE
+                        <= data.len());
+            }
+        }
+
+        // Every proper prefix of a valid buffer is rejected or decodes to
+        // a prefix of the messages.
+        for end in 0..valid.len() {
+            match framing::decode::<pb::Filters>(&valid[..end]) {
+                Ok(decoded) => assert_eq!(&decoded[..],
+                                          &filters[..decoded.len()]),
+                Err(FramingError::TruncatedLength { .. }) |
+                Err(FramingError::TruncatedMessage { .. }) => {},
+                Err(e) => panic!("unexpected {}", e),
+            }
+        }
+    }
+
+    // resources/mesos-c/framing_check.cpp, built by build.rs for the tests
+    // only.
+    #[cfg(feature = "native")]
+    #[link(name = "mesosc_framing_check", kind = "static")]
+    extern "C" {
+        fn mesos_c_decode_frames(
+            data: *const u8,
+            size: usize,
+            lengths: *mut usize,
+            capacity: usize
+        ) -> i64;
+    }
+
+    // The frames the shim decodes from `data`, or None if it rejects them.
+    #[cfg(feature = "native")]
+    fn native_frames(data: &[u8]) -> Option<Vec<usize>> {
+        let mut lengths = vec![0; data.len() / framing::LENGTH_SIZE];
+        let count = unsafe {
+            mesos_c_decode_frames(data.as_ptr(), data.len(),
+                                  lengths.as_mut_ptr(), lengths.len())
+        };
+        if count < 0 {
+            return None;
+        }
+        assert!(count as usize <= lengths.len());
+        lengths.truncate(count as usize);
+        Some(lengths)
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn native_framing_fuzz_decode() {
+        let (filters, valid) = framed_filters();
+        let lengths: Vec<usize> = filters.iter()
+            .map(|filters| filters.compute_size() as usize)
+            .collect();
+        assert_eq!(native_frames(&valid), Some(lengths));
+        assert_eq!(native_frames(&[]), Some(vec![]));
+
+        // The shim accepts exactly the framing that `framing::decode`
+        // accepts; it only ignores the contents of the messages.
+        for data in fuzz_inputs(&valid) {
+            let native = native_frames(&data);
+            match framing::decode::<pb::Filters>(&data) {
+                Ok(decoded) =>
+                    assert_eq!(native.map(|frames| frames.len()),
+                               Some(decoded.len()),
+                               "{:?}", data),
+                // Decoding stops at the message, before the framing of
+                // the rest of the buffer is checked.
+                Err(FramingError::Decode { index, .. }) =>
+                    assert!(native.map_or(true, |frames| frames.len() > index),
+                            "{:?}", data),
+                Err(_) => assert_eq!(native, None, "{:?}", data),
+            }
+        }
+    }
+
+    fn offer(index: usize) -> pb::Offer {
+        let mut offer = pb::Offer::new();
+        offer.mut_id().set_value(format!("offer-{}", index));
+        offer.mut_framework_id().set_value("framework".to_string());
+        offer.mut_slave_id().set_value(format!("agent-{}", index));
+        offer.set_hostname(format!("agent-{}.example.com", index));
+        offer.mut_resources().push(resource::scalar("cpus", 32.0));
+        offer.mut_resources().push(resource::scalar("mem", 131072.0));
+        offer.mut_resources().push(resource::scalar("disk", 1048576.0));
+        offer.mut_resources().push(
+            resource::ranges("ports", &[(31000, 32000)]));
+        offer
+    }
+
+    // Encodes offers as the native shim passes them to the callback.
+    fn native_offers(
+        offers: &[pb::Offer],
+        data: &mut Vec<Vec<u8>>
+    ) -> Vec<ProtobufObj> {
+        *data = offers.iter().map(|_| vec![]).collect();
+        offers.iter()
+            .zip(data.iter_mut())
+            .map(|(offer, data)| ProtobufObj::from_message(offer, data))
+            .collect()
+    }
+
+    #[test]
+    fn offers_decode_from_native_buffers() {
+        let offers: Vec<pb::Offer> = (0..3).map(offer).collect();
+        let data = &mut vec![];
+        let native = native_offers(&offers, data);
+
+        let decoded: Vec<pb::Offer> = decode_offers(&native)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(decoded, offers);
+        assert!(decode_offers(&[]).next().is_none());
+    }
+
+    // Times `f` over `rounds` calls, and prints the time of one.
+    fn time<F: FnMut() -> Vec<pb::Offer>>(what: &str, rounds: u32, mut f: F) {
+        let start = Instant::now();
+        for _ in 0..rounds {
+            assert_eq!(f().len(), 10000);
+        }
+        let elapsed = start.elapsed() / rounds;
+        println!("{}: decoded 10000 offers in {}.{:06}s", what,
+                 elapsed.as_secs(), elapsed.subsec_nanos() / 1000);
+    }
+
+    // Compares decoding with the earlier path, which copied the native
+    // buffers and cloned the decoded offers before handing them over.
+    // Run with `cargo test --release bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_decode_10k_offers() {
+        let offers: Vec<pb::Offer> = (0..10000).map(offer).collect();
+        let data = &mut vec![];
+        let native = native_offers(&offers, data);
+
+        time("copy and clone", 20, || {
+            let mut decoded = vec![];
+            for mut native_offer in native.to_vec() {
+                let mut offer = pb::Offer::new();
+                ProtobufObj::merge(&mut native_offer, &mut offer, "Offer")
+                    .unwrap();
+                decoded.push(offer);
+            }
+            decoded.clone()
+        });
+        time("decode in place", 20, || {
+            let mut decoded = Vec::with_capacity(native.len());
+            for offer in decode_offers(&native) {
+                decoded.push(offer.unwrap());
+            }
+            decoded
+        });
+    }
+
+    struct DecodeErrors {
+        errors: RefCell<Vec<DecodeError>>,
+    }
+
+    impl Scheduler for DecodeErrors {
+        fn decode_error(
+            &self,
+            _driver: &SchedulerDriver,
+            error: &DecodeError) {
+            self.errors.borrow_mut().push(error.clone());
+        }
+    }
+
+    #[test]
+    fn undecodable_messages_are_errors() {
+        // A length-delimited field 1 of 5 bytes, with only one present.
+        let bytes = &mut vec![0x0a, 0x05, 0x01];
+        let native = &mut ProtobufObj::from_vec(bytes);
+
+        let offer_id = &mut pb::OfferID::new();
+        let error = ProtobufObj::merge(native, offer_id, "OfferID")
+            .unwrap_err();
+        assert_eq!(error.message_type, "OfferID");
+        assert_eq!(&error.bytes, bytes);
+        assert!(error.to_string()
+                .starts_with("Failed to decode OfferID from 3 bytes: "));
+
+        let offers = &mut vec![];
+        let mut native = native_offers(&[offer(0), offer(1)], offers);
+        native[1] = ProtobufObj::from_vec(bytes);
+        let decoded: Vec<_> = decode_offers(&native).collect();
+        assert!(decoded[0].is_ok());
+        assert_eq!(decoded[1].as_ref().unwrap_err().message_type, "Offer");
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn decode_failures_follow_the_policy() {
+        use ::native::{DecodePolicy, MesosSchedulerDriver};
+
+        let scheduler = DecodeErrors { errors: RefCell::new(vec![]) };
+        let framework_info = pb::FrameworkInfo::new();
+        let mut driver = MesosSchedulerDriver::new(
+            &scheduler, &framework_info, "localhost:5050".to_string());
+        let error = DecodeError {
+            message_type: "TaskStatus".to_string(),
+            bytes: vec![0xff],
+            reason: "truncated".to_string(),
+        };
+
+        assert!(!driver.decode_failed(error.clone()));
+        driver.set_decode_policy(DecodePolicy::Continue);
+        assert!(driver.decode_failed(error.clone()));
+        assert_eq!(*scheduler.errors.borrow(), vec![error.clone(), error]);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn handles_fail_until_the_driver_runs() {
+        use ::native::MesosSchedulerDriver;
+        use std::thread;
+
+        let scheduler = DecodeErrors { errors: RefCell::new(vec![]) };
+        let framework_info = pb::FrameworkInfo::new();
+        let driver = MesosSchedulerDriver::new(
+            &scheduler, &framework_info, "localhost:5050".to_string());
+
+        let handle = driver.handle();
+        let status = thread::spawn(move || handle.revive_offers())
+            .join().unwrap();
+        assert_eq!(status, pb::Status::DRIVER_NOT_STARTED as i32);
+        assert_eq!(driver.revive_offers(),
+                   pb::Status::DRIVER_NOT_STARTED as i32);
+    }
+
+    #[cfg(all(feature = "dlopen", target_os = "linux"))]
+    #[test]
+    fn missing_libraries_and_symbols_are_errors() {
+        use ::native::{Library, LoadError};
+
+        match Library::open("/nonexistent/libmesos-c.so") {
+            Err(LoadError::Library { ref path, .. }) =>
+                assert_eq!(path.to_str(), Some("/nonexistent/libmesos-c.so")),
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("loaded a nonexistent library"),
+        }
+
+        // The C library loads, but has no shim.
+        match Library::open("libc.so.6") {
+            Err(error @ LoadError::Symbol { .. }) => assert!(
+                error.to_string()
+                    .starts_with("Failed to find 'scheduler_init' in \
+                                  'libc.so.6': ")),
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("found a shim in libc"),
+        }
+    }
+}
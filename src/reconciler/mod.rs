@@ -0,0 +1,370 @@
+//! # Automatic task reconciliation.
+//!
+//! After (re-)registering, a scheduler cannot assume that its view of the
+//! world matches the master's: status updates may have been missed while it
+//! was disconnected, and tasks may have been lost in transit.  Mesos
+//! recommends the following reconciliation algorithm, which `Reconciler`
+//! runs on every `registered` and `reregistered` callback:
+//!
+//! 1. Perform implicit reconciliation (`reconcile_tasks` with no tasks), so
+//!    that the master sends the latest status of every task it knows of.
+//! 2. Wait, then explicitly reconcile each non-terminal task that has not
+//!    received a status update since reconciliation started.
+//! 3. Repeat step 2 with truncated exponential backoff until every
+//!    non-terminal task has a fresh status.
+//!
+//! The driver reference handed to callbacks may not be retained, so the
+//! reconciler makes progress whenever a callback is delivered (resource
+//! offers arrive regularly) or when the scheduler calls `tick`.
+
+mod tests;
+
+use proto::mesos as pb;
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+use std::collections::HashSet;
+use std::cmp;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use task_registry::{TaskRegistry, Transition};
+
+/// Tunables for the reconciliation algorithm.
+#[derive(Clone, Debug)]
+pub struct ReconcilerConfig {
+    /// Time to wait after implicit reconciliation and after the first
+    /// round of explicit reconciliation.
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the exponentially growing wait between explicit
+    /// reconciliation rounds.
+    pub max_backoff: Duration,
+
+    /// Whether updates with `REASON_RECONCILIATION` that merely confirm the
+    /// known state of a task are withheld from the wrapped scheduler.
+    /// Updates that reveal a change (e.g. a task that was lost while the
+    /// scheduler was disconnected) are always forwarded.
+    pub suppress_reconciliation_updates: bool,
+}
+
+impl Default for ReconcilerConfig {
+    fn default() -> ReconcilerConfig {
+        ReconcilerConfig {
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(60),
+            suppress_reconciliation_updates: true,
+        }
+    }
+}
+
+/// Progress of the reconciliation algorithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    /// Not registered yet, or disconnected from the master.
+    Idle,
+
+    /// Implicit reconciliation has been requested.
+    Implicit,
+
+    /// Explicit reconciliation is in progress; `round` counts the requests
+    /// sent so far and `remaining` the tasks still lacking a fresh status.
+    Explicit { round: u32, remaining: usize },
+
+    /// Every non-terminal task has a fresh status.
+    Complete,
+}
+
+struct State {
+    phase: Phase,
+    registry: TaskRegistry,
+    // Tasks that received a status update since reconciliation started.
+    fresh: HashSet<String>,
+    backoff: Duration,
+    deadline: Instant,
+}
+
+// What the driver is asked to do after the algorithm moves forward.
+enum Step {
+    Wait,
+    Reconcile(Vec<pb::TaskStatus>),
+    Complete,
+}
+
+/// A `Scheduler` that reconciles task state on behalf of the scheduler it
+/// wraps, and forwards every callback to it.
+///
+/// The reconciler keeps a `TaskRegistry` up to date from status updates.
+/// Tasks launched by the wrapped scheduler must be reported through
+/// `launched`, so that they can be reconciled explicitly.
+pub struct Reconciler<'a> {
+    scheduler: &'a Scheduler,
+    config: ReconcilerConfig,
+    state: Mutex<State>,
+    on_complete: Option<Box<Fn(&SchedulerDriver) + 'a>>,
+}
+
+impl<'a> Reconciler<'a> {
+
+    pub fn new(
+        scheduler: &'a Scheduler,
+        config: ReconcilerConfig
+    ) -> Reconciler<'a> {
+        let backoff = config.initial_backoff;
+        Reconciler {
+            scheduler: scheduler,
+            config: config,
+            state: Mutex::new(State {
+                phase: Phase::Idle,
+                registry: TaskRegistry::new(),
+                fresh: HashSet::new(),
+                backoff: backoff,
+                deadline: Instant::now(),
+            }),
+            on_complete: None,
+        }
+    }
+
+    /// Registers a function that is invoked every time reconciliation
+    /// completes.
+    pub fn on_complete<F>(mut self, f: F) -> Reconciler<'a>
+        where F: Fn(&SchedulerDriver) + 'a {
+        self.on_complete = Some(Box::new(f));
+        self
+    }
+
+    /// Records a task passed to `SchedulerDriver::launch_tasks`.
+    pub fn launched(&self, task: &pb::TaskInfo) {
+        self.lock().registry.launched(task);
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.lock().phase
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.phase() == Phase::Complete
+    }
+
+    /// Runs `f` with the task registry maintained by the reconciler.
+    pub fn with_registry<F, R>(&self, f: F) -> R
+        where F: FnOnce(&TaskRegistry) -> R {
+        f(&self.lock().registry)
+    }
+
+    /// Sends the next reconciliation request if its backoff has elapsed.
+    pub fn tick(&self, driver: &SchedulerDriver) {
+        self.tick_at(driver, Instant::now());
+    }
+
+    /// Like `tick`, with the current time supplied by the caller.
+    pub fn tick_at(&self, driver: &SchedulerDriver, now: Instant) {
+        let step = self.advance(&mut self.lock(), now);
+        self.perform(step, driver);
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+
+    fn start(&self, driver: &SchedulerDriver) {
+        {
+            let now = Instant::now();
+            let mut state = self.lock();
+            state.phase = Phase::Implicit;
+            state.fresh.clear();
+            state.backoff = self.config.initial_backoff;
+            state.deadline = now + state.backoff;
+        }
+        driver.reconcile_tasks(&vec![]);
+    }
+
+    // Moves the algorithm forward, returning what is left to do once the
+    // state is unlocked: a synchronous driver may deliver updates from
+    // within `reconcile_tasks`.
+    fn advance(&self, state: &mut State, now: Instant) -> Step {
+        let round = match state.phase {
+            Phase::Implicit => 0,
+            Phase::Explicit { round, .. } => round,
+            Phase::Idle | Phase::Complete => return Step::Wait,
+        };
+
+        let remaining: Vec<pb::TaskStatus> = state.registry
+            .non_terminal()
+            .iter()
+            .filter(|task| !state.fresh.contains(task.task_id.get_value()))
+            .map(|task| {
+                let mut status = pb::TaskStatus::new();
+                status.set_task_id(task.task_id.clone());
+                status.set_state(task.state);
+                if let Some(ref slave_id) = task.slave_id {
+                    status.set_slave_id(slave_id.clone());
+                }
+                status
+            })
+            .collect();
+
+        if remaining.is_empty() {
+            if round > 0 || now >= state.deadline {
+                state.phase = Phase::Complete;
+                return Step::Complete;
+            }
+        } else if now >= state.deadline {
+            if round > 0 {
+                state.backoff = cmp::min(state.backoff * 2,
+                                         self.config.max_backoff);
+            }
+            state.deadline = now + state.backoff;
+            state.phase = Phase::Explicit {
+                round: round + 1,
+                remaining: remaining.len(),
+            };
+            return Step::Reconcile(remaining);
+        } else if round > 0 {
+            state.phase = Phase::Explicit {
+                round: round,
+                remaining: remaining.len(),
+            };
+        }
+
+        Step::Wait
+    }
+
+    fn perform(&self, step: Step, driver: &SchedulerDriver) {
+        match step {
+            Step::Wait => {},
+            Step::Reconcile(statuses) => {
+                driver.reconcile_tasks(&statuses.iter().collect());
+            },
+            Step::Complete => self.complete(driver),
+        }
+    }
+
+    fn complete(&self, driver: &SchedulerDriver) {
+        if let Some(ref on_complete) = self.on_complete {
+            on_complete(driver);
+        }
+    }
+}
+
+impl<'a> Scheduler for Reconciler<'a> {
+
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        master_info: &pb::MasterInfo) {
+
+        self.start(driver);
+        self.scheduler.registered(driver, framework_id, master_info);
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) {
+
+        self.start(driver);
+        self.scheduler.reregistered(driver, master_info);
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        self.tick(driver);
+        self.scheduler.resource_offers(driver, offers);
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        let (forward, step) = {
+            let mut state = self.lock();
+            let transition = state.registry.update(task_status);
+
+            state.fresh.insert(
+                task_status.get_task_id().get_value().to_string());
+
+            let confirmation = task_status.has_reason() &&
+                task_status.get_reason() ==
+                    pb::TaskStatus_Reason::REASON_RECONCILIATION &&
+                transition == Transition::Duplicate;
+
+            let forward = !(confirmation &&
+                            self.config.suppress_reconciliation_updates);
+
+            (forward, self.advance(&mut state, Instant::now()))
+        };
+
+        if forward {
+            self.scheduler.status_update(driver, task_status);
+        }
+        self.perform(step, driver);
+    }
+
+    fn disconnected(
+        &self,
+        driver: &SchedulerDriver) {
+
+        self.lock().phase = Phase::Idle;
+        self.scheduler.disconnected(driver);
+    }
+
+    fn offer_rescinded(
+        &self,
+        driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) {
+
+        self.tick(driver);
+        self.scheduler.offer_rescinded(driver, offer_id);
+    }
+
+    fn framework_message(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &String) {
+
+        self.tick(driver);
+        self.scheduler.framework_message(driver, executor_id, slave_id, data);
+    }
+
+    fn slave_lost(
+        &self,
+        driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) {
+
+        self.tick(driver);
+        self.scheduler.slave_lost(driver, slave_id);
+    }
+
+    fn executor_lost(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        status: i32) {
+
+        self.tick(driver);
+        self.scheduler.executor_lost(driver, executor_id, slave_id, status);
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        self.lock().phase = Phase::Idle;
+        self.scheduler.error(driver, message);
+    }
+
+    fn decode_error(
+        &self,
+        driver: &SchedulerDriver,
+        error: &DecodeError) {
+
+        self.scheduler.decode_error(driver, error);
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+
+    use ::proto::mesos as pb;
+    use ::reconciler::*;
+    use ::scheduler::{Scheduler, SchedulerDriver};
+    use ::testing::{FakeMaster, MockSchedulerDriver};
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    struct CountingScheduler {
+        updates: Cell<usize>,
+    }
+
+    impl Scheduler for CountingScheduler {
+        fn registered(
+            &self,
+            _: &SchedulerDriver,
+            _: &pb::FrameworkID,
+            _: &pb::MasterInfo) {}
+        fn reregistered(&self, _: &SchedulerDriver, _: &pb::MasterInfo) {}
+        fn resource_offers(&self, _: &SchedulerDriver, _: Vec<pb::Offer>) {}
+        fn status_update(&self, _: &SchedulerDriver, _: &pb::TaskStatus) {
+            self.updates.set(self.updates.get() + 1);
+        }
+        fn disconnected(&self, _: &SchedulerDriver) {}
+        fn offer_rescinded(&self, _: &SchedulerDriver, _: &pb::OfferID) {}
+        fn slave_lost(&self, _: &SchedulerDriver, _: &pb::SlaveID) {}
+        fn executor_lost(
+            &self,
+            _: &SchedulerDriver,
+            _: &pb::ExecutorID,
+            _: &pb::SlaveID,
+            _: i32) {}
+        fn framework_message(
+            &self,
+            _: &SchedulerDriver,
+            _: &pb::ExecutorID,
+            _: &pb::SlaveID,
+            _: &String) {}
+        fn error(&self, _: &SchedulerDriver, _: &String) {}
+    }
+
+    fn task(id: &str) -> pb::TaskInfo {
+        let mut task = pb::TaskInfo::new();
+        task.mut_task_id().set_value(id.to_string());
+        task.mut_slave_id().set_value("slave-1".to_string());
+        task
+    }
+
+    fn status(
+        id: &str,
+        state: pb::TaskState,
+        reconciliation: bool
+    ) -> pb::TaskStatus {
+        let mut status = pb::TaskStatus::new();
+        status.mut_task_id().set_value(id.to_string());
+        status.set_state(state);
+        if reconciliation {
+            status.set_reason(pb::TaskStatus_Reason::REASON_RECONCILIATION);
+        }
+        status
+    }
+
+    // The ids of the tasks in each reconciliation round.
+    fn rounds(driver: &MockSchedulerDriver) -> Vec<Vec<String>> {
+        driver.reconciliations().iter()
+            .map(|statuses| statuses.iter()
+                 .map(|s| s.get_task_id().get_value().to_string())
+                 .collect())
+            .collect()
+    }
+
+    fn config() -> ReconcilerConfig {
+        ReconcilerConfig {
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(25),
+            suppress_reconciliation_updates: true,
+        }
+    }
+
+    #[test]
+    fn implicit_then_explicit_with_backoff() {
+        let driver = MockSchedulerDriver::new();
+        let inner = CountingScheduler { updates: Cell::new(0) };
+        let completions = Cell::new(0);
+        let reconciler = Reconciler::new(&inner, config())
+            .on_complete(|_| completions.set(completions.get() + 1));
+
+        reconciler.launched(&task("t1"));
+        reconciler.launched(&task("t2"));
+
+        let start = Instant::now();
+        reconciler.registered(&driver,
+                              &pb::FrameworkID::new(),
+                              &pb::MasterInfo::new());
+        assert_eq!(reconciler.phase(), Phase::Implicit);
+        assert_eq!(rounds(&driver), vec![Vec::<String>::new()]);
+
+        // The master knows about t1 only.
+        reconciler.status_update(
+            &driver, &status("t1", pb::TaskState::TASK_RUNNING, true));
+        assert_eq!(inner.updates.get(), 1);
+
+        // Backoff has not elapsed yet.
+        reconciler.tick_at(&driver, start + Duration::from_secs(1));
+        assert_eq!(rounds(&driver).len(), 1);
+
+        let mut now = start + Duration::from_secs(11);
+        let waits = [10, 20, 25, 25];
+        for &wait in waits.iter() {
+            reconciler.tick_at(&driver, now);
+            assert_eq!(rounds(&driver).last().unwrap(),
+                       &vec!["t2".to_string()]);
+            now = now + Duration::from_secs(wait - 1);
+            reconciler.tick_at(&driver, now);
+            now = now + Duration::from_secs(1);
+        }
+        assert_eq!(rounds(&driver).len(), 5);
+        assert_eq!(reconciler.phase(),
+                   Phase::Explicit { round: 4, remaining: 1 });
+
+        // The master no longer knows about t2.
+        reconciler.status_update(
+            &driver, &status("t2", pb::TaskState::TASK_LOST, true));
+        assert!(reconciler.is_complete());
+        assert_eq!(completions.get(), 1);
+        assert_eq!(inner.updates.get(), 2);
+        assert_eq!(
+            reconciler.with_registry(|registry| registry.non_terminal().len()),
+            1);
+    }
+
+    #[test]
+    fn suppresses_confirming_updates() {
+        let driver = MockSchedulerDriver::new();
+        let inner = CountingScheduler { updates: Cell::new(0) };
+        let reconciler = Reconciler::new(&inner, config());
+
+        reconciler.launched(&task("t1"));
+        reconciler.status_update(
+            &driver, &status("t1", pb::TaskState::TASK_RUNNING, false));
+        assert_eq!(inner.updates.get(), 1);
+
+        reconciler.registered(&driver,
+                              &pb::FrameworkID::new(),
+                              &pb::MasterInfo::new());
+        reconciler.status_update(
+            &driver, &status("t1", pb::TaskState::TASK_RUNNING, true));
+        assert_eq!(inner.updates.get(), 1);
+
+        reconciler.tick_at(&driver, Instant::now() + Duration::from_secs(11));
+        assert!(reconciler.is_complete());
+    }
+
+    #[test]
+    fn drivers_may_deliver_updates_from_reconcile_tasks() {
+        let inner = CountingScheduler { updates: Cell::new(0) };
+        let reconciler = Reconciler::new(&inner, config());
+        reconciler.launched(&task("t1"));
+
+        // The fake master delivers the update for the unknown task from
+        // within `reconcile_tasks` when called outside of a callback.
+        let mut framework_info = pb::FrameworkInfo::new();
+        framework_info.set_name("reconciler".to_string());
+        let master = FakeMaster::new(&reconciler, &framework_info);
+        master.start();
+        assert_eq!(reconciler.phase(), Phase::Implicit);
+
+        reconciler.tick_at(&master, Instant::now() + Duration::from_secs(11));
+        assert!(reconciler.is_complete());
+        assert_eq!(master.task_state("t1"), None);
+
+        // The task was lost, which is news to the wrapped scheduler.
+        assert_eq!(inner.updates.get(), 1);
+    }
+}
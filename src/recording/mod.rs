@@ -0,0 +1,470 @@
+//! # Recording and replaying scheduler callbacks.
+//!
+//! `RecordingScheduler` wraps a `Scheduler` and writes every callback it
+//! receives, with the time it arrived, to a file before forwarding it.  A
+//! recording can later be read back with `read` and replayed into a
+//! scheduler with `replay`, typically against a `MockSchedulerDriver` to
+//! assert on the calls the scheduler makes in response:
+//!
+//! ```ignore
+//! let records = try!(recording::read_file("incident.log"));
+//! let driver = MockSchedulerDriver::new();
+//! recording::replay(&records, &scheduler, &driver);
+//! assert!(driver.calls().contains(&DriverCall::KillTask(task_id)));
+//! ```
+//!
+//! Recordings are text, one callback per line: the time in seconds since
+//! the epoch, the name of the callback, then its arguments, with messages
+//! in their hex encoded protobuf form and `-` for empty values:
+//!
+//! ```text
+//! 1444700000.250000000 status_update 0a090a07...
+//! ```
+
+mod tests;
+
+use proto::mesos as pb;
+use protobuf::{self, Message};
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A scheduler callback, with its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Callback {
+    Registered(pb::FrameworkID, pb::MasterInfo),
+    Reregistered(pb::MasterInfo),
+    ResourceOffers(Vec<pb::Offer>),
+    StatusUpdate(pb::TaskStatus),
+    Disconnected,
+    OfferRescinded(pb::OfferID),
+    FrameworkMessage(pb::ExecutorID, pb::SlaveID, String),
+    SlaveLost(pb::SlaveID),
+    ExecutorLost(pb::ExecutorID, pb::SlaveID, i32),
+    Error(String),
+    DecodeError(DecodeError),
+}
+
+impl Callback {
+
+    /// Invokes the callback on `scheduler`.
+    pub fn deliver(&self, scheduler: &Scheduler, driver: &SchedulerDriver) {
+        match *self {
+            Callback::Registered(ref framework_id, ref master_info) =>
+                scheduler.registered(driver, framework_id, master_info),
+            Callback::Reregistered(ref master_info) =>
+                scheduler.reregistered(driver, master_info),
+            Callback::ResourceOffers(ref offers) =>
+                scheduler.resource_offers(driver, offers.clone()),
+            Callback::StatusUpdate(ref task_status) =>
+                scheduler.status_update(driver, task_status),
+            Callback::Disconnected => scheduler.disconnected(driver),
+            Callback::OfferRescinded(ref offer_id) =>
+                scheduler.offer_rescinded(driver, offer_id),
+            Callback::FrameworkMessage(ref executor_id, ref slave_id,
+                                       ref data) =>
+                scheduler.framework_message(driver, executor_id, slave_id,
+                                            data),
+            Callback::SlaveLost(ref slave_id) =>
+                scheduler.slave_lost(driver, slave_id),
+            Callback::ExecutorLost(ref executor_id, ref slave_id, status) =>
+                scheduler.executor_lost(driver, executor_id, slave_id,
+                                        status),
+            Callback::Error(ref message) => scheduler.error(driver, message),
+            Callback::DecodeError(ref error) =>
+                scheduler.decode_error(driver, error),
+        }
+    }
+
+    /// The name of the callback in recordings, e.g. `status_update`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Callback::Registered(..) => "registered",
+            Callback::Reregistered(..) => "reregistered",
+            Callback::ResourceOffers(..) => "resource_offers",
+            Callback::StatusUpdate(..) => "status_update",
+            Callback::Disconnected => "disconnected",
+            Callback::OfferRescinded(..) => "offer_rescinded",
+            Callback::FrameworkMessage(..) => "framework_message",
+            Callback::SlaveLost(..) => "slave_lost",
+            Callback::ExecutorLost(..) => "executor_lost",
+            Callback::Error(..) => "error",
+            Callback::DecodeError(..) => "decode_error",
+        }
+    }
+}
+
+/// A callback and the time it was received, as a duration since the
+/// epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub timestamp: Duration,
+    pub callback: Callback,
+}
+
+impl Record {
+
+    /// Parses a line of a recording.
+    pub fn parse(line: &str) -> Result<Record, String> {
+        let mut words = line.split_whitespace();
+        let timestamp = try!(words.next()
+            .ok_or("empty line".to_string())
+            .and_then(parse_timestamp));
+        let name = try!(words.next().ok_or("missing callback".to_string()));
+        let arguments: Vec<&str> = words.collect();
+
+        let callback = match (name, arguments.len()) {
+            ("registered", 2) => Callback::Registered(
+                try!(message(arguments[0])), try!(message(arguments[1]))),
+            ("reregistered", 1) =>
+                Callback::Reregistered(try!(message(arguments[0]))),
+            ("resource_offers", _) => {
+                let mut offers = vec![];
+                for argument in arguments {
+                    offers.push(try!(message(argument)));
+                }
+                Callback::ResourceOffers(offers)
+            },
+            ("status_update", 1) =>
+                Callback::StatusUpdate(try!(message(arguments[0]))),
+            ("disconnected", 0) => Callback::Disconnected,
+            ("offer_rescinded", 1) =>
+                Callback::OfferRescinded(try!(message(arguments[0]))),
+            ("framework_message", 3) => Callback::FrameworkMessage(
+                try!(message(arguments[0])), try!(message(arguments[1])),
+                try!(text(arguments[2]))),
+            ("slave_lost", 1) =>
+                Callback::SlaveLost(try!(message(arguments[0]))),
+            ("executor_lost", 3) => Callback::ExecutorLost(
+                try!(message(arguments[0])), try!(message(arguments[1])),
+                try!(arguments[2].parse().map_err(|_| {
+                    format!("invalid status '{}'", arguments[2])
+                }))),
+            ("error", 1) => Callback::Error(try!(text(arguments[0]))),
+            ("decode_error", 3) => Callback::DecodeError(DecodeError {
+                message_type: try!(text(arguments[0])),
+                bytes: try!(unhex(arguments[1])),
+                reason: try!(text(arguments[2])),
+            }),
+            (name, count) => return Err(format!(
+                "unknown callback '{}' with {} argument(s)", name, count)),
+        };
+
+        Ok(Record {
+            timestamp: timestamp,
+            callback: callback,
+        })
+    }
+}
+
+impl fmt::Display for Record {
+
+    /// Formats the record as a line of a recording, without the newline.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}.{:09} {}", self.timestamp.as_secs(),
+                    self.timestamp.subsec_nanos(), self.callback.name()));
+
+        let mut arguments = vec![];
+        match self.callback {
+            Callback::Registered(ref framework_id, ref master_info) => {
+                arguments.push(encode(framework_id));
+                arguments.push(encode(master_info));
+            },
+            Callback::Reregistered(ref master_info) =>
+                arguments.push(encode(master_info)),
+            Callback::ResourceOffers(ref offers) => {
+                for offer in offers {
+                    arguments.push(encode(offer));
+                }
+            },
+            Callback::StatusUpdate(ref task_status) =>
+                arguments.push(encode(task_status)),
+            Callback::Disconnected => {},
+            Callback::OfferRescinded(ref offer_id) =>
+                arguments.push(encode(offer_id)),
+            Callback::FrameworkMessage(ref executor_id, ref slave_id,
+                                       ref data) => {
+                arguments.push(encode(executor_id));
+                arguments.push(encode(slave_id));
+                arguments.push(hex(data.as_bytes()));
+            },
+            Callback::SlaveLost(ref slave_id) =>
+                arguments.push(encode(slave_id)),
+            Callback::ExecutorLost(ref executor_id, ref slave_id, status) => {
+                arguments.push(encode(executor_id));
+                arguments.push(encode(slave_id));
+                arguments.push(status.to_string());
+            },
+            Callback::Error(ref message) =>
+                arguments.push(hex(message.as_bytes())),
+            Callback::DecodeError(ref error) => {
+                arguments.push(hex(error.message_type.as_bytes()));
+                arguments.push(hex(&error.bytes));
+                arguments.push(hex(error.reason.as_bytes()));
+            },
+        }
+
+        for argument in arguments {
+            try!(write!(f, " {}", argument));
+        }
+        Ok(())
+    }
+}
+
+/// A `Scheduler` that writes every callback to a recording before
+/// forwarding it to the scheduler it wraps.
+///
+/// Write failures are logged as errors rather than interrupting the
+/// wrapped scheduler.
+pub struct RecordingScheduler<'a, W: Write> {
+    scheduler: &'a Scheduler,
+    writer: RefCell<W>,
+}
+
+impl<'a> RecordingScheduler<'a, File> {
+
+    /// Records to a new file at `path`, replacing any existing one.
+    pub fn create<P: AsRef<Path>>(
+        scheduler: &'a Scheduler,
+        path: P
+    ) -> io::Result<RecordingScheduler<'a, File>> {
+        let file = try!(File::create(path));
+        Ok(RecordingScheduler::new(scheduler, file))
+    }
+}
+
+impl<'a, W: Write> RecordingScheduler<'a, W> {
+
+    pub fn new(
+        scheduler: &'a Scheduler,
+        writer: W
+    ) -> RecordingScheduler<'a, W> {
+        RecordingScheduler {
+            scheduler: scheduler,
+            writer: RefCell::new(writer),
+        }
+    }
+
+    /// Returns the writer, e.g. to inspect a recording made in memory.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    fn record(&self, callback: Callback) {
+        let record = Record {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::from_secs(0)),
+            callback: callback,
+        };
+
+        let mut writer = self.writer.borrow_mut();
+        if let Err(e) = writeln!(writer, "{}", record)
+            .and_then(|_| writer.flush()) {
+            error!("Failed to record callback: {}", e);
+        }
+    }
+}
+
+impl<'a, W: Write> Scheduler for RecordingScheduler<'a, W> {
+
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        master_info: &pb::MasterInfo) {
+
+        self.record(Callback::Registered(framework_id.clone(),
+                                         master_info.clone()));
+        self.scheduler.registered(driver, framework_id, master_info);
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) {
+
+        self.record(Callback::Reregistered(master_info.clone()));
+        self.scheduler.reregistered(driver, master_info);
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        self.record(Callback::ResourceOffers(offers.clone()));
+        self.scheduler.resource_offers(driver, offers);
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        self.record(Callback::StatusUpdate(task_status.clone()));
+        self.scheduler.status_update(driver, task_status);
+    }
+
+    fn disconnected(
+        &self,
+        driver: &SchedulerDriver) {
+
+        self.record(Callback::Disconnected);
+        self.scheduler.disconnected(driver);
+    }
+
+    fn offer_rescinded(
+        &self,
+        driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) {
+
+        self.record(Callback::OfferRescinded(offer_id.clone()));
+        self.scheduler.offer_rescinded(driver, offer_id);
+    }
+
+    fn framework_message(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &String) {
+
+        self.record(Callback::FrameworkMessage(executor_id.clone(),
+                                               slave_id.clone(),
+                                               data.clone()));
+        self.scheduler.framework_message(driver, executor_id, slave_id, data);
+    }
+
+    fn slave_lost(
+        &self,
+        driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) {
+
+        self.record(Callback::SlaveLost(slave_id.clone()));
+        self.scheduler.slave_lost(driver, slave_id);
+    }
+
+    fn executor_lost(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        status: i32) {
+
+        self.record(Callback::ExecutorLost(executor_id.clone(),
+                                           slave_id.clone(), status));
+        self.scheduler.executor_lost(driver, executor_id, slave_id, status);
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        self.record(Callback::Error(message.clone()));
+        self.scheduler.error(driver, message);
+    }
+
+    fn decode_error(
+        &self,
+        driver: &SchedulerDriver,
+        error: &DecodeError) {
+
+        self.record(Callback::DecodeError(error.clone()));
+        self.scheduler.decode_error(driver, error);
+    }
+}
+
+/// Reads a recording; blank lines are skipped.
+pub fn read<R: BufRead>(reader: R) -> io::Result<Vec<Record>> {
+    let mut records = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = try!(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = try!(Record::parse(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("line {}: {}", index + 1, e))
+        }));
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<Record>> {
+    let file = try!(File::open(path));
+    read(BufReader::new(file))
+}
+
+/// Delivers the recorded callbacks to `scheduler`, in order and without
+/// waiting between them.
+pub fn replay(
+    records: &[Record],
+    scheduler: &Scheduler,
+    driver: &SchedulerDriver
+) {
+    for record in records {
+        record.callback.deliver(scheduler, driver);
+    }
+}
+
+fn encode(message: &Message) -> String {
+    // Messages that were received are initialized, so this cannot fail.
+    hex(&message.write_to_bytes().unwrap_or(vec![]))
+}
+
+fn message<M: Message>(argument: &str) -> Result<M, String> {
+    let bytes = try!(unhex(argument));
+    protobuf::parse_from_bytes::<M>(&bytes).map_err(|e| e.to_string())
+}
+
+fn text(argument: &str) -> Result<String, String> {
+    let bytes = try!(unhex(argument));
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+// Empty values are written as `-`, so that they remain a word.
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "-".to_string();
+    }
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Result<Vec<u8>, String> {
+    if text == "-" {
+        return Ok(vec![]);
+    }
+    if text.len() % 2 != 0 {
+        return Err(format!("invalid hex '{}'", text));
+    }
+
+    text.as_bytes().chunks(2)
+        .map(|pair| str::from_utf8(pair).ok()
+             .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+             .ok_or(format!("invalid hex '{}'", text)))
+        .collect()
+}
+
+fn parse_timestamp(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid timestamp '{}'", text);
+    let mut parts = text.splitn(2, '.');
+    let seconds = try!(parts.next().unwrap_or("").parse::<u64>()
+                       .map_err(|_| invalid()));
+    let nanos = match parts.next() {
+        Some(fraction) if fraction.len() <= 9 && !fraction.is_empty() => {
+            let digits = try!(fraction.parse::<u32>().map_err(|_| invalid()));
+            digits * 10u32.pow(9 - fraction.len() as u32)
+        },
+        Some(_) => return Err(invalid()),
+        None => 0,
+    };
+    Ok(Duration::new(seconds, nanos))
+}
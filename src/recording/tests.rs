@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+
+    use ::proto::mesos as pb;
+    use ::recording::*;
+    use ::scheduler::{DecodeError, Scheduler, SchedulerDriver};
+    use ::testing::{DriverCall, FakeAgent, FakeMaster, MockSchedulerDriver};
+    use std::cell::Cell;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    // Launches a task on the first offer and declines the others; kills
+    // the task when it fails to become healthy.
+    struct OneShot {
+        launched: Cell<bool>,
+    }
+
+    impl Scheduler for OneShot {
+        fn resource_offers(
+            &self,
+            driver: &SchedulerDriver,
+            offers: Vec<pb::Offer>) {
+            for offer in offers {
+                if self.launched.get() {
+                    driver.decline_offer(offer.get_id(), &pb::Filters::new());
+                    continue;
+                }
+
+                self.launched.set(true);
+                let mut task = pb::TaskInfo::new();
+                task.set_name("one-shot".to_string());
+                task.mut_task_id().set_value("one-shot".to_string());
+                task.set_slave_id(offer.get_slave_id().clone());
+                driver.launch_tasks(offer.get_id(), &vec![&task],
+                                    &pb::Filters::new());
+            }
+        }
+
+        fn status_update(
+            &self,
+            driver: &SchedulerDriver,
+            status: &pb::TaskStatus) {
+            if status.has_healthy() && !status.get_healthy() {
+                driver.kill_task(status.get_task_id());
+            }
+        }
+    }
+
+    fn id<M: Default>(set: fn(&mut M, String), value: &str) -> M {
+        let mut id = M::default();
+        set(&mut id, value.to_string());
+        id
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let scheduler = OneShot { launched: Cell::new(false) };
+        let recorder = RecordingScheduler::new(&scheduler, vec![]);
+        {
+            let mut framework_info = pb::FrameworkInfo::new();
+            framework_info.set_name("one-shot".to_string());
+            framework_info.set_user("nobody".to_string());
+
+            let master = FakeMaster::new(&recorder, &framework_info);
+            master.add_agent(FakeAgent::new("a").cpus(1.0));
+            master.add_agent(FakeAgent::new("b").cpus(1.0));
+            master.start();
+            master.advance(Duration::from_secs(2));
+            master.rescind("offer-2");
+            master.error("Framework has been removed");
+        }
+
+        let recording = recorder.into_inner();
+        let records = read(Cursor::new(recording)).unwrap();
+        let names: Vec<&str> = records.iter()
+            .map(|record| record.callback.name())
+            .collect();
+        assert_eq!(names, vec!["registered", "resource_offers",
+                               "status_update", "error"]);
+        assert!(records.windows(2)
+                .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        match records[1].callback {
+            Callback::ResourceOffers(ref offers) => {
+                assert_eq!(offers.len(), 2);
+                assert_eq!(offers[1].get_hostname(), "b");
+            },
+            ref callback => panic!("unexpected {:?}", callback),
+        }
+
+        // Replaying the offers into a fresh scheduler makes it launch the
+        // same task.
+        let scheduler = OneShot { launched: Cell::new(false) };
+        let driver = MockSchedulerDriver::new();
+        replay(&records, &scheduler, &driver);
+        let calls = driver.calls();
+        assert_eq!(calls.len(), 2);
+        match calls[0] {
+            DriverCall::LaunchTasks(ref offer_id, ref tasks, _) => {
+                assert_eq!(offer_id.get_value(), "offer-1");
+                assert_eq!(tasks[0].get_task_id().get_value(), "one-shot");
+            },
+            ref call => panic!("unexpected {:?}", call),
+        }
+        assert_eq!(calls[1], DriverCall::DeclineOffer(
+            id(pb::OfferID::set_value, "offer-2"), pb::Filters::new()));
+    }
+
+    #[test]
+    fn every_callback_round_trips() {
+        let framework_id = id(pb::FrameworkID::set_value, "framework-1");
+        let executor_id = id(pb::ExecutorID::set_value, "executor-1");
+        let slave_id = id(pb::SlaveID::set_value, "slave-1");
+
+        let mut master_info = pb::MasterInfo::new();
+        master_info.set_id("master".to_string());
+        master_info.set_ip(0x0100007f);
+        master_info.set_port(5050);
+
+        let mut status = pb::TaskStatus::new();
+        status.set_task_id(id(pb::TaskID::set_value, "task-1"));
+        status.set_state(pb::TaskState::TASK_RUNNING);
+        status.set_healthy(false);
+
+        let callbacks = vec![
+            Callback::Registered(framework_id, master_info.clone()),
+            Callback::Reregistered(master_info),
+            Callback::ResourceOffers(vec![]),
+            Callback::StatusUpdate(status.clone()),
+            Callback::Disconnected,
+            Callback::OfferRescinded(id(pb::OfferID::set_value, "offer-1")),
+            Callback::FrameworkMessage(executor_id.clone(), slave_id.clone(),
+                                       "hello world".to_string()),
+            Callback::SlaveLost(slave_id.clone()),
+            Callback::ExecutorLost(executor_id, slave_id, -9),
+            Callback::Error("".to_string()),
+            Callback::DecodeError(DecodeError {
+                message_type: "Offer".to_string(),
+                bytes: vec![0x0a, 0xff],
+                reason: "unexpected EOF".to_string(),
+            }),
+        ];
+
+        for (index, callback) in callbacks.into_iter().enumerate() {
+            let record = Record {
+                timestamp: Duration::new(1444700000 + index as u64,
+                                         250000000),
+                callback: callback,
+            };
+            let line = record.to_string();
+            assert!(line.starts_with(&format!("{}.250000000 {}",
+                                              1444700000 + index,
+                                              record.callback.name())));
+            assert_eq!(Record::parse(&line), Ok(record));
+        }
+
+        // The task is killed when replayed against a mock driver.
+        let driver = MockSchedulerDriver::new();
+        let scheduler = OneShot { launched: Cell::new(true) };
+        Callback::StatusUpdate(status.clone()).deliver(&scheduler, &driver);
+        assert_eq!(driver.take_calls(), vec![
+            DriverCall::KillTask(status.get_task_id().clone()),
+        ]);
+        assert!(driver.calls().is_empty());
+    }
+
+    #[test]
+    fn invalid_recordings() {
+        assert_eq!(Record::parse("1.5 disconnected").unwrap().timestamp,
+                   Duration::from_millis(1500));
+        assert!(Record::parse("1.5 disconnected 00").is_err());
+        assert!(Record::parse("soon disconnected").is_err());
+        assert!(Record::parse("1 status_update 0g").is_err());
+        assert!(Record::parse("1 status_update 0a0").is_err());
+        assert!(Record::parse("1 unknown").is_err());
+
+        let error = read(Cursor::new("\n1 disconnected\n2 nonsense\n"))
+            .unwrap_err();
+        assert_eq!(error.to_string(),
+                   "line 3: unknown callback 'nonsense' with 0 argument(s)");
+    }
+}
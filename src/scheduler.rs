@@ -1,4 +1,27 @@
 use proto::mesos as pb;
+use std::error::Error;
+use std::fmt;
+
+/// A message from Mesos that could not be decoded, e.g. because libmesos
+/// was built from a different `mesos.proto` than this crate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeError {
+    /// The type of the message, e.g. `Offer`.
+    pub message_type: String,
+    /// The bytes that failed to decode.
+    pub bytes: Vec<u8>,
+    /// Why they failed to decode.
+    pub reason: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to decode {} from {} bytes: {}",
+               self.message_type, self.bytes.len(), self.reason)
+    }
+}
+
+impl Error for DecodeError {}
 
 /// Callback interface to be implemented by frameworks' schedulers.
 /// Note that only one callback will be invoked at a time, so it is not
@@ -9,6 +32,11 @@ use proto::mesos as pb;
 /// used to run this scheduler. The reference will not change for the
 /// duration of a scheduler. This is intended for convenience so that a
 /// scheduler doesn't need to store a reference to the driver itself.
+///
+/// Every callback has a default implementation that only logs it, so a
+/// scheduler need only implement the callbacks it is interested in.  Note
+/// that offers which are ignored are neither used nor declined, and remain
+/// allocated to the framework until they are rescinded.
 pub trait Scheduler {
 
     /// Invoked when the scheduler successfully registers with a Mesos master.
@@ -17,9 +45,12 @@ pub trait Scheduler {
     /// current master are provided as arguments.
     fn registered(
         &self,
-        driver: &SchedulerDriver,
+        _driver: &SchedulerDriver,
         framework_id: &pb::FrameworkID,
-        master_info: &pb::MasterInfo) -> ();
+        master_info: &pb::MasterInfo) -> () {
+        info!("Registered with framework id [{}] on master [{}]",
+              framework_id.get_value(), master_info.get_id());
+    }
 
     /// Invoked when the scheduler re-registers with a newly elected Mesos
     /// master. This is only called when the scheduler has previously been
@@ -27,8 +58,10 @@ pub trait Scheduler {
     /// elected master is provided as an argument.
     fn reregistered(
         &self,
-        driver: &SchedulerDriver,
-        master_info: &pb::MasterInfo) -> ();
+        _driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) -> () {
+        info!("Re-registered on master [{}]", master_info.get_id());
+    }
 
     /// Invoked when resources have been offered to this framework. A single
     /// offer will only contain resources from a single slave. Resources
@@ -48,8 +81,10 @@ pub trait Scheduler {
     /// and a message saying as much).
     fn resource_offers(
         &self,
-        driver: &SchedulerDriver,
-        offers: Vec<pb::Offer>) -> ();
+        _driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) -> () {
+        debug!("Ignoring [{}] offers", offers.len());
+    }
 
     /// Invoked when the status of a task has changed (e.g., a slave is lost
     /// and so the task is lost, a task finishes and an executor sends a
@@ -63,14 +98,20 @@ pub trait Scheduler {
     /// status on the driver.
     fn status_update(
         &self,
-        driver: &SchedulerDriver,
-        task_status: &pb::TaskStatus) -> ();
+        _driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) -> () {
+        debug!("Task [{}] is in state [{:?}]",
+               task_status.get_task_id().get_value(),
+               task_status.get_state());
+    }
 
     /// Invoked when the scheduler becomes "disconnected" from the master
     /// (e.g., the master fails and another is taking over).
     fn disconnected(
         &self,
-        driver: &SchedulerDriver) -> ();
+        _driver: &SchedulerDriver) -> () {
+        warn!("Disconnected from master");
+    }
 
     /// Invoked when an offer is no longer valid (e.g., the slave was lost or
     /// another framework used resources in the offer). If for whatever reason
@@ -79,42 +120,67 @@ pub trait Scheduler {
     /// invalid offer will receive TASK_LOST status updats for those tasks
     fn offer_rescinded(
         &self,
-        driver: &SchedulerDriver,
-        offer_id: &pb::OfferID) -> ();
+        _driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) -> () {
+        debug!("Offer [{}] rescinded", offer_id.get_value());
+    }
 
     /// Invoked when a slave has been determined unreachable (e.g., machine
     /// failure, network partition).  Most frameworks will need to reschedule
     /// any tasks launched on this slave on a new slave.
     fn slave_lost(
         &self,
-        driver: &SchedulerDriver,
-        slave_id: &pb::SlaveID) -> ();
+        _driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) -> () {
+        warn!("Slave [{}] lost", slave_id.get_value());
+    }
 
     /// Invoked when an executor has exited/terminated. Note that any tasks
     /// running will have TASK_LOST status updates automagically generated.
     fn executor_lost(
         &self,
-        driver: &SchedulerDriver,
+        _driver: &SchedulerDriver,
         executor_id: &pb::ExecutorID,
         slave_id: &pb::SlaveID,
-        status: i32) -> ();
+        status: i32) -> () {
+        warn!("Executor [{}] on slave [{}] lost with status [{}]",
+              executor_id.get_value(), slave_id.get_value(), status);
+    }
 
     /// Invoked when an executor sends a message. These messages are best
     /// effort; do not expect a framework message to be retransmitted in any
-    /// reliable fashion.
+    /// reliable fashion.  Data that is not valid UTF-8 is passed on with
+    /// the invalid sequences replaced by U+FFFD.
     fn framework_message(
         &self,
-        driver: &SchedulerDriver,
+        _driver: &SchedulerDriver,
         executor_id: &pb::ExecutorID,
         slave_id: &pb::SlaveID,
-        data: &String) -> ();
+        data: &String) -> () {
+        debug!("Ignoring message of [{}] bytes from executor [{}] on \
+                slave [{}]",
+               data.len(), executor_id.get_value(), slave_id.get_value());
+    }
 
     /// Invoked when there is an unrecoverable error in the scheduler or
     /// driver. The driver will be aborted BEFORE invoking this callback.
     fn error(
         &self,
-        driver: &SchedulerDriver,
-        message: &String) -> ();
+        _driver: &SchedulerDriver,
+        message: &String) -> () {
+        error!("Scheduler error: {}", message);
+    }
+
+    /// Invoked when the driver fails to decode a message from Mesos.  The
+    /// callback that would have carried the message is not invoked; the
+    /// driver then continues or aborts, depending on its policy (see
+    /// `native::DecodePolicy`).
+    fn decode_error(
+        &self,
+        _driver: &SchedulerDriver,
+        error: &DecodeError) -> () {
+        error!("{}", error);
+    }
 }
 
 /// Abstract interface for connecting a scheduler to Mesos. This interface is
@@ -182,6 +248,15 @@ pub trait SchedulerDriver {
         &self,
         task_id: &pb::TaskID) -> i32;
 
+    /// Allows the framework to query the status for non-terminal tasks.
+    /// This causes the master to send back the latest task status for each
+    /// task in `statuses`, if possible. Tasks that are no longer known will
+    /// result in a TASK_LOST update. If `statuses` is empty, then the master
+    /// will send the latest status for each task currently known.
+    fn reconcile_tasks(
+        &self,
+        statuses: &Vec<&pb::TaskStatus>) -> i32;
+
     /// Sends a message from the framework to one of its executors. These
     /// messages are best effort; do not expect a framework message to be
     /// retransmitted in any reliable fashion.
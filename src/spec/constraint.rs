@@ -0,0 +1,126 @@
+use proto::mesos as pb;
+use serde_json::Value;
+use std::fmt;
+
+/// A placement constraint on the offers instances may be bound to, in the
+/// style of Marathon.  `field` is `hostname` or the name of an attribute
+/// of the offering slave; offers without the field satisfy no constraint.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// `[field, UNIQUE]`: no two instances share a value.
+    Unique { field: String },
+
+    /// `[field, CLUSTER, value]`: every instance has the given value, or,
+    /// without a value, the value of the first instance placed.
+    Cluster { field: String, value: Option<String> },
+
+    /// `[field, MAX_PER, n]`: at most `n` instances share a value.
+    MaxPer { field: String, max: usize },
+}
+
+impl Constraint {
+
+    /// Parses the `[field, operator, value]` form used in specs.
+    pub fn parse(parts: &[Value]) -> Result<Constraint, String> {
+        let parts: Vec<String> = parts.iter().map(|part| match *part {
+            Value::String(ref part) => part.clone(),
+            ref part => part.to_string(),
+        }).collect();
+
+        let field = match parts.first() {
+            Some(field) if !field.is_empty() => field.clone(),
+            _ => return Err("expected [field, operator, value]".to_string()),
+        };
+
+        match (parts.get(1).map(|s| &s[..]), parts.get(2), parts.len()) {
+            (Some("UNIQUE"), None, 2) => Ok(Constraint::Unique {
+                field: field,
+            }),
+            (Some("CLUSTER"), value, 2..=3) => Ok(Constraint::Cluster {
+                field: field,
+                value: value.cloned(),
+            }),
+            (Some("MAX_PER"), Some(max), 3) => match max.parse() {
+                Ok(max) if max > 0 => Ok(Constraint::MaxPer {
+                    field: field,
+                    max: max,
+                }),
+                _ => Err(format!("MAX_PER needs a positive number, not \
+                                  '{}'", max)),
+            },
+            (Some(operator), _, _) =>
+                Err(format!("unsupported operator or arguments for '{}'",
+                            operator)),
+            (None, _, _) => Err("expected [field, operator, value]"
+                                .to_string()),
+        }
+    }
+
+    /// Returns whether an instance may be bound to `offer`, given the
+    /// offers that the instances placed so far were bound to.
+    pub fn satisfied(&self, offer: &pb::Offer, placed: &[&pb::Offer]) -> bool {
+        let value = match field_value(offer, self.field()) {
+            Some(value) => value,
+            None => return false,
+        };
+        let mut others = placed.iter()
+            .filter_map(|placed| field_value(placed, self.field()));
+
+        match *self {
+            Constraint::Unique { .. } => others.all(|other| other != value),
+            Constraint::Cluster { value: Some(ref expected), .. } =>
+                &value == expected,
+            Constraint::Cluster { value: None, .. } =>
+                others.next().map_or(true, |first| first == value),
+            Constraint::MaxPer { max, .. } =>
+                others.filter(|other| *other == value).count() < max,
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        match *self {
+            Constraint::Unique { ref field } |
+            Constraint::Cluster { ref field, .. } |
+            Constraint::MaxPer { ref field, .. } => field,
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Constraint::Unique { ref field } =>
+                write!(f, "{}:UNIQUE", field),
+            Constraint::Cluster { ref field, value: Some(ref value) } =>
+                write!(f, "{}:CLUSTER:{}", field, value),
+            Constraint::Cluster { ref field, value: None } =>
+                write!(f, "{}:CLUSTER", field),
+            Constraint::MaxPer { ref field, max } =>
+                write!(f, "{}:MAX_PER:{}", field, max),
+        }
+    }
+}
+
+// The value of `hostname` or of an attribute, as text.
+fn field_value(offer: &pb::Offer, field: &str) -> Option<String> {
+    if field == "hostname" {
+        return Some(offer.get_hostname().to_string());
+    }
+
+    offer.get_attributes().iter()
+        .find(|attribute| attribute.get_name() == field)
+        .map(|attribute| match attribute.get_field_type() {
+            pb::Value_Type::SCALAR =>
+                attribute.get_scalar().get_value().to_string(),
+            pb::Value_Type::TEXT =>
+                attribute.get_text().get_value().to_string(),
+            pb::Value_Type::SET => attribute.get_set().get_item().join(","),
+            pb::Value_Type::RANGES => {
+                let ranges: Vec<String> = attribute.get_ranges()
+                    .get_range().iter()
+                    .map(|r| format!("[{}-{}]", r.get_begin(), r.get_end()))
+                    .collect();
+                ranges.join(",")
+            },
+        })
+}
@@ -0,0 +1,270 @@
+//! # Declarative application specs.
+//!
+//! An `AppSpec` describes a workload in a YAML or JSON file rather than in
+//! code:
+//!
+//! ```text
+//! name: web
+//! instances: 3
+//! cpus: 0.5
+//! mem: 128
+//! ports: 1
+//! command:
+//!   shell: ./web --port $PORT0
+//!   uris:
+//!     - value: http://example.com/web.tgz
+//! health_check:
+//!   http: {port_index: 0, path: /health}
+//! constraints:
+//!   - [hostname, UNIQUE]
+//! labels:
+//!   team: infra
+//! ```
+//!
+//! `compile` validates a spec and turns it into a `TaskTemplate`, which
+//! `bind`s one instance at a time to an offer: it takes the resources and
+//! host ports from the offer, checks the placement constraints and fills
+//! the ports into the command environment (`PORT0`, `PORT1`, ..., and
+//! `PORT` for the first one), the Docker port mappings and the health
+//! check.
+//!
+//! Messages that Mesos already describes, such as URIs and volumes, are
+//! written in their JSON form (see `json`).
+//!
+//! This module is only available with the `spec` feature.
+
+mod constraint;
+mod template;
+mod tests;
+
+pub use self::constraint::Constraint;
+pub use self::template::{BindError, TaskTemplate};
+
+use builder::validation::ValidationError;
+use json::Json;
+use proto::mesos as pb;
+use serde_json;
+use serde_yaml;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// A workload: what each instance runs, the resources it needs and where
+/// it may be placed.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppSpec {
+    /// The name of the tasks.
+    pub name: String,
+
+    /// The number of instances to run, 1 by default.
+    #[serde(default = "one")]
+    pub instances: u32,
+
+    #[serde(default)]
+    pub cpus: f64,
+
+    /// Memory in megabytes.
+    #[serde(default)]
+    pub mem: f64,
+
+    /// Sandbox disk space in megabytes.
+    #[serde(default)]
+    pub disk: f64,
+
+    /// The number of host ports each instance needs.
+    #[serde(default)]
+    pub ports: usize,
+
+    /// Other scalar resources, e.g. `gpus: 1`.
+    #[serde(default)]
+    pub resources: BTreeMap<String, f64>,
+
+    /// The command to run.  Docker containers may omit it to run the
+    /// image's entry point.
+    pub command: Option<CommandSpec>,
+
+    pub container: Option<ContainerSpec>,
+
+    pub health_check: Option<HealthCheckSpec>,
+
+    /// Placement constraints, as `[field, operator]` or `[field, operator,
+    /// value]`; see `Constraint`.
+    #[serde(default)]
+    pub constraints: Vec<Vec<serde_json::Value>>,
+
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// The command of an `AppSpec`; exactly one of `shell` and `exec` must be
+/// set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandSpec {
+    /// A command line run with `/bin/sh -c`.
+    pub shell: Option<String>,
+
+    /// An executable and its arguments, run without a shell.
+    pub exec: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub uris: Vec<Json<pb::CommandInfo_URI>>,
+
+    pub user: Option<String>,
+}
+
+/// The container of an `AppSpec`: a Docker container if `docker` is set,
+/// and otherwise a Mesos container.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContainerSpec {
+    pub docker: Option<DockerSpec>,
+
+    #[serde(default)]
+    pub volumes: Vec<Json<pb::Volume>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DockerSpec {
+    pub image: String,
+
+    /// `HOST`, `BRIDGE` or `NONE`.
+    pub network: Option<String>,
+
+    #[serde(default)]
+    pub port_mappings: Vec<PortMappingSpec>,
+
+    #[serde(default)]
+    pub privileged: bool,
+
+    #[serde(default)]
+    pub force_pull_image: bool,
+
+    /// Extra `docker run` options, e.g. `memory-swap: "-1"`.
+    #[serde(default)]
+    pub parameters: BTreeMap<String, String>,
+}
+
+/// Maps a container port to one of the host ports of the instance.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortMappingSpec {
+    pub container_port: u32,
+
+    /// Which of the instance's host ports to map; the position of the
+    /// mapping by default.
+    pub port_index: Option<usize>,
+
+    /// `tcp` by default.
+    pub protocol: Option<String>,
+}
+
+/// The health check of an `AppSpec`; exactly one of `http` and `command`
+/// must be set.  Unset timings keep the Mesos defaults.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheckSpec {
+    pub http: Option<HttpCheckSpec>,
+
+    /// A shell command that exits with 0 while the task is healthy.
+    pub command: Option<String>,
+
+    pub delay_seconds: Option<f64>,
+    pub interval_seconds: Option<f64>,
+    pub timeout_seconds: Option<f64>,
+    pub grace_period_seconds: Option<f64>,
+    pub consecutive_failures: Option<u32>,
+}
+
+/// An HTTP health check against either a fixed `port` or one of the
+/// instance's host ports.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpCheckSpec {
+    pub port: Option<u32>,
+    pub port_index: Option<usize>,
+
+    /// `/` by default.
+    pub path: Option<String>,
+
+    /// The statuses that count as healthy; 200-399 by default.
+    #[serde(default)]
+    pub statuses: Vec<u32>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// The reasons a spec file could not be loaded.
+#[derive(Debug)]
+pub enum SpecError {
+    Io(io::Error),
+
+    /// The file is not valid YAML or JSON, or does not describe a spec.
+    Syntax(String),
+
+    /// The spec does not describe valid tasks.
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpecError::Io(ref error) =>
+                write!(f, "Reading spec failed: {}", error),
+            SpecError::Syntax(ref reason) =>
+                write!(f, "Invalid spec: {}", reason),
+            SpecError::Invalid(ref error) =>
+                write!(f, "Invalid spec: {}", error),
+        }
+    }
+}
+
+impl Error for SpecError {}
+
+impl AppSpec {
+
+    pub fn from_yaml(yaml: &str) -> Result<AppSpec, SpecError> {
+        serde_yaml::from_str(yaml)
+            .map_err(|e| SpecError::Syntax(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<AppSpec, SpecError> {
+        serde_json::from_str(json)
+            .map_err(|e| SpecError::Syntax(e.to_string()))
+    }
+
+    /// Reads a spec from a `.json` file, or from a YAML file otherwise.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<AppSpec, SpecError> {
+        let path = path.as_ref();
+        let mut contents = String::new();
+        try!(File::open(path)
+             .and_then(|mut file| file.read_to_string(&mut contents))
+             .map_err(SpecError::Io));
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => AppSpec::from_json(&contents),
+            _ => AppSpec::from_yaml(&contents),
+        }
+    }
+
+    /// Validates the spec and compiles it into a `TaskTemplate`.
+    pub fn compile(&self) -> Result<TaskTemplate, ValidationError> {
+        TaskTemplate::compile(self)
+    }
+}
+
+/// Reads and compiles a spec file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<TaskTemplate, SpecError> {
+    let spec = try!(AppSpec::from_file(path));
+    spec.compile().map_err(SpecError::Invalid)
+}
@@ -0,0 +1,494 @@
+use builder::resource;
+use builder::validation::{self, ValidationError, Violation};
+use proto::mesos as pb;
+use spec::{AppSpec, Constraint, ContainerSpec, HealthCheckSpec};
+use std::error::Error;
+use std::fmt;
+
+// Scalar amounts below this are considered satisfied, to absorb rounding.
+const EPSILON: f64 = 1e-9;
+
+/// A compiled `AppSpec`: a `TaskInfo` with everything but the task id, the
+/// slave id, the resources and the host ports filled in.
+#[derive(Clone, Debug)]
+pub struct TaskTemplate {
+    task: pb::TaskInfo,
+    instances: u32,
+    scalars: Vec<(String, f64)>,
+    ports: usize,
+    port_mappings: Vec<(usize, pb::ContainerInfo_DockerInfo_PortMapping)>,
+    health_check_port: Option<usize>,
+    constraints: Vec<Constraint>,
+}
+
+/// The reasons an offer cannot hold an instance.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BindError {
+    /// The offer has less of a resource than an instance needs.
+    Insufficient { resource: String, needed: f64, offered: f64 },
+
+    /// Binding to the offer would violate a placement constraint.
+    Constraint(Constraint),
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BindError::Insufficient { ref resource, needed, offered } =>
+                write!(f, "Insufficient {}: needed {}, offered {}",
+                       resource, needed, offered),
+            BindError::Constraint(ref constraint) =>
+                write!(f, "Constraint {} is not satisfied", constraint),
+        }
+    }
+}
+
+impl Error for BindError {}
+
+impl TaskTemplate {
+
+    pub fn compile(spec: &AppSpec) -> Result<TaskTemplate, ValidationError> {
+        let mut violations = vec![];
+
+        let mut task = pb::TaskInfo::new();
+        task.set_name(spec.name.clone());
+
+        let mut scalars = vec![("cpus".to_string(), spec.cpus),
+                               ("mem".to_string(), spec.mem),
+                               ("disk".to_string(), spec.disk)];
+        for (name, &amount) in &spec.resources {
+            if name == "ports" {
+                invalid("resources.ports", "use 'ports' for host ports",
+                        &mut violations);
+            }
+            scalars.push((name.clone(), amount));
+        }
+
+        if let Some(command) = compile_command(spec, &mut violations) {
+            task.set_command(command);
+        }
+
+        let mut port_mappings = vec![];
+        if let Some(ref container) = spec.container {
+            task.set_container(compile_container(
+                container, spec.ports, &mut port_mappings, &mut violations));
+        }
+
+        let mut health_check_port = None;
+        if let Some(ref check) = spec.health_check {
+            task.set_health_check(compile_health_check(
+                check, spec.ports, &mut health_check_port, &mut violations));
+        }
+
+        let mut constraints = vec![];
+        for (i, parts) in spec.constraints.iter().enumerate() {
+            match Constraint::parse(parts) {
+                Ok(constraint) => constraints.push(constraint),
+                Err(reason) => invalid(&format!("constraints[{}]", i),
+                                       &reason, &mut violations),
+            }
+        }
+
+        for (key, value) in &spec.labels {
+            let mut label = pb::Label::new();
+            label.set_key(key.clone());
+            label.set_value(value.clone());
+            task.mut_labels().mut_labels().push(label);
+        }
+
+        let template = TaskTemplate {
+            task: task,
+            instances: spec.instances,
+            scalars: scalars,
+            ports: spec.ports,
+            port_mappings: port_mappings,
+            health_check_port: health_check_port,
+            constraints: constraints,
+        };
+
+        // Check the tasks the template produces with placeholder ids and
+        // resources, so that problems are reported against the spec rather
+        // than when binding.
+        if violations.is_empty() {
+            let mut slave_id = pb::SlaveID::new();
+            slave_id.set_value("slave".to_string());
+            let ports: Vec<u64> = (0..spec.ports as u64)
+                .map(|i| 31000 + i)
+                .collect();
+            let mut resources: Vec<pb::Resource> = template.scalars.iter()
+                .filter(|&&(_, amount)| amount != 0.0)
+                .map(|&(ref name, amount)| resource::scalar(name, amount))
+                .collect();
+            if !ports.is_empty() {
+                resources.push(port_resource(&ports, "*"));
+            }
+
+            let task = template.instantiate(&spec.name, &slave_id,
+                                            resources, &ports);
+            if let Err(error) = validation::task_info(&task) {
+                violations.extend(error.violations);
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(template)
+        } else {
+            Err(ValidationError { violations: violations })
+        }
+    }
+
+    /// The number of instances the spec asks for.
+    pub fn instances(&self) -> u32 {
+        self.instances
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Binds one instance to `offer` as the task `task_id`.  `placed` are
+    /// the offers that the instances placed so far were bound to, for the
+    /// placement constraints.
+    ///
+    /// Resources are taken from the offer in order, keeping the role (and
+    /// reservation) of each, so that reserved resources are used where the
+    /// offer has them.
+    pub fn bind(
+        &self,
+        offer: &pb::Offer,
+        task_id: &str,
+        placed: &[&pb::Offer]
+    ) -> Result<pb::TaskInfo, BindError> {
+        for constraint in &self.constraints {
+            if !constraint.satisfied(offer, placed) {
+                return Err(BindError::Constraint(constraint.clone()));
+            }
+        }
+
+        let mut resources = vec![];
+        for &(ref name, amount) in &self.scalars {
+            let mut needed = amount;
+            for offered in offer.get_resources() {
+                if needed <= EPSILON {
+                    break;
+                }
+                if offered.get_name() != name
+                    || offered.get_field_type() != pb::Value_Type::SCALAR {
+                    continue;
+                }
+
+                let taken = needed.min(offered.get_scalar().get_value());
+                if taken > 0.0 {
+                    let mut resource = resource::scalar(name, taken);
+                    copy_role(offered, &mut resource);
+                    resources.push(resource);
+                    needed -= taken;
+                }
+            }
+            if needed > EPSILON {
+                return Err(BindError::Insufficient {
+                    resource: name.clone(),
+                    needed: amount,
+                    offered: amount - needed,
+                });
+            }
+        }
+
+        let mut ports = vec![];
+        'offered: for offered in offer.get_resources() {
+            if offered.get_name() != "ports"
+                || offered.get_field_type() != pb::Value_Type::RANGES {
+                continue;
+            }
+
+            let mut taken = vec![];
+            for range in offered.get_ranges().get_range() {
+                for port in range.get_begin()..range.get_end() + 1 {
+                    if ports.len() + taken.len() == self.ports {
+                        break;
+                    }
+                    taken.push(port);
+                }
+            }
+            if !taken.is_empty() {
+                let mut resource = port_resource(&taken, "*");
+                copy_role(offered, &mut resource);
+                resources.push(resource);
+                ports.extend(taken);
+            }
+            if ports.len() == self.ports {
+                break 'offered;
+            }
+        }
+        if ports.len() < self.ports {
+            return Err(BindError::Insufficient {
+                resource: "ports".to_string(),
+                needed: self.ports as f64,
+                offered: ports.len() as f64,
+            });
+        }
+
+        Ok(self.instantiate(task_id, offer.get_slave_id(), resources,
+                            &ports))
+    }
+
+    fn instantiate(
+        &self,
+        task_id: &str,
+        slave_id: &pb::SlaveID,
+        resources: Vec<pb::Resource>,
+        ports: &[u64]
+    ) -> pb::TaskInfo {
+        let mut task = self.task.clone();
+        task.mut_task_id().set_value(task_id.to_string());
+        task.set_slave_id(slave_id.clone());
+        for resource in resources {
+            task.mut_resources().push(resource);
+        }
+
+        if task.has_command() && !ports.is_empty() {
+            let environment = task.mut_command().mut_environment();
+            for (i, port) in ports.iter().enumerate() {
+                environment.mut_variables()
+                    .push(variable(&format!("PORT{}", i), *port));
+            }
+            environment.mut_variables().push(variable("PORT", ports[0]));
+        }
+
+        for &(index, ref mapping) in &self.port_mappings {
+            let mut mapping = mapping.clone();
+            mapping.set_host_port(ports[index] as u32);
+            task.mut_container().mut_docker().mut_port_mappings()
+                .push(mapping);
+        }
+
+        if let Some(index) = self.health_check_port {
+            task.mut_health_check().mut_http()
+                .set_port(ports[index] as u32);
+        }
+        task
+    }
+}
+
+fn compile_command(
+    spec: &AppSpec,
+    violations: &mut Vec<Violation>
+) -> Option<pb::CommandInfo> {
+    let mut command = pb::CommandInfo::new();
+    let spec_command = match spec.command {
+        Some(ref spec_command) => spec_command,
+        None => {
+            if spec.container.as_ref().map_or(false, |c| c.docker.is_some()) {
+                // Run the entry point of the image.
+                command.set_shell(false);
+                return Some(command);
+            }
+            violations.push(Violation::Missing {
+                field: "command".to_string(),
+            });
+            return None;
+        },
+    };
+
+    match (&spec_command.shell, &spec_command.exec) {
+        (&Some(ref shell), &None) => command.set_value(shell.clone()),
+        (&None, &Some(ref exec)) => {
+            command.set_shell(false);
+            if let Some(executable) = exec.first() {
+                command.set_value(executable.clone());
+            }
+            for argument in exec {
+                command.mut_arguments().push(argument.clone());
+            }
+        },
+        (&Some(_), &Some(_)) => violations.push(Violation::Conflict {
+            field: "command.shell".to_string(),
+            other: "command.exec".to_string(),
+        }),
+        (&None, &None) => violations.push(Violation::Missing {
+            field: "command.shell or command.exec".to_string(),
+        }),
+    }
+
+    for (name, value) in &spec_command.env {
+        let mut variable = pb::Environment_Variable::new();
+        variable.set_name(name.clone());
+        variable.set_value(value.clone());
+        command.mut_environment().mut_variables().push(variable);
+    }
+    for uri in &spec_command.uris {
+        command.mut_uris().push(uri.0.clone());
+    }
+    if let Some(ref user) = spec_command.user {
+        command.set_user(user.clone());
+    }
+    Some(command)
+}
+
+// The port mappings are returned separately, with the index of the host
+// port they use, since the host ports are only known when binding.
+fn compile_container(
+    spec: &ContainerSpec,
+    ports: usize,
+    port_mappings: &mut Vec<(usize, pb::ContainerInfo_DockerInfo_PortMapping)>,
+    violations: &mut Vec<Violation>
+) -> pb::ContainerInfo {
+    let mut container = pb::ContainerInfo::new();
+    for volume in &spec.volumes {
+        container.mut_volumes().push(volume.0.clone());
+    }
+
+    let spec_docker = match spec.docker {
+        Some(ref spec_docker) => spec_docker,
+        None => {
+            container.set_field_type(pb::ContainerInfo_Type::MESOS);
+            return container;
+        },
+    };
+
+    container.set_field_type(pb::ContainerInfo_Type::DOCKER);
+    {
+        let docker = container.mut_docker();
+        docker.set_image(spec_docker.image.clone());
+        docker.set_privileged(spec_docker.privileged);
+        docker.set_force_pull_image(spec_docker.force_pull_image);
+
+        match spec_docker.network.as_ref().map(|network| &network[..]) {
+            None => (),
+            Some("HOST") => docker.set_network(
+                pb::ContainerInfo_DockerInfo_Network::HOST),
+            Some("BRIDGE") => docker.set_network(
+                pb::ContainerInfo_DockerInfo_Network::BRIDGE),
+            Some("NONE") => docker.set_network(
+                pb::ContainerInfo_DockerInfo_Network::NONE),
+            Some(_) => invalid("container.docker.network",
+                               "must be 'HOST', 'BRIDGE' or 'NONE'",
+                               violations),
+        }
+
+        for (key, value) in &spec_docker.parameters {
+            let mut parameter = pb::Parameter::new();
+            parameter.set_key(key.clone());
+            parameter.set_value(value.clone());
+            docker.mut_parameters().push(parameter);
+        }
+    }
+
+    for (i, spec_mapping) in spec_docker.port_mappings.iter().enumerate() {
+        let index = spec_mapping.port_index.unwrap_or(i);
+        if index >= ports {
+            invalid(&format!("container.docker.port_mappings[{}].port_index",
+                             i),
+                    "must be less than 'ports'", violations);
+        }
+
+        let mut mapping = pb::ContainerInfo_DockerInfo_PortMapping::new();
+        mapping.set_container_port(spec_mapping.container_port);
+        mapping.set_protocol(spec_mapping.protocol.clone()
+                             .unwrap_or("tcp".to_string()));
+        port_mappings.push((index, mapping));
+    }
+    container
+}
+
+// The index of the host port an HTTP check uses, if any, is returned
+// separately, since the host ports are only known when binding.
+fn compile_health_check(
+    spec: &HealthCheckSpec,
+    ports: usize,
+    port_index: &mut Option<usize>,
+    violations: &mut Vec<Violation>
+) -> pb::HealthCheck {
+    let mut check = pb::HealthCheck::new();
+    match (&spec.http, &spec.command) {
+        (&Some(ref http), &None) => {
+            match (http.port, http.port_index) {
+                (Some(port), None) => check.mut_http().set_port(port),
+                (None, Some(index)) => {
+                    if index >= ports {
+                        invalid("health_check.http.port_index",
+                                "must be less than 'ports'", violations);
+                    }
+                    *port_index = Some(index);
+                },
+                (Some(_), Some(_)) => violations.push(Violation::Conflict {
+                    field: "health_check.http.port".to_string(),
+                    other: "health_check.http.port_index".to_string(),
+                }),
+                (None, None) => violations.push(Violation::Missing {
+                    field: "health_check.http.port".to_string(),
+                }),
+            }
+            if let Some(ref path) = http.path {
+                check.mut_http().set_path(path.clone());
+            }
+            for &status in &http.statuses {
+                check.mut_http().mut_statuses().push(status);
+            }
+        },
+        (&None, &Some(ref command)) => {
+            check.mut_command().set_value(command.clone());
+        },
+        (&Some(_), &Some(_)) => violations.push(Violation::Conflict {
+            field: "health_check.http".to_string(),
+            other: "health_check.command".to_string(),
+        }),
+        (&None, &None) => violations.push(Violation::Missing {
+            field: "health_check.http or health_check.command".to_string(),
+        }),
+    }
+
+    if let Some(seconds) = spec.delay_seconds {
+        check.set_delay_seconds(seconds);
+    }
+    if let Some(seconds) = spec.interval_seconds {
+        check.set_interval_seconds(seconds);
+    }
+    if let Some(seconds) = spec.timeout_seconds {
+        check.set_timeout_seconds(seconds);
+    }
+    if let Some(seconds) = spec.grace_period_seconds {
+        check.set_grace_period_seconds(seconds);
+    }
+    if let Some(failures) = spec.consecutive_failures {
+        check.set_consecutive_failures(failures);
+    }
+    check
+}
+
+fn invalid(field: &str, reason: &str, violations: &mut Vec<Violation>) {
+    violations.push(Violation::Invalid {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    });
+}
+
+// Returns a `ports` resource with consecutive ports merged into ranges.
+fn port_resource(ports: &[u64], role: &str) -> pb::Resource {
+    let mut ranges: Vec<(u64, u64)> = vec![];
+    for &port in ports {
+        match ranges.last_mut() {
+            Some(&mut (_, ref mut end)) if *end + 1 == port => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    let mut resource = resource::ranges("ports", &ranges);
+    resource.set_role(role.to_string());
+    resource
+}
+
+fn copy_role(offered: &pb::Resource, resource: &mut pb::Resource) {
+    if offered.has_role() {
+        resource.set_role(offered.get_role().to_string());
+    }
+    if offered.has_reservation() {
+        resource.set_reservation(offered.get_reservation().clone());
+    }
+}
+
+fn variable(name: &str, port: u64) -> pb::Environment_Variable {
+    let mut variable = pb::Environment_Variable::new();
+    variable.set_name(name.to_string());
+    variable.set_value(port.to_string());
+    variable
+}
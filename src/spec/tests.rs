@@ -0,0 +1,231 @@
+#[cfg(test)]
+mod tests {
+
+    use ::builder::resource;
+    use ::builder::validation::Violation;
+    use ::json;
+    use ::proto::mesos as pb;
+    use ::spec::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/fixtures/spec")
+            .join(name)
+    }
+
+    fn offer(hostname: &str, rack: &str) -> pb::Offer {
+        let mut offer: pb::Offer = json::from_str(r#"{
+            "id": {"value": "offer-1"},
+            "framework_id": {"value": "framework-1"},
+            "slave_id": {"value": "slave-1"},
+            "hostname": "",
+            "attributes": [
+                {"name": "zone", "type": "TEXT", "text": {"value": "a"}}
+            ]
+        }"#).unwrap();
+        offer.set_hostname(hostname.to_string());
+
+        let mut attribute = pb::Attribute::new();
+        attribute.set_name("rack".to_string());
+        attribute.set_field_type(pb::Value_Type::TEXT);
+        attribute.mut_text().set_value(rack.to_string());
+        offer.mut_attributes().push(attribute);
+
+        let mut reserved = resource::scalar("cpus", 0.25);
+        reserved.set_role("web".to_string());
+        offer.mut_resources().push(reserved);
+        offer.mut_resources().push(resource::scalar("cpus", 4.0));
+        offer.mut_resources().push(resource::scalar("mem", 1024.0));
+        offer.mut_resources().push(
+            resource::ranges("ports", &[(31000, 31000), (31005, 31010)]));
+        offer
+    }
+
+    fn variable<'a>(task: &'a pb::TaskInfo, name: &str) -> &'a str {
+        task.get_command().get_environment().get_variables().iter()
+            .find(|variable| variable.get_name() == name)
+            .map(|variable| variable.get_value())
+            .unwrap()
+    }
+
+    #[test]
+    fn compiles_and_binds_yaml_spec() {
+        let template = load(fixture("web.yaml")).unwrap();
+        assert_eq!(template.instances(), 3);
+        assert_eq!(template.constraints()[1], Constraint::MaxPer {
+            field: "rack".to_string(),
+            max: 2,
+        });
+
+        let offer = offer("agent-1", "r1");
+        let task = template.bind(&offer, "web.1", &[]).unwrap();
+
+        assert_eq!(task.get_name(), "web");
+        assert_eq!(task.get_task_id().get_value(), "web.1");
+        assert_eq!(task.get_slave_id().get_value(), "slave-1");
+
+        // Reserved cpus are used first, then the rest from '*'.
+        let resources = task.get_resources();
+        assert_eq!(resources.len(), 4);
+        assert_eq!(resources[0].get_role(), "web");
+        assert_eq!(resources[0].get_scalar().get_value(), 0.25);
+        assert_eq!(resources[1].get_scalar().get_value(), 0.25);
+        assert_eq!(resources[2].get_name(), "mem");
+        let ports = resources[3].get_ranges().get_range();
+        assert_eq!((ports[0].get_begin(), ports[0].get_end()),
+                   (31000, 31000));
+        assert_eq!((ports[1].get_begin(), ports[1].get_end()),
+                   (31005, 31005));
+
+        assert_eq!(variable(&task, "PORT0"), "31000");
+        assert_eq!(variable(&task, "PORT1"), "31005");
+        assert_eq!(variable(&task, "PORT"), "31000");
+        assert_eq!(variable(&task, "LOG_LEVEL"), "info");
+        assert!(task.get_command().get_uris()[0].get_cache());
+
+        let docker = task.get_container().get_docker();
+        assert_eq!(docker.get_network(),
+                   pb::ContainerInfo_DockerInfo_Network::BRIDGE);
+        let mappings = docker.get_port_mappings();
+        assert_eq!((mappings[0].get_host_port(),
+                    mappings[0].get_container_port()), (31000, 8080));
+        assert_eq!((mappings[1].get_host_port(),
+                    mappings[1].get_container_port()), (31005, 9090));
+        assert_eq!(task.get_container().get_volumes()[0].get_mode(),
+                   pb::Volume_Mode::RW);
+
+        let check = task.get_health_check();
+        assert_eq!(check.get_http().get_port(), 31000);
+        assert_eq!(check.get_http().get_path(), "/health");
+        assert_eq!(check.get_interval_seconds(), 5.0);
+        assert_eq!(task.get_labels().get_labels()[0].get_key(), "team");
+    }
+
+    #[test]
+    fn binding_checks_resources_and_constraints() {
+        let template = load(fixture("web.yaml")).unwrap();
+        let first = offer("agent-1", "r1");
+        let second = offer("agent-2", "r1");
+        let third = offer("agent-3", "r1");
+
+        assert_eq!(
+            template.bind(&first, "web.2", &[&first]).unwrap_err(),
+            BindError::Constraint(Constraint::Unique {
+                field: "hostname".to_string(),
+            }));
+        assert!(template.bind(&second, "web.2", &[&first]).is_ok());
+        match template.bind(&third, "web.3", &[&first, &second]) {
+            Err(BindError::Constraint(Constraint::MaxPer { .. })) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut small = offer("agent-4", "r2");
+        small.mut_resources().remove(3);
+        small.mut_resources()
+            .push(resource::ranges("ports", &[(31000, 31000)]));
+        assert_eq!(template.bind(&small, "web.1", &[]).unwrap_err(),
+                   BindError::Insufficient {
+                       resource: "ports".to_string(),
+                       needed: 2.0,
+                       offered: 1.0,
+                   });
+    }
+
+    #[test]
+    fn compiles_json_spec() {
+        let template = AppSpec::from_file(fixture("worker.json")).unwrap()
+            .compile()
+            .unwrap();
+
+        let offer = offer("agent-1", "r1");
+        assert_eq!(template.bind(&offer, "worker.1", &[]).unwrap_err(),
+                   BindError::Insufficient {
+                       resource: "gpus".to_string(),
+                       needed: 1.0,
+                       offered: 0.0,
+                   });
+
+        let mut gpu_offer = offer.clone();
+        gpu_offer.mut_resources().push(resource::scalar("gpus", 2.0));
+        let task = template.bind(&gpu_offer, "worker.1", &[]).unwrap();
+
+        let command = task.get_command();
+        assert!(!command.get_shell());
+        assert_eq!(command.get_value(), "/usr/bin/worker");
+        assert_eq!(command.get_arguments().len(), 3);
+        assert_eq!(command.get_user(), "nobody");
+        assert!(!command.has_environment());
+        assert_eq!(task.get_health_check().get_command().get_value(),
+                   "test -f /tmp/alive");
+
+        let mut elsewhere = gpu_offer.clone();
+        elsewhere.mut_attributes()[0].mut_text().set_value("b".to_string());
+        assert!(template.bind(&elsewhere, "worker.1", &[]).is_err());
+    }
+
+    #[test]
+    fn reports_every_problem_in_a_spec() {
+        let spec = AppSpec::from_yaml("
+            name: bad/name
+            ports: 1
+            command:
+              shell: ./run
+              exec: [./run]
+            container:
+              docker:
+                image: app
+                network: OVERLAY
+                port_mappings:
+                  - {container_port: 80, port_index: 1}
+            health_check:
+              http: {path: /health}
+            constraints:
+              - [hostname, SOMETIMES]
+        ").unwrap();
+
+        let violations = spec.compile().unwrap_err().violations;
+        assert_eq!(violations, vec![
+            Violation::Conflict {
+                field: "command.shell".to_string(),
+                other: "command.exec".to_string(),
+            },
+            Violation::Invalid {
+                field: "container.docker.network".to_string(),
+                reason: "must be 'HOST', 'BRIDGE' or 'NONE'".to_string(),
+            },
+            Violation::Invalid {
+                field: "container.docker.port_mappings[0].port_index"
+                    .to_string(),
+                reason: "must be less than 'ports'".to_string(),
+            },
+            Violation::Missing {
+                field: "health_check.http.port".to_string(),
+            },
+            Violation::Invalid {
+                field: "constraints[0]".to_string(),
+                reason: "unsupported operator or arguments for 'SOMETIMES'"
+                    .to_string(),
+            },
+        ]);
+
+        // Problems with the tasks themselves are found by validating them.
+        let spec = AppSpec::from_yaml("
+            name: bad/name
+            cpus: 1
+            command: {shell: ./run}
+        ").unwrap();
+        assert_eq!(spec.compile().unwrap_err().violations,
+                   vec![Violation::Invalid {
+                       field: "task_id".to_string(),
+                       reason: "must not contain path separators"
+                           .to_string(),
+                   }]);
+
+        match AppSpec::from_yaml("name: web\ncpu: 1\n") {
+            Err(SpecError::Syntax(ref reason)) =>
+                assert!(reason.contains("unknown field `cpu`")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use state::StateStore;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+// Suffix of files being written; they are renamed into place once complete
+// and are ignored when listing keys.
+const TEMP_SUFFIX: &'static str = ".tmp";
+
+/// A `StateStore` that keeps each key in its own file inside a directory.
+///
+/// Values are written to a temporary file, flushed to disk and then renamed
+/// over the previous file, so a crash leaves either the old or the new
+/// value in place.  Key names are percent-encoded into file names.
+#[derive(Clone, Debug)]
+pub struct FileStateStore {
+    directory: PathBuf,
+}
+
+impl FileStateStore {
+
+    /// Opens a store in `directory`, creating it if necessary.
+    pub fn new<P: AsRef<Path>>(directory: P) -> io::Result<FileStateStore> {
+        try!(fs::create_dir_all(directory.as_ref()));
+        Ok(FileStateStore { directory: directory.as_ref().to_path_buf() })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(encode_key(key))
+    }
+}
+
+impl StateStore for FileStateStore {
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let mut file = match File::open(self.path(key)) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(None)
+            },
+            Err(e) => return Err(e),
+        };
+
+        let mut value = vec![];
+        try!(file.read_to_end(&mut value));
+        Ok(Some(value))
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        let path = self.path(key);
+        let temp = self.directory.join(
+            format!("{}{}", encode_key(key), TEMP_SUFFIX));
+
+        {
+            let mut file = try!(File::create(&temp));
+            try!(file.write_all(value));
+            try!(file.sync_all());
+        }
+
+        try!(fs::rename(&temp, &path));
+
+        // Persist the rename itself.  Directories cannot be synced on every
+        // platform, so failures here are not fatal.
+        if let Ok(directory) = File::open(&self.directory) {
+            let _ = directory.sync_all();
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        let mut keys = vec![];
+        for entry in try!(fs::read_dir(&self.directory)) {
+            let entry = try!(entry);
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            if name.ends_with(TEMP_SUFFIX) {
+                continue;
+            }
+            if let Some(key) = decode_key(name) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+// Percent-encodes every byte outside of `[A-Za-z0-9_-]`, so that any key
+// (including ones containing '/' or '.') maps to a single safe file name.
+fn encode_key(key: &str) -> String {
+    let mut encoded = String::new();
+    for byte in key.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-' => {
+                encoded.push(byte as char)
+            },
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode_key(name: &str) -> Option<String> {
+    let bytes = name.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if i + 3 > bytes.len() {
+                return None;
+            }
+            let hex = match ::std::str::from_utf8(&bytes[i + 1..i + 3]) {
+                Ok(hex) => hex,
+                Err(_) => return None,
+            };
+            match u8::from_str_radix(hex, 16) {
+                Ok(byte) => decoded.push(byte),
+                Err(_) => return None,
+            }
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,38 @@
+use state::StateStore;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+
+/// A `StateStore` that keeps its contents in memory; state does not
+/// survive the process, which makes it suitable for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    values: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStateStore {
+    pub fn new() -> MemoryStateStore {
+        MemoryStateStore { values: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl StateStore for MemoryStateStore {
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.values.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> io::Result<()> {
+        self.values.lock().unwrap().insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        Ok(self.values.lock().unwrap().keys().cloned().collect())
+    }
+}
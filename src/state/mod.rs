@@ -0,0 +1,259 @@
+//! # Persistent framework state.
+//!
+//! A framework that wants to fail over without losing its tasks has to
+//! remember the `FrameworkID` assigned by the master, and re-register with
+//! it in `FrameworkInfo`.  The `StateStore` trait abstracts a small
+//! key-value store used for that purpose, along with the ids of launched
+//! tasks and any other state the framework wishes to keep.
+//!
+//! Two implementations are provided: `FileStateStore`, which keeps one file
+//! per key and replaces values atomically, and `MemoryStateStore`, which is
+//! useful in tests.
+
+mod file;
+mod memory;
+mod tests;
+
+pub use self::file::FileStateStore;
+pub use self::memory::MemoryStateStore;
+
+use proto::mesos as pb;
+use protobuf::{self, Message};
+use scheduler::{DecodeError, Scheduler, SchedulerDriver};
+use std::io;
+
+const FRAMEWORK_ID_KEY: &'static str = "framework_id";
+const TASK_KEY_PREFIX: &'static str = "task/";
+
+/// A key-value store for framework state.  Keys are arbitrary strings;
+/// implementations must make each `set` atomic.
+pub trait StateStore {
+
+    /// Returns the value stored under `key`, if any.
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn set(&self, key: &str, value: &[u8]) -> io::Result<()>;
+
+    /// Removes `key`; removing a missing key is not an error.
+    fn remove(&self, key: &str) -> io::Result<()>;
+
+    /// Returns all keys, in no particular order.
+    fn keys(&self) -> io::Result<Vec<String>>;
+
+    /// Returns the persisted framework id, if any.
+    fn framework_id(&self) -> io::Result<Option<pb::FrameworkID>> {
+        decode(FRAMEWORK_ID_KEY, try!(self.get(FRAMEWORK_ID_KEY)))
+    }
+
+    fn set_framework_id(
+        &self,
+        framework_id: &pb::FrameworkID
+    ) -> io::Result<()> {
+        self.set(FRAMEWORK_ID_KEY,
+                 &try!(encode(FRAMEWORK_ID_KEY, framework_id)))
+    }
+
+    /// Forgets the framework id, e.g. after the framework has been
+    /// removed from the cluster.
+    fn clear_framework_id(&self) -> io::Result<()> {
+        self.remove(FRAMEWORK_ID_KEY)
+    }
+
+    /// Records that `task_id` has been launched.
+    fn add_task_id(&self, task_id: &pb::TaskID) -> io::Result<()> {
+        let key = task_key(task_id);
+        self.set(&key, &try!(encode(&key, task_id)))
+    }
+
+    /// Forgets a task, typically once it has reached a terminal state.
+    fn remove_task_id(&self, task_id: &pb::TaskID) -> io::Result<()> {
+        self.remove(&task_key(task_id))
+    }
+
+    /// Returns the ids of all launched tasks that have not been removed.
+    fn task_ids(&self) -> io::Result<Vec<pb::TaskID>> {
+        let mut task_ids = vec![];
+        for key in try!(self.keys()) {
+            if key.starts_with(TASK_KEY_PREFIX) {
+                if let Some(task_id) = try!(decode(&key,
+                                                   try!(self.get(&key)))) {
+                    task_ids.push(task_id);
+                }
+            }
+        }
+        Ok(task_ids)
+    }
+}
+
+// Decodes a protobuf message read from the store.
+fn decode<M: Message>(
+    key: &str,
+    bytes: Option<Vec<u8>>
+) -> io::Result<Option<M>> {
+    match bytes {
+        None => Ok(None),
+        Some(bytes) => {
+            protobuf::parse_from_bytes::<M>(&bytes)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData,
+                                            format!("{}: {}", key, e)))
+        },
+    }
+}
+
+// Encodes a protobuf message to be written to the store.
+fn encode(key: &str, message: &Message) -> io::Result<Vec<u8>> {
+    message.write_to_bytes().map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", key, e))
+    })
+}
+
+fn task_key(task_id: &pb::TaskID) -> String {
+    format!("{}{}", TASK_KEY_PREFIX, task_id.get_value())
+}
+
+/// Sets the persisted framework id, if any, in `framework_info` so that
+/// the framework re-registers as itself.  Returns whether an id was found.
+pub fn inject_framework_id(
+    store: &StateStore,
+    framework_info: &mut pb::FrameworkInfo
+) -> io::Result<bool> {
+    match try!(store.framework_id()) {
+        Some(framework_id) => {
+            framework_info.set_id(framework_id);
+            Ok(true)
+        },
+        None => Ok(false),
+    }
+}
+
+/// A `Scheduler` that persists the framework id assigned in `registered`
+/// and forgets tasks once they reach a terminal state, before forwarding
+/// every callback to the scheduler it wraps.
+///
+/// Tasks are launched through the driver, which this wrapper never sees:
+/// record them with `StateStore::add_task_id` when launching them.
+///
+/// Storage failures are logged as errors rather than interrupting the
+/// wrapped scheduler.
+pub struct PersistentScheduler<'a> {
+    scheduler: &'a Scheduler,
+    store: &'a StateStore,
+}
+
+impl<'a> PersistentScheduler<'a> {
+    pub fn new(
+        scheduler: &'a Scheduler,
+        store: &'a StateStore
+    ) -> PersistentScheduler<'a> {
+        PersistentScheduler {
+            scheduler: scheduler,
+            store: store,
+        }
+    }
+}
+
+impl<'a> Scheduler for PersistentScheduler<'a> {
+
+    fn registered(
+        &self,
+        driver: &SchedulerDriver,
+        framework_id: &pb::FrameworkID,
+        master_info: &pb::MasterInfo) {
+
+        if let Err(e) = self.store.set_framework_id(framework_id) {
+            error!("Failed to persist framework id: {}", e);
+        }
+        self.scheduler.registered(driver, framework_id, master_info);
+    }
+
+    fn reregistered(
+        &self,
+        driver: &SchedulerDriver,
+        master_info: &pb::MasterInfo) {
+
+        self.scheduler.reregistered(driver, master_info);
+    }
+
+    fn resource_offers(
+        &self,
+        driver: &SchedulerDriver,
+        offers: Vec<pb::Offer>) {
+
+        self.scheduler.resource_offers(driver, offers);
+    }
+
+    fn status_update(
+        &self,
+        driver: &SchedulerDriver,
+        task_status: &pb::TaskStatus) {
+
+        if ::task_registry::is_terminal(task_status.get_state()) {
+            if let Err(e) = self.store.remove_task_id(
+                task_status.get_task_id()) {
+                error!("Failed to remove persisted task id: {}", e);
+            }
+        }
+        self.scheduler.status_update(driver, task_status);
+    }
+
+    fn disconnected(
+        &self,
+        driver: &SchedulerDriver) {
+
+        self.scheduler.disconnected(driver);
+    }
+
+    fn offer_rescinded(
+        &self,
+        driver: &SchedulerDriver,
+        offer_id: &pb::OfferID) {
+
+        self.scheduler.offer_rescinded(driver, offer_id);
+    }
+
+    fn framework_message(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &String) {
+
+        self.scheduler.framework_message(driver, executor_id, slave_id, data);
+    }
+
+    fn slave_lost(
+        &self,
+        driver: &SchedulerDriver,
+        slave_id: &pb::SlaveID) {
+
+        self.scheduler.slave_lost(driver, slave_id);
+    }
+
+    fn executor_lost(
+        &self,
+        driver: &SchedulerDriver,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        status: i32) {
+
+        self.scheduler.executor_lost(driver, executor_id, slave_id, status);
+    }
+
+    fn error(
+        &self,
+        driver: &SchedulerDriver,
+        message: &String) {
+
+        self.scheduler.error(driver, message);
+    }
+
+    fn decode_error(
+        &self,
+        driver: &SchedulerDriver,
+        error: &DecodeError) {
+
+        self.scheduler.decode_error(driver, error);
+    }
+}
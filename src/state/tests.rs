@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+
+    use ::builder::SchedulerBuilder;
+    use ::proto::mesos as pb;
+    use ::scheduler::Scheduler;
+    use ::state::*;
+    use ::testing::MockSchedulerDriver;
+    use std::cell::Cell;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::process;
+
+    fn task_id(value: &str) -> pb::TaskID {
+        let mut task_id = pb::TaskID::new();
+        task_id.set_value(value.to_string());
+        task_id
+    }
+
+    fn exercise(store: &StateStore) {
+        assert_eq!(store.framework_id().unwrap(), None);
+
+        let mut framework_id = pb::FrameworkID::new();
+        framework_id.set_value("20151012-0000-0001".to_string());
+        store.set_framework_id(&framework_id).unwrap();
+        assert_eq!(store.framework_id().unwrap(), Some(framework_id.clone()));
+
+        store.add_task_id(&task_id("web.1")).unwrap();
+        store.add_task_id(&task_id("web/2")).unwrap();
+        store.add_task_id(&task_id("web.3")).unwrap();
+        store.remove_task_id(&task_id("web.3")).unwrap();
+        store.remove_task_id(&task_id("missing")).unwrap();
+
+        let mut ids: Vec<String> = store.task_ids().unwrap()
+            .iter()
+            .map(|id| id.get_value().to_string())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["web.1", "web/2"]);
+
+        store.set("app/config", b"replicas=3").unwrap();
+        store.set("app/config", b"replicas=4").unwrap();
+        assert_eq!(store.get("app/config").unwrap(),
+                   Some(b"replicas=4".to_vec()));
+
+        let mut framework_info = pb::FrameworkInfo::new();
+        assert!(inject_framework_id(store, &mut framework_info).unwrap());
+        assert_eq!(framework_info.get_id(), &framework_id);
+
+        store.clear_framework_id().unwrap();
+        let mut framework_info = pb::FrameworkInfo::new();
+        assert!(!inject_framework_id(store, &mut framework_info).unwrap());
+        assert!(!framework_info.has_id());
+    }
+
+    #[test]
+    fn memory_state_store() {
+        exercise(&MemoryStateStore::new());
+    }
+
+    #[test]
+    fn file_state_store() {
+        let directory = env::temp_dir().join(
+            format!("mesos-rust-state-{}", process::id()));
+
+        {
+            let store = FileStateStore::new(&directory).unwrap();
+            exercise(&store);
+            store.set("survives", b"restart").unwrap();
+        }
+
+        // A new store over the same directory sees the previous state, and
+        // no temporary files are left behind.
+        let store = FileStateStore::new(&directory).unwrap();
+        assert_eq!(store.get("survives").unwrap(), Some(b"restart".to_vec()));
+        for entry in fs::read_dir(&directory).unwrap() {
+            let name = entry.unwrap().file_name();
+            assert!(!name.to_str().unwrap().ends_with(".tmp"));
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn framework_id(value: &str) -> pb::FrameworkID {
+        let mut framework_id = pb::FrameworkID::new();
+        framework_id.set_value(value.to_string());
+        framework_id
+    }
+
+    fn update(task: &str, state: pb::TaskState) -> pb::TaskStatus {
+        let mut status = pb::TaskStatus::new();
+        status.set_task_id(task_id(task));
+        status.set_state(state);
+        status
+    }
+
+    #[test]
+    fn persistent_scheduler_tracks_framework_and_tasks() {
+        let registrations = Cell::new(0);
+        let updates = Cell::new(0);
+        let inner = SchedulerBuilder::new()
+            .on_registered(|_, _, _| {
+                registrations.set(registrations.get() + 1)
+            })
+            .on_update(|_, _| updates.set(updates.get() + 1))
+            .build();
+        let store = MemoryStateStore::new();
+        let scheduler = PersistentScheduler::new(&inner, &store);
+        let driver = MockSchedulerDriver::new();
+
+        scheduler.registered(&driver, &framework_id("fw-1"),
+                             &pb::MasterInfo::new());
+        assert_eq!(store.framework_id().unwrap(),
+                   Some(framework_id("fw-1")));
+
+        store.add_task_id(&task_id("running")).unwrap();
+        store.add_task_id(&task_id("finished")).unwrap();
+        scheduler.status_update(
+            &driver, &update("running", pb::TaskState::TASK_RUNNING));
+        scheduler.status_update(
+            &driver, &update("finished", pb::TaskState::TASK_FINISHED));
+        assert_eq!(store.task_ids().unwrap(), vec![task_id("running")]);
+
+        assert_eq!(registrations.get(), 1);
+        assert_eq!(updates.get(), 2);
+    }
+
+    // A store on which every operation fails.
+    struct BrokenStore;
+
+    impl StateStore for BrokenStore {
+        fn get(&self, _: &str) -> io::Result<Option<Vec<u8>>> {
+            Err(io::Error::new(io::ErrorKind::Other, "broken"))
+        }
+
+        fn set(&self, _: &str, _: &[u8]) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "broken"))
+        }
+
+        fn remove(&self, _: &str) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "broken"))
+        }
+
+        fn keys(&self) -> io::Result<Vec<String>> {
+            Err(io::Error::new(io::ErrorKind::Other, "broken"))
+        }
+    }
+
+    #[test]
+    fn storage_failures_do_not_stop_callbacks() {
+        let registrations = Cell::new(0);
+        let updates = Cell::new(0);
+        let inner = SchedulerBuilder::new()
+            .on_registered(|_, _, _| {
+                registrations.set(registrations.get() + 1)
+            })
+            .on_update(|_, _| updates.set(updates.get() + 1))
+            .build();
+        let store = BrokenStore;
+        let scheduler = PersistentScheduler::new(&inner, &store);
+        let driver = MockSchedulerDriver::new();
+
+        scheduler.registered(&driver, &framework_id("fw-1"),
+                             &pb::MasterInfo::new());
+        scheduler.status_update(
+            &driver, &update("lost", pb::TaskState::TASK_LOST));
+
+        assert_eq!(registrations.get(), 1);
+        assert_eq!(updates.get(), 1);
+    }
+}
@@ -0,0 +1,33 @@
+//! # Resource usage statistics.
+//!
+//! Executors report the resource usage of their tasks to Mesos and to
+//! their schedulers as `pb::ResourceStatistics`.  This module collects
+//! those statistics on Linux:
+//!
+//! * `Sampler` fills CPU, memory, process, thread and sandbox disk usage
+//!   for a process tree from `/proc`, preferring the accounting of the
+//!   tree's cgroup (v1 or v2) when there is one, along with the SNMP
+//!   counters of its network namespace.
+//! * `parse_snmp` reads the counters of `/proc/net/snmp` into
+//!   `pb::SNMPStatistics`.
+//! * `qdisc_statistics` and `parse_qdisc` read the queueing disciplines
+//!   reported by `tc -s qdisc show` into `pb::TrafficControlStatistics`.
+//! * `perf_stat` and `parse_perf_stat` count hardware and software events
+//!   of a process or of cgroups with `perf stat` into
+//!   `pb::PerfStatistics`.
+//!
+//! All readers take their file system roots from their configuration, so
+//! that they can be pointed at captured fixtures in tests.  Like the rest
+//! of the protobuf messages, the statistics can be shipped to a scheduler
+//! with `write_to_bytes` in a framework message.
+
+mod perf;
+mod sampler;
+mod snmp;
+mod tests;
+mod traffic_control;
+
+pub use self::perf::{parse_perf_stat, perf_stat, PerfTarget};
+pub use self::sampler::{Sampler, SamplerConfig};
+pub use self::snmp::parse_snmp;
+pub use self::traffic_control::{parse_qdisc, qdisc_statistics};
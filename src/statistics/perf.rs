@@ -0,0 +1,210 @@
+use proto::mesos as pb;
+use std::collections::HashMap;
+use std::io;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What `perf_stat` counts events for.
+#[derive(Clone, Copy, Debug)]
+pub enum PerfTarget<'a> {
+    /// A single process, and the threads it creates while being counted.
+    Pid(u32),
+
+    /// Every process in each of the given `perf_event` cgroups, relative
+    /// to the root of that hierarchy.
+    Cgroups(&'a [&'a str]),
+}
+
+/// Runs `perf stat -x,` to count `events` (e.g. `cycles`, `task-clock`)
+/// for `target` over `duration`, and returns the statistics keyed by
+/// cgroup, or under `""` for a process.  The statistics record when
+/// counting started and how long it lasted.
+pub fn perf_stat(
+    target: PerfTarget,
+    events: &[&str],
+    duration: Duration
+) -> io::Result<HashMap<String, pb::PerfStatistics>> {
+    let events = events.join(",");
+
+    let mut command = Command::new("perf");
+    command.args(&["stat", "-x,"]);
+
+    match target {
+        PerfTarget::Pid(pid) => {
+            command.arg("-p").arg(pid.to_string()).arg("-e").arg(&events);
+        },
+        PerfTarget::Cgroups(cgroups) => {
+            // `-G` applies to the events listed before it, one by one.
+            command.arg("-a");
+            for cgroup in cgroups {
+                let repeated: Vec<&str> = events.split(',')
+                    .map(|_| *cgroup)
+                    .collect();
+                command.arg("-e").arg(&events)
+                    .arg("-G").arg(repeated.join(","));
+            }
+        },
+    }
+
+    let seconds = duration.as_secs() as f64
+        + duration.subsec_nanos() as f64 * 1e-9;
+    command.arg("--").arg("sleep").arg(seconds.to_string());
+
+    let timestamp = try!(SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as f64 + now.subsec_nanos() as f64 * 1e-9)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e)));
+
+    let output = try!(command.output());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("perf failed with {}: {}", output.status, stderr.trim())));
+    }
+
+    // `perf stat` reports on stderr.
+    let mut statistics = try!(parse_perf_stat(&stderr));
+    for statistics in statistics.values_mut() {
+        statistics.set_timestamp(timestamp);
+        statistics.set_duration(seconds);
+    }
+    Ok(statistics)
+}
+
+/// Parses the CSV output of `perf stat -x,`, whose lines start with
+///
+/// ```text
+/// <value>,<unit>,<event>,<cgroup>,<running time>,<percentage>,...
+/// ```
+///
+/// where the cgroup column is only present when counting per cgroup.  The
+/// statistics are keyed by cgroup, or by `""` without one; `timestamp` and
+/// `duration` are left for the caller to set.
+///
+/// Event names are mapped to the fields of `pb::PerfStatistics` by
+/// lowercasing them and replacing dashes with underscores, after removing
+/// modifiers (`cycles:u`) and PMU names (`cpu_core/cycles/`).  Events that
+/// were not counted and events without a field are skipped.
+pub fn parse_perf_stat(
+    output: &str
+) -> io::Result<HashMap<String, pb::PerfStatistics>> {
+    let mut statistics = HashMap::new();
+
+    for line in output.lines() {
+        // Comments and blank lines, such as perf's own notices.
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed perf stat line: {:?}", line)));
+        }
+
+        // E.g. "<not counted>" or "<not supported>".
+        let value = match fields[0].parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        // The running time that follows the event without a cgroup is
+        // always numeric.
+        let cgroup = match fields.get(3) {
+            Some(cgroup) if !cgroup.is_empty()
+                            && cgroup.parse::<f64>().is_err() => *cgroup,
+            _ => "",
+        };
+
+        let statistics = statistics.entry(cgroup.to_string())
+            .or_insert(pb::PerfStatistics::new());
+        set(statistics, &field_name(fields[2]), value);
+    }
+
+    Ok(statistics)
+}
+
+fn field_name(event: &str) -> String {
+    // "cpu_core/cycles/" -> "cycles"
+    let event = if event.ends_with('/') {
+        event.trim_right_matches('/').rsplit('/').next().unwrap_or(event)
+    } else {
+        event
+    };
+
+    // "cycles:u" -> "cycles"
+    let event = event.split(':').next().unwrap_or(event);
+
+    event.to_lowercase().replace('-', "_")
+}
+
+fn set(statistics: &mut pb::PerfStatistics, field: &str, value: f64) {
+    match field {
+        "cycles" => statistics.set_cycles(value as u64),
+        "stalled_cycles_frontend" =>
+            statistics.set_stalled_cycles_frontend(value as u64),
+        "stalled_cycles_backend" =>
+            statistics.set_stalled_cycles_backend(value as u64),
+        "instructions" => statistics.set_instructions(value as u64),
+        "cache_references" => statistics.set_cache_references(value as u64),
+        "cache_misses" => statistics.set_cache_misses(value as u64),
+        "branches" => statistics.set_branches(value as u64),
+        "branch_misses" => statistics.set_branch_misses(value as u64),
+        "bus_cycles" => statistics.set_bus_cycles(value as u64),
+        "ref_cycles" => statistics.set_ref_cycles(value as u64),
+        "cpu_clock" => statistics.set_cpu_clock(value),
+        "task_clock" => statistics.set_task_clock(value),
+        "page_faults" => statistics.set_page_faults(value as u64),
+        "minor_faults" => statistics.set_minor_faults(value as u64),
+        "major_faults" => statistics.set_major_faults(value as u64),
+        "context_switches" => statistics.set_context_switches(value as u64),
+        "cpu_migrations" => statistics.set_cpu_migrations(value as u64),
+        "alignment_faults" => statistics.set_alignment_faults(value as u64),
+        "emulation_faults" => statistics.set_emulation_faults(value as u64),
+        "l1_dcache_loads" => statistics.set_l1_dcache_loads(value as u64),
+        "l1_dcache_load_misses" =>
+            statistics.set_l1_dcache_load_misses(value as u64),
+        "l1_dcache_stores" => statistics.set_l1_dcache_stores(value as u64),
+        "l1_dcache_store_misses" =>
+            statistics.set_l1_dcache_store_misses(value as u64),
+        "l1_dcache_prefetches" =>
+            statistics.set_l1_dcache_prefetches(value as u64),
+        "l1_dcache_prefetch_misses" =>
+            statistics.set_l1_dcache_prefetch_misses(value as u64),
+        "l1_icache_loads" => statistics.set_l1_icache_loads(value as u64),
+        "l1_icache_load_misses" =>
+            statistics.set_l1_icache_load_misses(value as u64),
+        "l1_icache_prefetches" =>
+            statistics.set_l1_icache_prefetches(value as u64),
+        "l1_icache_prefetch_misses" =>
+            statistics.set_l1_icache_prefetch_misses(value as u64),
+        "llc_loads" => statistics.set_llc_loads(value as u64),
+        "llc_load_misses" => statistics.set_llc_load_misses(value as u64),
+        "llc_stores" => statistics.set_llc_stores(value as u64),
+        "llc_store_misses" => statistics.set_llc_store_misses(value as u64),
+        "llc_prefetches" => statistics.set_llc_prefetches(value as u64),
+        "llc_prefetch_misses" =>
+            statistics.set_llc_prefetch_misses(value as u64),
+        "dtlb_loads" => statistics.set_dtlb_loads(value as u64),
+        "dtlb_load_misses" => statistics.set_dtlb_load_misses(value as u64),
+        "dtlb_stores" => statistics.set_dtlb_stores(value as u64),
+        "dtlb_store_misses" => statistics.set_dtlb_store_misses(value as u64),
+        "dtlb_prefetches" => statistics.set_dtlb_prefetches(value as u64),
+        "dtlb_prefetch_misses" =>
+            statistics.set_dtlb_prefetch_misses(value as u64),
+        "itlb_loads" => statistics.set_itlb_loads(value as u64),
+        "itlb_load_misses" => statistics.set_itlb_load_misses(value as u64),
+        "branch_loads" => statistics.set_branch_loads(value as u64),
+        "branch_load_misses" =>
+            statistics.set_branch_load_misses(value as u64),
+        "node_loads" => statistics.set_node_loads(value as u64),
+        "node_load_misses" => statistics.set_node_load_misses(value as u64),
+        "node_stores" => statistics.set_node_stores(value as u64),
+        "node_store_misses" => statistics.set_node_store_misses(value as u64),
+        "node_prefetches" => statistics.set_node_prefetches(value as u64),
+        "node_prefetch_misses" =>
+            statistics.set_node_prefetch_misses(value as u64),
+        _ => debug!("Ignoring unknown perf event '{}'", field),
+    }
+}
@@ -0,0 +1,433 @@
+use libc;
+use proto::mesos as pb;
+use statistics::snmp;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where and how `Sampler` reads its statistics.
+#[derive(Clone, Debug)]
+pub struct SamplerConfig {
+    /// Mount point of procfs.
+    pub proc_root: PathBuf,
+
+    /// Mount point of the cgroup file systems: the unified hierarchy
+    /// itself for cgroup v2, or the directory holding one mount per
+    /// controller (e.g. `memory`, `cpu,cpuacct`) for cgroup v1.
+    pub cgroup_root: PathBuf,
+
+    /// Units of the CPU times in `/proc/<pid>/stat` and `cpuacct.stat`.
+    pub clock_ticks: u64,
+
+    /// Unit of the resident set size in `/proc/<pid>/stat`.
+    pub page_size: u64,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> SamplerConfig {
+        let (clock_ticks, page_size) = unsafe {
+            (libc::sysconf(libc::_SC_CLK_TCK),
+             libc::sysconf(libc::_SC_PAGESIZE))
+        };
+
+        SamplerConfig {
+            proc_root: PathBuf::from("/proc"),
+            cgroup_root: PathBuf::from("/sys/fs/cgroup"),
+            clock_ticks: if clock_ticks > 0 { clock_ticks as u64 }
+                         else { 100 },
+            page_size: if page_size > 0 { page_size as u64 } else { 4096 },
+        }
+    }
+}
+
+/// Samples the resource usage of a process and its descendants.
+pub struct Sampler {
+    config: SamplerConfig,
+}
+
+/// The fields of `/proc/<pid>/stat` that are of interest.
+struct Stat {
+    ppid: u32,
+    utime: u64,
+    stime: u64,
+    threads: u32,
+    rss_pages: u64,
+}
+
+impl Sampler {
+    pub fn new(config: SamplerConfig) -> Sampler {
+        Sampler { config: config }
+    }
+
+    /// Samples the process tree rooted at `pid`, and the disk usage of
+    /// `sandbox` if given.  CPU and memory figures come from the cgroup of
+    /// `pid` if it has one, and are otherwise summed over the tree.  The
+    /// SNMP counters are those of the network namespace of `pid`.
+    pub fn sample(
+        &self,
+        pid: u32,
+        sandbox: Option<&Path>
+    ) -> io::Result<pb::ResourceStatistics> {
+        let mut statistics = pb::ResourceStatistics::new();
+
+        if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            statistics.set_timestamp(
+                now.as_secs() as f64 + now.subsec_nanos() as f64 * 1e-9);
+        }
+
+        try!(self.sample_processes(pid, &mut statistics));
+        try!(self.sample_cgroups(pid, &mut statistics));
+
+        let snmp = self.config.proc_root
+            .join(pid.to_string())
+            .join("net")
+            .join("snmp");
+        if let Ok(contents) = read(&snmp) {
+            statistics.set_net_snmp_statistics(
+                try!(snmp::parse_snmp(&contents)));
+        }
+
+        if let Some(sandbox) = sandbox {
+            statistics.set_disk_used_bytes(try!(disk_usage(sandbox)));
+        }
+
+        Ok(statistics)
+    }
+
+    fn sample_processes(
+        &self,
+        pid: u32,
+        statistics: &mut pb::ResourceStatistics
+    ) -> io::Result<()> {
+        let stats = try!(self.read_stats());
+        if !stats.contains_key(&pid) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such process: {}", pid)));
+        }
+
+        let mut tree = vec![pid];
+        let mut i = 0;
+        while i < tree.len() {
+            let parent = tree[i];
+            let mut children: Vec<u32> = stats.iter()
+                .filter(|&(&child, stat)| {
+                    stat.ppid == parent && child != parent
+                })
+                .map(|(&child, _)| child)
+                .collect();
+            children.sort();
+            tree.extend(children);
+            i += 1;
+        }
+
+        let (mut utime, mut stime, mut threads, mut rss_pages) = (0, 0, 0, 0);
+        for pid in &tree {
+            let stat = &stats[pid];
+            utime += stat.utime;
+            stime += stat.stime;
+            threads += stat.threads;
+            rss_pages += stat.rss_pages;
+        }
+
+        let ticks = self.config.clock_ticks as f64;
+        statistics.set_processes(tree.len() as u32);
+        statistics.set_threads(threads);
+        statistics.set_cpus_user_time_secs(utime as f64 / ticks);
+        statistics.set_cpus_system_time_secs(stime as f64 / ticks);
+        statistics.set_mem_rss_bytes(rss_pages * self.config.page_size);
+
+        Ok(())
+    }
+
+    /// Reads the stat file of every process; processes which exit while
+    /// this is going on are skipped.
+    fn read_stats(&self) -> io::Result<HashMap<u32, Stat>> {
+        let mut stats = HashMap::new();
+
+        for entry in try!(fs::read_dir(&self.config.proc_root)) {
+            let entry = try!(entry);
+            let pid = match entry.file_name().to_str()
+                .and_then(|name| name.parse::<u32>().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            let contents = match read(&entry.path().join("stat")) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+
+            if let Some(stat) = parse_stat(&contents) {
+                stats.insert(pid, stat);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    fn sample_cgroups(
+        &self,
+        pid: u32,
+        statistics: &mut pb::ResourceStatistics
+    ) -> io::Result<()> {
+        let path = self.config.proc_root
+            .join(pid.to_string())
+            .join("cgroup");
+
+        // Not every kernel has cgroups.
+        let contents = match read(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Ok(()),
+        };
+
+        for line in contents.lines() {
+            // E.g. "0::/system.slice/task.scope" for cgroup v2, and
+            // "4:memory:/mesos/task" for cgroup v1.
+            let fields: Vec<&str> = line.splitn(3, ':').collect();
+            if fields.len() != 3 {
+                continue;
+            }
+            let (controllers, cgroup) = (fields[1], fields[2]);
+            let relative = cgroup.trim_left_matches('/');
+
+            if fields[0] == "0" && controllers.is_empty() {
+                let dir = self.config.cgroup_root.join(relative);
+                self.sample_unified(&dir, statistics);
+            } else {
+                let dir = self.config.cgroup_root
+                    .join(controllers)
+                    .join(relative);
+                for controller in controllers.split(',') {
+                    match controller {
+                        "cpu" => sample_cpu_v1(&dir, statistics),
+                        "cpuacct" => self.sample_cpuacct_v1(
+                            &dir, statistics),
+                        "memory" => sample_memory_v1(&dir, statistics),
+                        _ => {},
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sample_cpuacct_v1(
+        &self,
+        dir: &Path,
+        statistics: &mut pb::ResourceStatistics) {
+
+        let stat = flat_keyed(&dir.join("cpuacct.stat"));
+        let ticks = self.config.clock_ticks as f64;
+
+        if let Some(&user) = stat.get("user") {
+            statistics.set_cpus_user_time_secs(user as f64 / ticks);
+        }
+        if let Some(&system) = stat.get("system") {
+            statistics.set_cpus_system_time_secs(system as f64 / ticks);
+        }
+    }
+
+    fn sample_unified(
+        &self,
+        dir: &Path,
+        statistics: &mut pb::ResourceStatistics) {
+
+        let cpu = flat_keyed(&dir.join("cpu.stat"));
+        if let Some(&user) = cpu.get("user_usec") {
+            statistics.set_cpus_user_time_secs(user as f64 / 1e6);
+        }
+        if let Some(&system) = cpu.get("system_usec") {
+            statistics.set_cpus_system_time_secs(system as f64 / 1e6);
+        }
+        if let Some(&periods) = cpu.get("nr_periods") {
+            statistics.set_cpus_nr_periods(periods as u32);
+        }
+        if let Some(&throttled) = cpu.get("nr_throttled") {
+            statistics.set_cpus_nr_throttled(throttled as u32);
+        }
+        if let Some(&throttled) = cpu.get("throttled_usec") {
+            statistics.set_cpus_throttled_time_secs(throttled as f64 / 1e6);
+        }
+
+        // "max 100000" when unlimited, else "<quota> <period>".
+        if let Ok(max) = read(&dir.join("cpu.max")) {
+            let fields: Vec<&str> = max.split_whitespace().collect();
+            if fields.len() == 2 {
+                if let (Ok(quota), Ok(period)) =
+                    (fields[0].parse::<f64>(), fields[1].parse::<f64>()) {
+                    if period > 0.0 {
+                        statistics.set_cpus_limit(quota / period);
+                    }
+                }
+            }
+        }
+
+        if let Some(current) = single(&dir.join("memory.current")) {
+            statistics.set_mem_total_bytes(current);
+        }
+        if let Some(max) = single(&dir.join("memory.max")) {
+            statistics.set_mem_limit_bytes(max);
+        }
+        if let Some(high) = single(&dir.join("memory.high")) {
+            statistics.set_mem_soft_limit_bytes(high);
+        }
+        if let Some(swap) = single(&dir.join("memory.swap.current")) {
+            statistics.set_mem_swap_bytes(swap);
+        }
+
+        let memory = flat_keyed(&dir.join("memory.stat"));
+        if let Some(&anon) = memory.get("anon") {
+            statistics.set_mem_anon_bytes(anon);
+            statistics.set_mem_rss_bytes(anon);
+        }
+        if let Some(&file) = memory.get("file") {
+            statistics.set_mem_file_bytes(file);
+            statistics.set_mem_cache_bytes(file);
+        }
+        if let Some(&mapped) = memory.get("file_mapped") {
+            statistics.set_mem_mapped_file_bytes(mapped);
+        }
+        if let Some(&unevictable) = memory.get("unevictable") {
+            statistics.set_mem_unevictable_bytes(unevictable);
+        }
+    }
+}
+
+fn sample_cpu_v1(dir: &Path, statistics: &mut pb::ResourceStatistics) {
+    let stat = flat_keyed(&dir.join("cpu.stat"));
+    if let Some(&periods) = stat.get("nr_periods") {
+        statistics.set_cpus_nr_periods(periods as u32);
+    }
+    if let Some(&throttled) = stat.get("nr_throttled") {
+        statistics.set_cpus_nr_throttled(throttled as u32);
+    }
+    if let Some(&throttled) = stat.get("throttled_time") {
+        statistics.set_cpus_throttled_time_secs(throttled as f64 / 1e9);
+    }
+
+    // A quota of -1 means unlimited and does not parse.
+    let quota = single(&dir.join("cpu.cfs_quota_us"));
+    let period = single(&dir.join("cpu.cfs_period_us"));
+    if let (Some(quota), Some(period)) = (quota, period) {
+        if period > 0 {
+            statistics.set_cpus_limit(quota as f64 / period as f64);
+        }
+    }
+}
+
+fn sample_memory_v1(dir: &Path, statistics: &mut pb::ResourceStatistics) {
+    if let Some(usage) = single(&dir.join("memory.usage_in_bytes")) {
+        statistics.set_mem_total_bytes(usage);
+    }
+    if let Some(usage) = single(&dir.join("memory.memsw.usage_in_bytes")) {
+        statistics.set_mem_total_memsw_bytes(usage);
+    }
+    if let Some(limit) = single(&dir.join("memory.limit_in_bytes")) {
+        statistics.set_mem_limit_bytes(limit);
+    }
+    if let Some(limit) = single(&dir.join("memory.soft_limit_in_bytes")) {
+        statistics.set_mem_soft_limit_bytes(limit);
+    }
+
+    // The "total_" entries include descendant cgroups.
+    let stat = flat_keyed(&dir.join("memory.stat"));
+    let get = |key: &str| stat.get(&format!("total_{}", key)).cloned();
+
+    if let Some(cache) = get("cache") {
+        statistics.set_mem_cache_bytes(cache);
+    }
+    if let Some(rss) = get("rss") {
+        statistics.set_mem_rss_bytes(rss);
+    }
+    if let Some(mapped) = get("mapped_file") {
+        statistics.set_mem_mapped_file_bytes(mapped);
+    }
+    if let Some(swap) = get("swap") {
+        statistics.set_mem_swap_bytes(swap);
+    }
+    if let Some(unevictable) = get("unevictable") {
+        statistics.set_mem_unevictable_bytes(unevictable);
+    }
+    if let (Some(active), Some(inactive)) =
+        (get("active_anon"), get("inactive_anon")) {
+        statistics.set_mem_anon_bytes(active + inactive);
+    }
+    if let (Some(active), Some(inactive)) =
+        (get("active_file"), get("inactive_file")) {
+        statistics.set_mem_file_bytes(active + inactive);
+    }
+}
+
+fn parse_stat(contents: &str) -> Option<Stat> {
+    // The command name may contain spaces and parentheses, so the fields
+    // are counted from the last ')'; the first of them is the state.
+    let rest = match contents.rfind(')') {
+        Some(i) => &contents[i + 1..],
+        None => return None,
+    };
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    if fields.len() < 22 {
+        return None;
+    }
+
+    Some(Stat {
+        ppid: fields[1].parse().unwrap_or(0),
+        utime: fields[11].parse().unwrap_or(0),
+        stime: fields[12].parse().unwrap_or(0),
+        threads: fields[17].parse().unwrap_or(0),
+        rss_pages: fields[21].parse().unwrap_or(0),
+    })
+}
+
+/// Sums the space allocated to the files under `path`, without following
+/// symbolic links.
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = try!(fs::symlink_metadata(path));
+    let mut total = metadata.blocks() * 512;
+
+    if metadata.is_dir() {
+        for entry in try!(fs::read_dir(path)) {
+            // The task may remove files while they are being counted.
+            match entry.and_then(|entry| disk_usage(&entry.path())) {
+                Ok(usage) => total += usage,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+fn read(path: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    try!(try!(File::open(path)).read_to_string(&mut contents));
+    Ok(contents)
+}
+
+/// Reads a file holding a single number; "max" and missing files yield
+/// `None`.
+fn single(path: &Path) -> Option<u64> {
+    read(path).ok().and_then(|contents| contents.trim().parse().ok())
+}
+
+/// Reads a file of "key value" lines, such as `memory.stat`.
+fn flat_keyed(path: &Path) -> HashMap<String, u64> {
+    let contents = read(path).unwrap_or(String::new());
+
+    contents.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(key), Some(value)) => value.parse().ok()
+                    .map(|value| (key.to_string(), value)),
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use proto::mesos as pb;
+use std::io;
+
+/// Parses the contents of `/proc/net/snmp`, which for each protocol holds
+/// a line of counter names followed by a line of their values, e.g.
+///
+/// ```text
+/// Tcp: RtoAlgorithm RtoMin RtoMax MaxConn ActiveOpens ...
+/// Tcp: 1 200 120000 -1 1285 ...
+/// ```
+///
+/// The `Ip`, `Icmp`, `Tcp` and `Udp` sections are used; other sections and
+/// counters unknown to `pb::SNMPStatistics` are ignored.
+pub fn parse_snmp(contents: &str) -> io::Result<pb::SNMPStatistics> {
+    let mut statistics = pb::SNMPStatistics::new();
+    let mut lines = contents.lines();
+
+    while let Some(names) = lines.next() {
+        let values = try!(lines.next().ok_or(invalid(
+            format!("Missing values for {:?}", names))));
+
+        let (section, names) = try!(split_section(names));
+        let (other, values) = try!(split_section(values));
+        if section != other || names.len() != values.len() {
+            return Err(invalid(format!(
+                "Mismatched /proc/net/snmp lines for '{}' and '{}'",
+                section, other)));
+        }
+
+        for (name, value) in names.iter().zip(values.iter()) {
+            let value = try!(value.parse::<i64>().map_err(|_| invalid(
+                format!("Invalid value '{}' for {}:{}",
+                        value, section, name))));
+
+            match section {
+                "Ip" => set_ip(statistics.mut_ip_stats(), name, value),
+                "Icmp" => set_icmp(statistics.mut_icmp_stats(), name, value),
+                "Tcp" => set_tcp(statistics.mut_tcp_stats(), name, value),
+                "Udp" => set_udp(statistics.mut_udp_stats(), name, value),
+                _ => {},
+            }
+        }
+    }
+
+    Ok(statistics)
+}
+
+fn split_section(line: &str) -> io::Result<(&str, Vec<&str>)> {
+    let colon = try!(line.find(':').ok_or(invalid(
+        format!("Malformed /proc/net/snmp line {:?}", line))));
+    Ok((&line[..colon], line[colon + 1..].split_whitespace().collect()))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn set_ip(stats: &mut pb::IpStatistics, key: &str, value: i64) {
+    match key {
+        "Forwarding" => stats.set_Forwarding(value),
+        "DefaultTTL" => stats.set_DefaultTTL(value),
+        "InReceives" => stats.set_InReceives(value),
+        "InHdrErrors" => stats.set_InHdrErrors(value),
+        "InAddrErrors" => stats.set_InAddrErrors(value),
+        "ForwDatagrams" => stats.set_ForwDatagrams(value),
+        "InUnknownProtos" => stats.set_InUnknownProtos(value),
+        "InDiscards" => stats.set_InDiscards(value),
+        "InDelivers" => stats.set_InDelivers(value),
+        "OutRequests" => stats.set_OutRequests(value),
+        "OutDiscards" => stats.set_OutDiscards(value),
+        "OutNoRoutes" => stats.set_OutNoRoutes(value),
+        "ReasmTimeout" => stats.set_ReasmTimeout(value),
+        "ReasmReqds" => stats.set_ReasmReqds(value),
+        "ReasmOKs" => stats.set_ReasmOKs(value),
+        "ReasmFails" => stats.set_ReasmFails(value),
+        "FragOKs" => stats.set_FragOKs(value),
+        "FragFails" => stats.set_FragFails(value),
+        "FragCreates" => stats.set_FragCreates(value),
+        _ => {},
+    }
+}
+
+fn set_icmp(stats: &mut pb::IcmpStatistics, key: &str, value: i64) {
+    match key {
+        "InMsgs" => stats.set_InMsgs(value),
+        "InErrors" => stats.set_InErrors(value),
+        "InCsumErrors" => stats.set_InCsumErrors(value),
+        "InDestUnreachs" => stats.set_InDestUnreachs(value),
+        "InTimeExcds" => stats.set_InTimeExcds(value),
+        "InParmProbs" => stats.set_InParmProbs(value),
+        "InSrcQuenchs" => stats.set_InSrcQuenchs(value),
+        "InRedirects" => stats.set_InRedirects(value),
+        "InEchos" => stats.set_InEchos(value),
+        "InEchoReps" => stats.set_InEchoReps(value),
+        "InTimestamps" => stats.set_InTimestamps(value),
+        "InTimestampReps" => stats.set_InTimestampReps(value),
+        "InAddrMasks" => stats.set_InAddrMasks(value),
+        "InAddrMaskReps" => stats.set_InAddrMaskReps(value),
+        "OutMsgs" => stats.set_OutMsgs(value),
+        "OutErrors" => stats.set_OutErrors(value),
+        "OutDestUnreachs" => stats.set_OutDestUnreachs(value),
+        "OutTimeExcds" => stats.set_OutTimeExcds(value),
+        "OutParmProbs" => stats.set_OutParmProbs(value),
+        "OutSrcQuenchs" => stats.set_OutSrcQuenchs(value),
+        "OutRedirects" => stats.set_OutRedirects(value),
+        "OutEchos" => stats.set_OutEchos(value),
+        "OutEchoReps" => stats.set_OutEchoReps(value),
+        "OutTimestamps" => stats.set_OutTimestamps(value),
+        "OutTimestampReps" => stats.set_OutTimestampReps(value),
+        "OutAddrMasks" => stats.set_OutAddrMasks(value),
+        "OutAddrMaskReps" => stats.set_OutAddrMaskReps(value),
+        _ => {},
+    }
+}
+
+fn set_tcp(stats: &mut pb::TcpStatistics, key: &str, value: i64) {
+    match key {
+        "RtoAlgorithm" => stats.set_RtoAlgorithm(value),
+        "RtoMin" => stats.set_RtoMin(value),
+        "RtoMax" => stats.set_RtoMax(value),
+        "MaxConn" => stats.set_MaxConn(value),
+        "ActiveOpens" => stats.set_ActiveOpens(value),
+        "PassiveOpens" => stats.set_PassiveOpens(value),
+        "AttemptFails" => stats.set_AttemptFails(value),
+        "EstabResets" => stats.set_EstabResets(value),
+        "CurrEstab" => stats.set_CurrEstab(value),
+        "InSegs" => stats.set_InSegs(value),
+        "OutSegs" => stats.set_OutSegs(value),
+        "RetransSegs" => stats.set_RetransSegs(value),
+        "InErrs" => stats.set_InErrs(value),
+        "OutRsts" => stats.set_OutRsts(value),
+        "InCsumErrors" => stats.set_InCsumErrors(value),
+        _ => {},
+    }
+}
+
+fn set_udp(stats: &mut pb::UdpStatistics, key: &str, value: i64) {
+    match key {
+        "InDatagrams" => stats.set_InDatagrams(value),
+        "NoPorts" => stats.set_NoPorts(value),
+        "InErrors" => stats.set_InErrors(value),
+        "OutDatagrams" => stats.set_OutDatagrams(value),
+        "RcvbufErrors" => stats.set_RcvbufErrors(value),
+        "SndbufErrors" => stats.set_SndbufErrors(value),
+        "InCsumErrors" => stats.set_InCsumErrors(value),
+        "IgnoredMulti" => stats.set_IgnoredMulti(value),
+        _ => {},
+    }
+}
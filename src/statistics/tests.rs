@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod tests {
+
+    use ::statistics::*;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    fn fixture(path: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/fixtures/statistics")
+            .join(path)
+    }
+
+    fn read(path: PathBuf) -> String {
+        let mut contents = String::new();
+        File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    fn sampler() -> Sampler {
+        Sampler::new(SamplerConfig {
+            proc_root: fixture("proc"),
+            cgroup_root: fixture("cgroup"),
+            clock_ticks: 100,
+            page_size: 4096,
+        })
+    }
+
+    #[test]
+    fn samples_process_tree_without_cgroup() {
+        let statistics = sampler().sample(400, None).unwrap();
+
+        assert_eq!(statistics.get_processes(), 1);
+        assert_eq!(statistics.get_threads(), 3);
+        assert_eq!(statistics.get_cpus_user_time_secs(), 3.0);
+        assert_eq!(statistics.get_cpus_system_time_secs(), 1.0);
+        assert_eq!(statistics.get_mem_rss_bytes(), 25 * 4096);
+        assert!(!statistics.has_mem_total_bytes());
+        assert!(!statistics.has_disk_used_bytes());
+        assert!(statistics.get_timestamp() > 0.0);
+
+        assert!(sampler().sample(999, None).is_err());
+    }
+
+    #[test]
+    fn samples_cgroup_v2() {
+        let statistics = sampler().sample(100, None).unwrap();
+
+        // 100 -> 101 -> 102, but not the unrelated 200.
+        assert_eq!(statistics.get_processes(), 3);
+        assert_eq!(statistics.get_threads(), 7);
+
+        assert_eq!(statistics.get_cpus_user_time_secs(), 12.0);
+        assert_eq!(statistics.get_cpus_system_time_secs(), 3.5);
+        assert_eq!(statistics.get_cpus_limit(), 1.5);
+        assert_eq!(statistics.get_cpus_nr_periods(), 500);
+        assert_eq!(statistics.get_cpus_nr_throttled(), 20);
+        assert_eq!(statistics.get_cpus_throttled_time_secs(), 1.5);
+
+        assert_eq!(statistics.get_mem_total_bytes(), 268435456);
+        assert_eq!(statistics.get_mem_limit_bytes(), 536870912);
+        assert!(!statistics.has_mem_soft_limit_bytes());
+        assert_eq!(statistics.get_mem_rss_bytes(), 134217728);
+        assert_eq!(statistics.get_mem_anon_bytes(), 134217728);
+        assert_eq!(statistics.get_mem_cache_bytes(), 67108864);
+        assert_eq!(statistics.get_mem_file_bytes(), 67108864);
+        assert_eq!(statistics.get_mem_mapped_file_bytes(), 8388608);
+        assert_eq!(statistics.get_mem_swap_bytes(), 4096);
+
+        let snmp = statistics.get_net_snmp_statistics();
+        assert_eq!(snmp.get_tcp_stats().get_CurrEstab(), 14);
+    }
+
+    #[test]
+    fn samples_cgroup_v1() {
+        let statistics = sampler().sample(300, None).unwrap();
+
+        assert_eq!(statistics.get_processes(), 1);
+        assert_eq!(statistics.get_cpus_user_time_secs(), 42.0);
+        assert_eq!(statistics.get_cpus_system_time_secs(), 8.0);
+        assert_eq!(statistics.get_cpus_limit(), 0.5);
+        assert_eq!(statistics.get_cpus_nr_periods(), 1000);
+        assert_eq!(statistics.get_cpus_nr_throttled(), 100);
+        assert_eq!(statistics.get_cpus_throttled_time_secs(), 2.5);
+
+        assert_eq!(statistics.get_mem_total_bytes(), 104857600);
+        assert_eq!(statistics.get_mem_total_memsw_bytes(), 110100480);
+        assert_eq!(statistics.get_mem_limit_bytes(), 134217728);
+        assert_eq!(statistics.get_mem_cache_bytes(), 20971520);
+        assert_eq!(statistics.get_mem_rss_bytes(), 73400320);
+        assert_eq!(statistics.get_mem_mapped_file_bytes(), 4194304);
+        assert_eq!(statistics.get_mem_swap_bytes(), 5242880);
+        assert_eq!(statistics.get_mem_anon_bytes(), 73400320);
+        assert_eq!(statistics.get_mem_file_bytes(), 20971520);
+    }
+
+    #[test]
+    fn samples_sandbox_disk_usage() {
+        let sandbox = env::temp_dir().join(format!(
+            "mesos-rust-statistics-{}", process::id()));
+        let _ = fs::remove_dir_all(&sandbox);
+        fs::create_dir_all(sandbox.join("nested")).unwrap();
+        File::create(sandbox.join("nested").join("data")).unwrap()
+            .write_all(&[0; 64 * 1024]).unwrap();
+
+        let statistics = sampler().sample(400, Some(&sandbox)).unwrap();
+        assert!(statistics.get_disk_used_bytes() >= 64 * 1024);
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn disk_usage_skips_files_removed_during_the_walk() {
+        let sandbox = env::temp_dir().join(format!(
+            "mesos-rust-statistics-churn-{}", process::id()));
+        let _ = fs::remove_dir_all(&sandbox);
+        fs::create_dir_all(&sandbox).unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let churn = {
+            let sandbox = sandbox.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    let directory = sandbox.join("scratch");
+                    fs::create_dir_all(directory.join("nested")).unwrap();
+                    for i in 0..16 {
+                        File::create(directory.join("nested")
+                                     .join(i.to_string())).unwrap();
+                    }
+                    fs::remove_dir_all(&directory).unwrap();
+                }
+            })
+        };
+
+        for _ in 0..1000 {
+            sampler().sample(400, Some(&sandbox)).unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+        churn.join().unwrap();
+        fs::remove_dir_all(sandbox).unwrap();
+    }
+
+    #[test]
+    fn parses_proc_net_snmp() {
+        let snmp = parse_snmp(&read(fixture("proc/100/net/snmp"))).unwrap();
+
+        let ip = snmp.get_ip_stats();
+        assert_eq!(ip.get_Forwarding(), 1);
+        assert_eq!(ip.get_InReceives(), 1893765);
+        assert_eq!(ip.get_OutNoRoutes(), 40);
+
+        let icmp = snmp.get_icmp_stats();
+        assert_eq!(icmp.get_InErrors(), 2);
+        assert_eq!(icmp.get_OutDestUnreachs(), 105);
+
+        let tcp = snmp.get_tcp_stats();
+        assert_eq!(tcp.get_MaxConn(), -1);
+        assert_eq!(tcp.get_RetransSegs(), 3512);
+
+        // UdpLite uses the same names, but must not override Udp.
+        let udp = snmp.get_udp_stats();
+        assert_eq!(udp.get_InDatagrams(), 123456);
+        assert_eq!(udp.get_IgnoredMulti(), 4481);
+
+        assert!(parse_snmp("Ip: Forwarding DefaultTTL\nIp: 1\n").is_err());
+        assert!(parse_snmp("Ip: Forwarding\n").is_err());
+    }
+
+    #[test]
+    fn parses_tc_qdisc_output() {
+        let qdiscs = parse_qdisc(&read(fixture("tc-qdisc.txt"))).unwrap();
+        assert_eq!(qdiscs.len(), 3);
+
+        let htb = &qdiscs[0];
+        assert_eq!(htb.get_id(), "htb 1:");
+        assert_eq!(htb.get_bytes(), 2894716);
+        assert_eq!(htb.get_packets(), 31840);
+        assert_eq!(htb.get_drops(), 17);
+        assert_eq!(htb.get_overlimits(), 2045);
+        assert_eq!(htb.get_requeues(), 3);
+        assert_eq!(htb.get_ratebps(), 1500);
+        assert_eq!(htb.get_ratepps(), 9);
+        assert_eq!(htb.get_backlog(), 3028);
+        assert_eq!(htb.get_qlen(), 2);
+
+        let codel = &qdiscs[1];
+        assert_eq!(codel.get_id(), "fq_codel 8001:");
+        assert_eq!(codel.get_drops(), 0);
+        assert!(!codel.has_ratebps());
+
+        assert_eq!(qdiscs[2].get_id(), "ingress ffff:");
+        assert!(parse_qdisc("qdisc htb 1: root\n Sent x bytes").is_err());
+    }
+
+    #[test]
+    fn parses_perf_stat_output() {
+        let statistics =
+            parse_perf_stat(&read(fixture("perf-stat.csv"))).unwrap();
+        assert_eq!(statistics.len(), 1);
+
+        let perf = &statistics[""];
+        assert_eq!(perf.get_task_clock(), 1523.456789);
+        assert_eq!(perf.get_context_switches(), 2210);
+        assert_eq!(perf.get_cpu_migrations(), 12);
+        assert_eq!(perf.get_page_faults(), 4096);
+        assert_eq!(perf.get_cycles(), 3456789012);
+        assert_eq!(perf.get_instructions(), 4567890123);
+        assert_eq!(perf.get_l1_dcache_load_misses(), 98765432);
+        assert_eq!(perf.get_dtlb_load_misses(), 12345);
+        assert!(!perf.has_stalled_cycles_frontend());
+        assert!(!perf.has_llc_loads());
+        assert!(!perf.has_timestamp());
+
+        assert!(parse_perf_stat("1234\n").is_err());
+    }
+
+    #[test]
+    fn parses_perf_stat_output_per_cgroup() {
+        let statistics =
+            parse_perf_stat(&read(fixture("perf-stat-cgroup.csv"))).unwrap();
+        assert_eq!(statistics.len(), 2);
+
+        let first = &statistics["mesos/task-1"];
+        assert_eq!(first.get_cycles(), 1200000);
+        assert_eq!(first.get_instructions(), 300000);
+
+        let second = &statistics["mesos/task-2"];
+        assert_eq!(second.get_cycles(), 2400000);
+        assert!(!second.has_instructions());
+    }
+}
@@ -0,0 +1,160 @@
+use proto::mesos as pb;
+use std::io;
+use std::process::Command;
+
+/// Runs `tc -s qdisc show dev <interface>` and parses its output.  With a
+/// `pid`, the command is run in the network namespace of that process via
+/// `nsenter`, which requires the appropriate privileges.
+pub fn qdisc_statistics(
+    pid: Option<u32>,
+    interface: &str
+) -> io::Result<Vec<pb::TrafficControlStatistics>> {
+    let mut command = match pid {
+        Some(pid) => {
+            let mut command = Command::new("nsenter");
+            command.arg(format!("--net=/proc/{}/ns/net", pid)).arg("tc");
+            command
+        },
+        None => Command::new("tc"),
+    };
+
+    let output = try!(command
+        .args(&["-s", "qdisc", "show", "dev", interface])
+        .output());
+
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("tc failed with {}: {}", output.status,
+                    String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    parse_qdisc(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses the output of `tc -s qdisc show`, in which each queueing
+/// discipline is described by a header line followed by indented
+/// statistics, e.g.
+///
+/// ```text
+/// qdisc htb 1: root refcnt 2 r2q 10 default 0x1 direct_packets_stat 0
+///  Sent 5678 bytes 40 pkt (dropped 2, overlimits 3 requeues 1)
+///  rate 8bit 1pps backlog 1514b 2p requeues 1
+/// ```
+///
+/// The `id` of each statistic is the kind and handle of the queueing
+/// discipline, e.g. `htb 1:`.  Rates are converted from bits to bytes per
+/// second, and `backlog` and `qlen` are the queued bytes and packets.
+pub fn parse_qdisc(
+    output: &str
+) -> io::Result<Vec<pb::TrafficControlStatistics>> {
+    let mut statistics: Vec<pb::TrafficControlStatistics> = vec![];
+
+    for line in output.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+
+        if words[0] == "qdisc" {
+            if words.len() < 3 {
+                return Err(invalid(line));
+            }
+            let mut qdisc = pb::TrafficControlStatistics::new();
+            qdisc.set_id(format!("{} {}", words[1], words[2]));
+            statistics.push(qdisc);
+            continue;
+        }
+
+        // Statistics of classes, filters etc. are not of interest.
+        let qdisc = match statistics.last_mut() {
+            Some(qdisc) => qdisc,
+            None => continue,
+        };
+
+        let mut i = 0;
+        while i < words.len() {
+            let word = words[i].trim_matches(|c| c == '(' || c == ',');
+            let next = words.get(i + 1)
+                .map(|next| next.trim_matches(|c| c == ')' || c == ','));
+
+            // "Sent <bytes> bytes <packets> pkt" counts forward, and the
+            // other statistics name the value that follows them.
+            match (word, next) {
+                ("Sent", Some(bytes)) => {
+                    qdisc.set_bytes(try!(number(bytes, line)));
+                    if let Some(packets) = words.get(i + 3) {
+                        qdisc.set_packets(try!(number(packets, line)));
+                    }
+                    i += 4;
+                    continue;
+                },
+                ("dropped", Some(value)) =>
+                    qdisc.set_drops(try!(number(value, line))),
+                ("overlimits", Some(value)) =>
+                    qdisc.set_overlimits(try!(number(value, line))),
+                ("requeues", Some(value)) =>
+                    qdisc.set_requeues(try!(number(value, line))),
+                ("rate", Some(value)) => {
+                    qdisc.set_ratebps(try!(bit_rate(value, line)) / 8);
+                    if let Some(packets) = words.get(i + 2) {
+                        if packets.ends_with("pps") {
+                            let packets = &packets[..packets.len() - 3];
+                            qdisc.set_ratepps(try!(number(packets, line)));
+                        }
+                    }
+                },
+                ("backlog", Some(value)) => {
+                    qdisc.set_backlog(try!(size(value, line)));
+                    if let Some(packets) = words.get(i + 2) {
+                        if packets.ends_with('p') {
+                            let packets = &packets[..packets.len() - 1];
+                            qdisc.set_qlen(try!(number(packets, line)));
+                        }
+                    }
+                },
+                _ => {},
+            }
+
+            i += 1;
+        }
+    }
+
+    Ok(statistics)
+}
+
+fn number(value: &str, line: &str) -> io::Result<u64> {
+    value.parse().map_err(|_| invalid(line))
+}
+
+/// Parses sizes such as "1514b", "10Kb" or "2Mb", in binary units.
+fn size(value: &str, line: &str) -> io::Result<u64> {
+    let value = value.trim_right_matches('b');
+    scaled(value, 1024, line)
+}
+
+/// Parses rates such as "8bit", "12Kbit" or "3Mbit", in decimal units.
+fn bit_rate(value: &str, line: &str) -> io::Result<u64> {
+    if !value.ends_with("bit") {
+        return Err(invalid(line));
+    }
+    scaled(&value[..value.len() - 3], 1000, line)
+}
+
+fn scaled(value: &str, base: u64, line: &str) -> io::Result<u64> {
+    let (digits, multiplier) = match value.chars().last() {
+        Some('K') => (&value[..value.len() - 1], base),
+        Some('M') => (&value[..value.len() - 1], base * base),
+        Some('G') => (&value[..value.len() - 1], base * base * base),
+        _ => (value, 1),
+    };
+
+    let value = try!(digits.parse::<f64>().map_err(|_| invalid(line)));
+    Ok((value * multiplier as f64) as u64)
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Malformed tc output: {:?}", line))
+}
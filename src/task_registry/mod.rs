@@ -0,0 +1,286 @@
+//! # Task lifecycle tracking.
+//!
+//! Mesos reports task progress through `Scheduler::status_update`, but the
+//! protobuf types say nothing about which sequences of states are legal.
+//! `TaskRegistry` consumes those updates, enforces the task state machine
+//!
+//! ```text
+//! STAGING -> STARTING -> RUNNING -> KILLING -> (terminal)
+//! ```
+//!
+//! (where any non-terminal state may skip ahead, and the terminal states
+//! are FINISHED, FAILED, KILLED, LOST and ERROR), keeps the reason and
+//! source history of every task, and answers queries about the tasks it
+//! knows of.
+//!
+//! The registry takes `&mut self`; since scheduler callbacks only receive
+//! `&self`, schedulers typically keep it behind a `Mutex` or `RefCell`.
+
+mod tests;
+
+use proto::mesos as pb;
+use std::collections::BTreeMap;
+
+/// Returns whether `state` is terminal, i.e. the task will never run again.
+pub fn is_terminal(state: pb::TaskState) -> bool {
+    match state {
+        pb::TaskState::TASK_FINISHED |
+        pb::TaskState::TASK_FAILED |
+        pb::TaskState::TASK_KILLED |
+        pb::TaskState::TASK_LOST |
+        pb::TaskState::TASK_ERROR => true,
+        pb::TaskState::TASK_STAGING |
+        pb::TaskState::TASK_STARTING |
+        pb::TaskState::TASK_RUNNING |
+        pb::TaskState::TASK_KILLING => false,
+    }
+}
+
+// Position of a non-terminal state along the lifecycle; a task may only
+// move forwards.
+fn rank(state: pb::TaskState) -> u8 {
+    match state {
+        pb::TaskState::TASK_STAGING => 0,
+        pb::TaskState::TASK_STARTING => 1,
+        pb::TaskState::TASK_RUNNING => 2,
+        pb::TaskState::TASK_KILLING => 3,
+        _ => 4,
+    }
+}
+
+/// Returns whether a task may move from state `from` to a different state
+/// `to`.  Transitions out of a terminal state are never legal.
+pub fn is_legal_transition(from: pb::TaskState, to: pb::TaskState) -> bool {
+    !is_terminal(from) && rank(to) > rank(from)
+}
+
+/// How a status update related to the previously known state of its task.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// The registry had no record of the task (e.g. the update arrived
+    /// after a scheduler failover, or via reconciliation).
+    Discovered,
+
+    /// The task moved forward along its lifecycle.
+    Legal {
+        from: pb::TaskState,
+        to: pb::TaskState,
+    },
+
+    /// The update repeated the current state of the task.  This happens
+    /// during reconciliation and for health changes of running tasks.
+    Duplicate,
+
+    /// The task moved backwards or out of a terminal state.  The current
+    /// state of the task is left unchanged.
+    Illegal {
+        from: pb::TaskState,
+        to: pb::TaskState,
+    },
+}
+
+/// A status update as recorded in the history of a task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusRecord {
+    pub state: pb::TaskState,
+    pub reason: Option<pb::TaskStatus_Reason>,
+    pub source: Option<pb::TaskStatus_Source>,
+    pub message: Option<String>,
+    pub timestamp: Option<f64>,
+    pub healthy: Option<bool>,
+    pub transition: Transition,
+}
+
+/// Everything the registry knows about a single task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedTask {
+    pub task_id: pb::TaskID,
+    pub slave_id: Option<pb::SlaveID>,
+    pub executor_id: Option<pb::ExecutorID>,
+    pub state: pb::TaskState,
+    pub history: Vec<StatusRecord>,
+}
+
+impl TrackedTask {
+
+    pub fn is_terminal(&self) -> bool {
+        is_terminal(self.state)
+    }
+
+    /// Returns the most recently recorded status update, if any.
+    pub fn last_status(&self) -> Option<&StatusRecord> {
+        self.history.last()
+    }
+}
+
+/// Registry of tasks keyed by task id, driven by status updates.
+#[derive(Clone, Debug, Default)]
+pub struct TaskRegistry {
+    tasks: BTreeMap<String, TrackedTask>,
+}
+
+impl TaskRegistry {
+
+    pub fn new() -> TaskRegistry {
+        TaskRegistry { tasks: BTreeMap::new() }
+    }
+
+    /// Records that `task` has been passed to `SchedulerDriver::launch_tasks`.
+    /// The task starts out in TASK_STAGING, as it does in the master.
+    pub fn launched(&mut self, task: &pb::TaskInfo) {
+        let executor_id = if task.has_executor() {
+            Some(task.get_executor().get_executor_id().clone())
+        } else {
+            None
+        };
+
+        self.tasks.insert(
+            task.get_task_id().get_value().to_string(),
+            TrackedTask {
+                task_id: task.get_task_id().clone(),
+                slave_id: Some(task.get_slave_id().clone()),
+                executor_id: executor_id,
+                state: pb::TaskState::TASK_STAGING,
+                history: vec![],
+            });
+    }
+
+    /// Applies a status update received in `Scheduler::status_update` and
+    /// reports how it relates to the task's previous state.
+    pub fn update(&mut self, status: &pb::TaskStatus) -> Transition {
+        let key = status.get_task_id().get_value().to_string();
+        let to = status.get_state();
+
+        let transition = match self.tasks.get(&key) {
+            None => Transition::Discovered,
+            Some(task) => {
+                let from = task.state;
+                if from == to {
+                    Transition::Duplicate
+                } else if is_legal_transition(from, to) {
+                    Transition::Legal { from: from, to: to }
+                } else {
+                    Transition::Illegal { from: from, to: to }
+                }
+            },
+        };
+
+        let task = self.tasks.entry(key).or_insert_with(|| {
+            TrackedTask {
+                task_id: status.get_task_id().clone(),
+                slave_id: None,
+                executor_id: None,
+                state: to,
+                history: vec![],
+            }
+        });
+
+        match transition {
+            Transition::Illegal { .. } => {},
+            _ => task.state = to,
+        }
+
+        if status.has_slave_id() {
+            task.slave_id = Some(status.get_slave_id().clone());
+        }
+        if status.has_executor_id() {
+            task.executor_id = Some(status.get_executor_id().clone());
+        }
+
+        task.history.push(StatusRecord {
+            state: to,
+            reason: if status.has_reason() {
+                Some(status.get_reason())
+            } else {
+                None
+            },
+            source: if status.has_source() {
+                Some(status.get_source())
+            } else {
+                None
+            },
+            message: if status.has_message() {
+                Some(status.get_message().to_string())
+            } else {
+                None
+            },
+            timestamp: if status.has_timestamp() {
+                Some(status.get_timestamp())
+            } else {
+                None
+            },
+            healthy: if status.has_healthy() {
+                Some(status.get_healthy())
+            } else {
+                None
+            },
+            transition: transition,
+        });
+
+        transition
+    }
+
+    pub fn get(&self, task_id: &pb::TaskID) -> Option<&TrackedTask> {
+        self.tasks.get(task_id.get_value())
+    }
+
+    pub fn state(&self, task_id: &pb::TaskID) -> Option<pb::TaskState> {
+        self.get(task_id).map(|task| task.state)
+    }
+
+    /// Stops tracking a task, returning its record.
+    pub fn remove(&mut self, task_id: &pb::TaskID) -> Option<TrackedTask> {
+        self.tasks.remove(task_id.get_value())
+    }
+
+    /// Stops tracking every task in a terminal state.
+    pub fn expunge_terminal(&mut self) -> Vec<TrackedTask> {
+        let keys: Vec<String> = self.tasks.iter()
+            .filter(|&(_, task)| task.is_terminal())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        keys.iter()
+            .filter_map(|key| self.tasks.remove(key))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Returns all tracked tasks, ordered by task id.
+    pub fn tasks(&self) -> Vec<&TrackedTask> {
+        self.tasks.values().collect()
+    }
+
+    pub fn in_state(&self, state: pb::TaskState) -> Vec<&TrackedTask> {
+        self.tasks.values().filter(|task| task.state == state).collect()
+    }
+
+    pub fn non_terminal(&self) -> Vec<&TrackedTask> {
+        self.tasks.values().filter(|task| !task.is_terminal()).collect()
+    }
+
+    pub fn on_slave(&self, slave_id: &pb::SlaveID) -> Vec<&TrackedTask> {
+        self.tasks.values()
+            .filter(|task| task.slave_id.as_ref() == Some(slave_id))
+            .collect()
+    }
+
+    /// Returns the tasks on `slave_id` that are not in a terminal state,
+    /// e.g. to find the tasks affected by `Scheduler::slave_lost`.
+    pub fn non_terminal_on_slave(
+        &self,
+        slave_id: &pb::SlaveID
+    ) -> Vec<&TrackedTask> {
+        self.on_slave(slave_id)
+            .into_iter()
+            .filter(|task| !task.is_terminal())
+            .collect()
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+
+    use ::proto::mesos as pb;
+    use ::task_registry::*;
+
+    fn task_id(value: &str) -> pb::TaskID {
+        let mut task_id = pb::TaskID::new();
+        task_id.set_value(value.to_string());
+        task_id
+    }
+
+    fn slave_id(value: &str) -> pb::SlaveID {
+        let mut slave_id = pb::SlaveID::new();
+        slave_id.set_value(value.to_string());
+        slave_id
+    }
+
+    fn task(id: &str, slave: &str) -> pb::TaskInfo {
+        let mut task = pb::TaskInfo::new();
+        task.set_name(id.to_string());
+        task.set_task_id(task_id(id));
+        task.set_slave_id(slave_id(slave));
+        task
+    }
+
+    fn status(id: &str, state: pb::TaskState) -> pb::TaskStatus {
+        let mut status = pb::TaskStatus::new();
+        status.set_task_id(task_id(id));
+        status.set_state(state);
+        status
+    }
+
+    #[test]
+    fn legal_lifecycle() {
+        let mut registry = TaskRegistry::new();
+        registry.launched(&task("t1", "s1"));
+        assert_eq!(registry.state(&task_id("t1")),
+                   Some(pb::TaskState::TASK_STAGING));
+
+        let states = [
+            pb::TaskState::TASK_STARTING,
+            pb::TaskState::TASK_RUNNING,
+            pb::TaskState::TASK_KILLING,
+            pb::TaskState::TASK_KILLED,
+        ];
+        let mut from = pb::TaskState::TASK_STAGING;
+        for &to in states.iter() {
+            assert_eq!(registry.update(&status("t1", to)),
+                       Transition::Legal { from: from, to: to });
+            from = to;
+        }
+
+        let tracked = registry.get(&task_id("t1")).unwrap();
+        assert!(tracked.is_terminal());
+        assert_eq!(tracked.history.len(), 4);
+    }
+
+    #[test]
+    fn states_may_be_skipped() {
+        let mut registry = TaskRegistry::new();
+        registry.launched(&task("t1", "s1"));
+        assert_eq!(
+            registry.update(&status("t1", pb::TaskState::TASK_FINISHED)),
+            Transition::Legal {
+                from: pb::TaskState::TASK_STAGING,
+                to: pb::TaskState::TASK_FINISHED,
+            });
+    }
+
+    #[test]
+    fn illegal_and_duplicate_transitions() {
+        let mut registry = TaskRegistry::new();
+        registry.launched(&task("t1", "s1"));
+        registry.update(&status("t1", pb::TaskState::TASK_RUNNING));
+
+        assert_eq!(registry.update(&status("t1", pb::TaskState::TASK_RUNNING)),
+                   Transition::Duplicate);
+        assert_eq!(
+            registry.update(&status("t1", pb::TaskState::TASK_STARTING)),
+            Transition::Illegal {
+                from: pb::TaskState::TASK_RUNNING,
+                to: pb::TaskState::TASK_STARTING,
+            });
+        assert_eq!(registry.state(&task_id("t1")),
+                   Some(pb::TaskState::TASK_RUNNING));
+
+        registry.update(&status("t1", pb::TaskState::TASK_FAILED));
+        assert_eq!(
+            registry.update(&status("t1", pb::TaskState::TASK_FINISHED)),
+            Transition::Illegal {
+                from: pb::TaskState::TASK_FAILED,
+                to: pb::TaskState::TASK_FINISHED,
+            });
+        assert_eq!(registry.state(&task_id("t1")),
+                   Some(pb::TaskState::TASK_FAILED));
+    }
+
+    #[test]
+    fn records_reason_and_source_history() {
+        let mut registry = TaskRegistry::new();
+
+        let mut lost = status("t1", pb::TaskState::TASK_LOST);
+        lost.set_reason(pb::TaskStatus_Reason::REASON_SLAVE_REMOVED);
+        lost.set_source(pb::TaskStatus_Source::SOURCE_MASTER);
+        lost.set_message("Slave removed".to_string());
+        lost.set_slave_id(slave_id("s1"));
+
+        assert_eq!(registry.update(&lost), Transition::Discovered);
+
+        let tracked = registry.get(&task_id("t1")).unwrap();
+        let record = tracked.last_status().unwrap();
+        assert_eq!(record.reason,
+                   Some(pb::TaskStatus_Reason::REASON_SLAVE_REMOVED));
+        assert_eq!(record.source, Some(pb::TaskStatus_Source::SOURCE_MASTER));
+        assert_eq!(record.message, Some("Slave removed".to_string()));
+        assert_eq!(record.healthy, None);
+        assert_eq!(tracked.slave_id, Some(slave_id("s1")));
+    }
+
+    #[test]
+    fn queries() {
+        let mut registry = TaskRegistry::new();
+        registry.launched(&task("t1", "s1"));
+        registry.launched(&task("t2", "s1"));
+        registry.launched(&task("t3", "s2"));
+        registry.update(&status("t1", pb::TaskState::TASK_RUNNING));
+        registry.update(&status("t2", pb::TaskState::TASK_FINISHED));
+
+        let ids = |tasks: Vec<&TrackedTask>| -> Vec<String> {
+            tasks.iter()
+                .map(|task| task.task_id.get_value().to_string())
+                .collect()
+        };
+
+        assert_eq!(ids(registry.non_terminal()), vec!["t1", "t3"]);
+        assert_eq!(ids(registry.on_slave(&slave_id("s1"))), vec!["t1", "t2"]);
+        assert_eq!(ids(registry.non_terminal_on_slave(&slave_id("s1"))),
+                   vec!["t1"]);
+        assert_eq!(ids(registry.in_state(pb::TaskState::TASK_STAGING)),
+                   vec!["t3"]);
+
+        assert_eq!(registry.expunge_terminal().len(), 1);
+        assert_eq!(registry.len(), 2);
+    }
+}
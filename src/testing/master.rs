@@ -0,0 +1,793 @@
+use proto::mesos as pb;
+use scheduler::{Scheduler, SchedulerDriver};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use task_registry;
+
+use super::{FakeAgent, TaskScript};
+use super::resources;
+
+/// A simulated Mesos master, which is also the `SchedulerDriver` of the
+/// scheduler under test.  See the module documentation.
+///
+/// Callbacks are delivered one at a time, after the call that caused them
+/// returns: driver calls made from within a callback (e.g. launching tasks
+/// from `resource_offers`) only queue their consequences.
+pub struct FakeMaster<'a> {
+    scheduler: &'a Scheduler,
+    state: RefCell<State>,
+    delivering: Cell<bool>,
+}
+
+struct State {
+    framework_info: pb::FrameworkInfo,
+    status: pb::Status,
+    now: Duration,
+    allocation_interval: Duration,
+    next_allocation: Duration,
+    time_limit: Duration,
+    default_script: TaskScript,
+    scripts: BTreeMap<String, TaskScript>,
+    agents: BTreeMap<String, Agent>,
+    offers: BTreeMap<String, pb::Offer>,
+    tasks: BTreeMap<String, Task>,
+    timers: Vec<Timer>,
+    events: VecDeque<Event>,
+    framework_messages: Vec<(pb::ExecutorID, pb::SlaveID, Vec<u8>)>,
+    offer_count: u64,
+    timer_count: u64,
+}
+
+struct Agent {
+    agent: FakeAgent,
+    filtered_until: Duration,
+}
+
+struct Task {
+    info: pb::TaskInfo,
+    status: pb::TaskStatus,
+}
+
+// A scripted status update, due at `at` on the simulated clock.  Timers
+// due at the same time fire in the order they were set.
+struct Timer {
+    at: Duration,
+    sequence: u64,
+    task_id: String,
+    state: pb::TaskState,
+    message: Option<String>,
+}
+
+// A callback waiting to be delivered.
+enum Event {
+    Registered,
+    Reregistered,
+    Disconnected,
+    Offers(Vec<pb::Offer>),
+    Rescinded(pb::OfferID),
+    Update(pb::TaskStatus),
+    SlaveLost(pb::SlaveID),
+    Message(pb::ExecutorID, pb::SlaveID, Vec<u8>),
+    Error(String),
+}
+
+impl<'a> FakeMaster<'a> {
+
+    /// A master for `scheduler`, which registers with `framework_info`
+    /// once `start` or `run` is called.
+    pub fn new(
+        scheduler: &'a Scheduler,
+        framework_info: &pb::FrameworkInfo
+    ) -> FakeMaster<'a> {
+        let mut framework_info = framework_info.clone();
+        if !framework_info.has_id() {
+            framework_info.mut_id().set_value("fake-framework".to_string());
+        }
+
+        FakeMaster {
+            scheduler: scheduler,
+            state: RefCell::new(State {
+                framework_info: framework_info,
+                status: pb::Status::DRIVER_NOT_STARTED,
+                now: Duration::from_secs(0),
+                allocation_interval: Duration::from_secs(1),
+                next_allocation: Duration::from_secs(0),
+                time_limit: Duration::from_secs(3600),
+                default_script: TaskScript::default(),
+                scripts: BTreeMap::new(),
+                agents: BTreeMap::new(),
+                offers: BTreeMap::new(),
+                tasks: BTreeMap::new(),
+                timers: vec![],
+                events: VecDeque::new(),
+                framework_messages: vec![],
+                offer_count: 0,
+                timer_count: 0,
+            }),
+            delivering: Cell::new(false),
+        }
+    }
+
+    /// Sets how often offers are made, 1 second by default.
+    ///
+    /// Panics if `interval` is zero, with which simulated time could not
+    /// move forward.
+    pub fn set_allocation_interval(&self, interval: Duration) {
+        assert!(interval > Duration::from_secs(0),
+                "The allocation interval must not be zero");
+        self.state.borrow_mut().allocation_interval = interval;
+    }
+
+    /// Sets how much simulated time `run` may take before it aborts the
+    /// driver, 1 hour by default.
+    pub fn set_time_limit(&self, limit: Duration) {
+        self.state.borrow_mut().time_limit = limit;
+    }
+
+    /// Sets the script of tasks that have none of their own.
+    pub fn set_default_script(&self, script: TaskScript) {
+        self.state.borrow_mut().default_script = script;
+    }
+
+    /// Sets the script of the task with the given id, once it is launched.
+    pub fn set_script(&self, task_id: &str, script: TaskScript) {
+        self.state.borrow_mut().scripts.insert(task_id.to_string(), script);
+    }
+
+    /// Adds an agent, whose resources are offered from the next
+    /// allocation on.
+    pub fn add_agent(&self, agent: FakeAgent) {
+        self.state.borrow_mut().agents.insert(agent.id.clone(), Agent {
+            agent: agent,
+            filtered_until: Duration::from_secs(0),
+        });
+    }
+
+    /// Removes an agent as if it had failed: its outstanding offer is
+    /// rescinded, its tasks are `TASK_LOST` and the scheduler is told that
+    /// the slave was lost.
+    pub fn lose_agent(&self, agent_id: &str) {
+        {
+            let mut state = self.state.borrow_mut();
+            let agent = match state.agents.remove(agent_id) {
+                Some(agent) => agent.agent,
+                None => return,
+            };
+
+            let offer_ids: Vec<String> = state.offers.values()
+                .filter(|offer| offer.get_slave_id().get_value() == agent_id)
+                .map(|offer| offer.get_id().get_value().to_string())
+                .collect();
+            for offer_id in offer_ids {
+                if let Some(offer) = state.offers.remove(&offer_id) {
+                    state.events.push_back(
+                        Event::Rescinded(offer.get_id().clone()));
+                }
+            }
+
+            let task_ids: Vec<String> = state.tasks.values()
+                .filter(|task| {
+                    task.info.get_slave_id().get_value() == agent_id &&
+                        !task_registry::is_terminal(task.status.get_state())
+                })
+                .map(|task| task.info.get_task_id().get_value().to_string())
+                .collect();
+            for task_id in task_ids {
+                state.update(&task_id, pb::TaskState::TASK_LOST,
+                             Some(pb::TaskStatus_Reason::REASON_SLAVE_REMOVED),
+                             Some("Slave removed".to_string()));
+            }
+
+            state.events.push_back(Event::SlaveLost(agent.slave_id()));
+        }
+        self.deliver();
+    }
+
+    /// Simulates the failover of the master: the scheduler is
+    /// disconnected, outstanding offers become invalid, and the scheduler
+    /// re-registers with the new master.  Tasks keep running.
+    pub fn fail_over(&self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.offers.clear();
+            state.events.push_back(Event::Disconnected);
+            state.events.push_back(Event::Reregistered);
+        }
+        self.deliver();
+    }
+
+    /// Registers the scheduler and makes the first offers.
+    pub fn start(&self) -> i32 {
+        {
+            let mut state = self.state.borrow_mut();
+            if state.status != pb::Status::DRIVER_NOT_STARTED {
+                return state.status as i32;
+            }
+            state.status = pb::Status::DRIVER_RUNNING;
+            state.events.push_back(Event::Registered);
+            state.allocate();
+        }
+        self.deliver();
+        self.status()
+    }
+
+    /// Makes offers for every agent with unused, unfiltered resources that
+    /// are not already offered, without waiting for the next allocation.
+    pub fn allocate(&self) {
+        self.state.borrow_mut().allocate();
+        self.deliver();
+    }
+
+    /// Rescinds an outstanding offer.
+    pub fn rescind(&self, offer_id: &str) {
+        {
+            let mut state = self.state.borrow_mut();
+            if let Some(offer) = state.offers.remove(offer_id) {
+                state.events.push_back(
+                    Event::Rescinded(offer.get_id().clone()));
+            }
+        }
+        self.deliver();
+    }
+
+    /// Sends a status update for a launched task, out of script.
+    pub fn update(
+        &self,
+        task_id: &str,
+        state: pb::TaskState,
+        message: Option<&str>
+    ) {
+        self.state.borrow_mut().update(
+            task_id, state, None, message.map(|m| m.to_string()));
+        self.deliver();
+    }
+
+    /// Delivers a framework message from an executor.
+    pub fn executor_message(
+        &self,
+        executor_id: &str,
+        agent_id: &str,
+        data: &[u8]
+    ) {
+        let mut id = pb::ExecutorID::new();
+        id.set_value(executor_id.to_string());
+        let mut slave_id = pb::SlaveID::new();
+        slave_id.set_value(agent_id.to_string());
+
+        self.state.borrow_mut().events.push_back(
+            Event::Message(id, slave_id, data.to_vec()));
+        self.deliver();
+    }
+
+    /// Reports an unrecoverable error to the scheduler, which aborts the
+    /// driver.
+    pub fn error(&self, message: &str) {
+        self.state.borrow_mut().events.push_back(
+            Event::Error(message.to_string()));
+        self.deliver();
+    }
+
+    /// Moves the simulated clock forward, sending the scripted updates and
+    /// making the offers that fall due on the way.
+    pub fn advance(&self, duration: Duration) {
+        let target = self.now() + duration;
+        loop {
+            {
+                let mut state = self.state.borrow_mut();
+                if state.status != pb::Status::DRIVER_RUNNING {
+                    break;
+                }
+
+                let next_timer = state.timers.iter()
+                    .map(|timer| timer.at)
+                    .min();
+                let next = match next_timer {
+                    Some(at) if at < state.next_allocation => at,
+                    _ => state.next_allocation,
+                };
+                if next > target {
+                    break;
+                }
+
+                state.now = next;
+                state.fire_timers();
+                if state.next_allocation <= state.now {
+                    state.allocate();
+                }
+            }
+            self.deliver();
+        }
+
+        let mut state = self.state.borrow_mut();
+        if state.now < target {
+            state.now = target;
+        }
+    }
+
+    /// The simulated time since the master was created.
+    pub fn now(&self) -> Duration {
+        self.state.borrow().now
+    }
+
+    /// The current driver status, as returned by driver calls.
+    pub fn status(&self) -> i32 {
+        self.state.borrow().status as i32
+    }
+
+    /// The outstanding offers, in the order they were made.
+    pub fn offers(&self) -> Vec<pb::Offer> {
+        let state = self.state.borrow();
+        let mut offers: Vec<pb::Offer> = state.offers.values()
+            .cloned()
+            .collect();
+        offers.sort_by_key(|offer| offer_number(offer.get_id()));
+        offers
+    }
+
+    /// The tasks accepted by the master, in task id order.
+    pub fn tasks(&self) -> Vec<pb::TaskInfo> {
+        self.state.borrow().tasks.values()
+            .map(|task| task.info.clone())
+            .collect()
+    }
+
+    /// The latest state of a task, if it was launched.
+    pub fn task_state(&self, task_id: &str) -> Option<pb::TaskState> {
+        self.state.borrow().tasks.get(task_id)
+            .map(|task| task.status.get_state())
+    }
+
+    /// The messages the scheduler sent to executors.
+    pub fn framework_messages(
+        &self
+    ) -> Vec<(pb::ExecutorID, pb::SlaveID, Vec<u8>)> {
+        self.state.borrow().framework_messages.clone()
+    }
+
+    // Delivers the queued callbacks, unless called from within one.
+    fn deliver(&self) {
+        if self.delivering.get() {
+            return;
+        }
+
+        self.delivering.set(true);
+        loop {
+            let (event, framework_info) = {
+                let mut state = self.state.borrow_mut();
+                if state.status != pb::Status::DRIVER_RUNNING {
+                    state.events.clear();
+                }
+                match state.events.pop_front() {
+                    Some(event) => (event, state.framework_info.clone()),
+                    None => break,
+                }
+            };
+            self.dispatch(event, &framework_info);
+        }
+        self.delivering.set(false);
+    }
+
+    fn dispatch(&self, event: Event, framework_info: &pb::FrameworkInfo) {
+        let scheduler = self.scheduler;
+        match event {
+            Event::Registered => scheduler.registered(
+                self, framework_info.get_id(), &master_info()),
+            Event::Reregistered =>
+                scheduler.reregistered(self, &master_info()),
+            Event::Disconnected => scheduler.disconnected(self),
+            Event::Offers(offers) => scheduler.resource_offers(self, offers),
+            Event::Rescinded(offer_id) =>
+                scheduler.offer_rescinded(self, &offer_id),
+            Event::Update(status) => scheduler.status_update(self, &status),
+            Event::SlaveLost(slave_id) =>
+                scheduler.slave_lost(self, &slave_id),
+            Event::Message(executor_id, slave_id, data) => {
+                let data = String::from_utf8_lossy(&data).into_owned();
+                scheduler.framework_message(
+                    self, &executor_id, &slave_id, &data);
+            },
+            Event::Error(message) => {
+                self.state.borrow_mut().status = pb::Status::DRIVER_ABORTED;
+                scheduler.error(self, &message);
+            },
+        }
+    }
+
+    // Runs `call` if the driver is running, and returns the driver status.
+    fn call<F: FnOnce(&mut State)>(&self, call: F) -> i32 {
+        {
+            let mut state = self.state.borrow_mut();
+            if state.status != pb::Status::DRIVER_RUNNING {
+                return state.status as i32;
+            }
+            call(&mut state);
+        }
+        self.deliver();
+        self.status()
+    }
+}
+
+impl<'a> SchedulerDriver for FakeMaster<'a> {
+
+    /// Starts the simulation and advances the clock until the scheduler
+    /// stops the driver, or aborts it once the time limit is reached.
+    fn run(&mut self) -> i32 {
+        self.start();
+        while self.status() == pb::Status::DRIVER_RUNNING as i32 {
+            let (now, limit, interval) = {
+                let state = self.state.borrow();
+                (state.now, state.time_limit, state.allocation_interval)
+            };
+            if now >= limit {
+                warn!("Aborting the fake master after [{:?}]", limit);
+                self.state.borrow_mut().status = pb::Status::DRIVER_ABORTED;
+                break;
+            }
+            self.advance(interval);
+        }
+        self.status()
+    }
+
+    fn stop(&self, failover: bool) -> i32 {
+        let mut state = self.state.borrow_mut();
+        if state.status != pb::Status::DRIVER_RUNNING {
+            return state.status as i32;
+        }
+
+        // Without failover, the framework is torn down with its tasks.
+        if !failover {
+            for task in state.tasks.values_mut() {
+                if !task_registry::is_terminal(task.status.get_state()) {
+                    task.status.set_state(pb::TaskState::TASK_KILLED);
+                }
+            }
+            state.timers.clear();
+        }
+        state.offers.clear();
+        state.status = pb::Status::DRIVER_STOPPED;
+        state.status as i32
+    }
+
+    fn request_resources(&self, _requests: &Vec<&pb::Request>) -> i32 {
+        self.call(|_| {})
+    }
+
+    fn decline_offer(
+        &self,
+        offer_id: &pb::OfferID,
+        filters: &pb::Filters) -> i32 {
+
+        self.call(|state| state.decline(offer_id, filters))
+    }
+
+    fn launch_tasks(
+        &self,
+        offer_id: &pb::OfferID,
+        tasks: &Vec<&pb::TaskInfo>,
+        filters: &pb::Filters) -> i32 {
+
+        self.call(|state| state.launch(offer_id, tasks, filters))
+    }
+
+    fn revive_offers(&self) -> i32 {
+        self.call(|state| {
+            for agent in state.agents.values_mut() {
+                agent.filtered_until = Duration::from_secs(0);
+            }
+        })
+    }
+
+    fn kill_task(&self, task_id: &pb::TaskID) -> i32 {
+        self.call(|state| state.kill(task_id))
+    }
+
+    fn reconcile_tasks(&self, statuses: &Vec<&pb::TaskStatus>) -> i32 {
+        self.call(|state| state.reconcile(statuses))
+    }
+
+    fn send_framework_message(
+        &self,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &Vec<u8>) -> i32 {
+
+        self.call(|state| state.framework_messages.push(
+            (executor_id.clone(), slave_id.clone(), data.clone())))
+    }
+}
+
+impl State {
+
+    fn allocate(&mut self) {
+        let mut offers = vec![];
+        let offered: Vec<String> = self.offers.values()
+            .map(|offer| offer.get_slave_id().get_value().to_string())
+            .collect();
+
+        let agents: Vec<FakeAgent> = self.agents.values()
+            .filter(|agent| agent.filtered_until <= self.now)
+            .filter(|agent| !offered.contains(&agent.agent.id))
+            .map(|agent| agent.agent.clone())
+            .collect();
+
+        for agent in agents {
+            let available = self.available(&agent.id);
+            if available.is_empty() {
+                continue;
+            }
+
+            self.offer_count += 1;
+            let mut offer = pb::Offer::new();
+            offer.mut_id().set_value(format!("offer-{}", self.offer_count));
+            offer.set_framework_id(self.framework_info.get_id().clone());
+            offer.set_slave_id(agent.slave_id());
+            offer.set_hostname(agent.hostname.clone());
+            offer.set_resources(available.into_iter().collect());
+            offer.set_attributes(agent.attributes.into_iter().collect());
+            offers.push(offer);
+        }
+
+        self.next_allocation = self.now + self.allocation_interval;
+        if !offers.is_empty() {
+            for offer in &offers {
+                self.offers.insert(offer.get_id().get_value().to_string(),
+                                   offer.clone());
+            }
+            self.events.push_back(Event::Offers(offers));
+        }
+    }
+
+    // The resources of an agent that no live task is using.
+    fn available(&self, agent_id: &str) -> Vec<pb::Resource> {
+        let mut available = match self.agents.get(agent_id) {
+            Some(agent) => agent.agent.resources.clone(),
+            None => return vec![],
+        };
+
+        for task in self.tasks.values() {
+            if task.info.get_slave_id().get_value() == agent_id &&
+               !task_registry::is_terminal(task.status.get_state()) {
+                for resource in task.info.get_resources() {
+                    resources::subtract(&mut available, resource);
+                }
+            }
+        }
+        available.retain(|resource| !resources::is_empty(resource));
+        available
+    }
+
+    fn filter(&mut self, agent_id: &str, filters: &pb::Filters) {
+        let refuse = filters.get_refuse_seconds().max(0.0);
+        let until = self.now + Duration::from_millis(
+            (refuse * 1000.0) as u64);
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.filtered_until = until;
+        }
+    }
+
+    fn decline(&mut self, offer_id: &pb::OfferID, filters: &pb::Filters) {
+        if let Some(offer) = self.offers.remove(offer_id.get_value()) {
+            self.filter(offer.get_slave_id().get_value(), filters);
+        }
+    }
+
+    fn launch(
+        &mut self,
+        offer_id: &pb::OfferID,
+        tasks: &[&pb::TaskInfo],
+        filters: &pb::Filters
+    ) {
+        let offer = match self.offers.remove(offer_id.get_value()) {
+            Some(offer) => offer,
+            None => {
+                for task in tasks {
+                    self.reject(task, pb::TaskState::TASK_LOST,
+                                pb::TaskStatus_Reason::REASON_INVALID_OFFERS,
+                                "Task launched with invalid offers");
+                }
+                return;
+            },
+        };
+
+        let mut available = offer.get_resources().to_vec();
+        for task in tasks {
+            let task_id = task.get_task_id().get_value().to_string();
+            if self.tasks.contains_key(&task_id) {
+                self.reject(task, pb::TaskState::TASK_ERROR,
+                            pb::TaskStatus_Reason::REASON_TASK_INVALID,
+                            "Task has duplicate ID");
+                continue;
+            }
+            if task.get_slave_id() != offer.get_slave_id() {
+                self.reject(task, pb::TaskState::TASK_ERROR,
+                            pb::TaskStatus_Reason::REASON_TASK_INVALID,
+                            "Task uses invalid slave");
+                continue;
+            }
+
+            let mut remaining = available.clone();
+            let fits = task.get_resources().iter()
+                .all(|resource| resources::subtract(&mut remaining,
+                                                    resource));
+            if !fits {
+                self.reject(task, pb::TaskState::TASK_ERROR,
+                            pb::TaskStatus_Reason::REASON_TASK_INVALID,
+                            "Task uses more resources than offered");
+                continue;
+            }
+            available = remaining;
+
+            let mut status = pb::TaskStatus::new();
+            status.set_task_id(task.get_task_id().clone());
+            status.set_state(pb::TaskState::TASK_STAGING);
+            status.set_slave_id(offer.get_slave_id().clone());
+            self.tasks.insert(task_id.clone(), Task {
+                info: (*task).clone(),
+                status: status,
+            });
+
+            let script = self.scripts.get(&task_id)
+                .unwrap_or(&self.default_script)
+                .clone();
+            for step in script.steps {
+                self.timer_count += 1;
+                self.timers.push(Timer {
+                    at: self.now + step.after,
+                    sequence: self.timer_count,
+                    task_id: task_id.clone(),
+                    state: step.state,
+                    message: step.message,
+                });
+            }
+        }
+
+        if available.iter().any(|resource| !resources::is_empty(resource)) {
+            self.filter(offer.get_slave_id().get_value(), filters);
+        }
+        self.fire_timers();
+    }
+
+    // Sends a terminal update for a task the master did not accept.
+    fn reject(
+        &mut self,
+        task: &pb::TaskInfo,
+        state: pb::TaskState,
+        reason: pb::TaskStatus_Reason,
+        message: &str
+    ) {
+        let mut status = pb::TaskStatus::new();
+        status.set_task_id(task.get_task_id().clone());
+        status.set_state(state);
+        status.set_reason(reason);
+        status.set_message(message.to_string());
+        status.set_source(pb::TaskStatus_Source::SOURCE_MASTER);
+        status.set_timestamp(seconds(self.now));
+        self.events.push_back(Event::Update(status));
+    }
+
+    fn kill(&mut self, task_id: &pb::TaskID) {
+        let task_id = task_id.get_value();
+        match self.tasks.get(task_id).map(|task| task.status.get_state()) {
+            Some(state) if !task_registry::is_terminal(state) => {
+                self.update(task_id, pb::TaskState::TASK_KILLED, None, None);
+            },
+            Some(_) => {},
+            None => {
+                let mut status = pb::TaskStatus::new();
+                status.mut_task_id().set_value(task_id.to_string());
+                status.set_state(pb::TaskState::TASK_LOST);
+                status.set_message("Attempted to kill an unknown task"
+                                   .to_string());
+                status.set_source(pb::TaskStatus_Source::SOURCE_MASTER);
+                status.set_timestamp(seconds(self.now));
+                self.events.push_back(Event::Update(status));
+            },
+        }
+    }
+
+    fn reconcile(&mut self, statuses: &[&pb::TaskStatus]) {
+        let task_ids: Vec<String> = if statuses.is_empty() {
+            self.tasks.values()
+                .filter(|task| {
+                    !task_registry::is_terminal(task.status.get_state())
+                })
+                .map(|task| task.info.get_task_id().get_value().to_string())
+                .collect()
+        } else {
+            statuses.iter()
+                .map(|status| status.get_task_id().get_value().to_string())
+                .collect()
+        };
+
+        for task_id in task_ids {
+            let mut status = match self.tasks.get(&task_id) {
+                Some(task) => task.status.clone(),
+                None => {
+                    let mut status = pb::TaskStatus::new();
+                    status.mut_task_id().set_value(task_id);
+                    status.set_state(pb::TaskState::TASK_LOST);
+                    status
+                },
+            };
+            status.set_reason(pb::TaskStatus_Reason::REASON_RECONCILIATION);
+            status.set_source(pb::TaskStatus_Source::SOURCE_MASTER);
+            status.set_timestamp(seconds(self.now));
+            self.events.push_back(Event::Update(status));
+        }
+    }
+
+    // Records and queues an update for a launched task, unless it has
+    // already reached a terminal state.
+    fn update(
+        &mut self,
+        task_id: &str,
+        state: pb::TaskState,
+        reason: Option<pb::TaskStatus_Reason>,
+        message: Option<String>
+    ) {
+        let now = self.now;
+        let task = match self.tasks.get_mut(task_id) {
+            Some(task) => task,
+            None => return,
+        };
+        if task_registry::is_terminal(task.status.get_state()) {
+            return;
+        }
+
+        let mut status = pb::TaskStatus::new();
+        status.set_task_id(task.info.get_task_id().clone());
+        status.set_state(state);
+        status.set_slave_id(task.info.get_slave_id().clone());
+        status.set_timestamp(seconds(now));
+        status.set_source(match reason {
+            Some(_) => pb::TaskStatus_Source::SOURCE_MASTER,
+            None => pb::TaskStatus_Source::SOURCE_EXECUTOR,
+        });
+        if let Some(reason) = reason {
+            status.set_reason(reason);
+        }
+        if let Some(message) = message {
+            status.set_message(message);
+        }
+
+        task.status = status.clone();
+        self.events.push_back(Event::Update(status));
+    }
+
+    // Sends the scripted updates that are due, in order.
+    fn fire_timers(&mut self) {
+        let now = self.now;
+        let mut due: Vec<Timer> = vec![];
+        let mut index = 0;
+        while index < self.timers.len() {
+            if self.timers[index].at <= now {
+                due.push(self.timers.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+
+        due.sort_by_key(|timer| (timer.at, timer.sequence));
+        for timer in due {
+            self.update(&timer.task_id, timer.state, None, timer.message);
+        }
+    }
+}
+
+fn master_info() -> pb::MasterInfo {
+    let mut master_info = pb::MasterInfo::new();
+    master_info.set_id("fake-master".to_string());
+    master_info.set_ip(0x0100007f);
+    master_info.set_port(5050);
+    master_info.set_hostname("localhost".to_string());
+    master_info
+}
+
+fn offer_number(offer_id: &pb::OfferID) -> u64 {
+    offer_id.get_value().trim_start_matches("offer-").parse().unwrap_or(0)
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
@@ -0,0 +1,426 @@
+use executor::ExecutorDriver;
+use proto::mesos as pb;
+use scheduler::SchedulerDriver;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// A call made on a `MockSchedulerDriver`, with copies of its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DriverCall {
+    Run,
+    Stop { failover: bool },
+    RequestResources(Vec<pb::Request>),
+    DeclineOffer(pb::OfferID, pb::Filters),
+    LaunchTasks(pb::OfferID, Vec<pb::TaskInfo>, pb::Filters),
+    ReviveOffers,
+    KillTask(pb::TaskID),
+    ReconcileTasks(Vec<pb::TaskStatus>),
+    SendFrameworkMessage(pb::ExecutorID, pb::SlaveID, Vec<u8>),
+}
+
+/// A method of `MockSchedulerDriver`, for `return_status_for`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DriverMethod {
+    Run,
+    Stop,
+    RequestResources,
+    DeclineOffer,
+    LaunchTasks,
+    ReviveOffers,
+    KillTask,
+    ReconcileTasks,
+    SendFrameworkMessage,
+}
+
+impl DriverMethod {
+
+    /// The name of the method, e.g. `launch_tasks`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            DriverMethod::Run => "run",
+            DriverMethod::Stop => "stop",
+            DriverMethod::RequestResources => "request_resources",
+            DriverMethod::DeclineOffer => "decline_offer",
+            DriverMethod::LaunchTasks => "launch_tasks",
+            DriverMethod::ReviveOffers => "revive_offers",
+            DriverMethod::KillTask => "kill_task",
+            DriverMethod::ReconcileTasks => "reconcile_tasks",
+            DriverMethod::SendFrameworkMessage => "send_framework_message",
+        }
+    }
+}
+
+impl DriverCall {
+
+    /// The driver method that was called.
+    pub fn method(&self) -> DriverMethod {
+        match *self {
+            DriverCall::Run => DriverMethod::Run,
+            DriverCall::Stop { .. } => DriverMethod::Stop,
+            DriverCall::RequestResources(..) => DriverMethod::RequestResources,
+            DriverCall::DeclineOffer(..) => DriverMethod::DeclineOffer,
+            DriverCall::LaunchTasks(..) => DriverMethod::LaunchTasks,
+            DriverCall::ReviveOffers => DriverMethod::ReviveOffers,
+            DriverCall::KillTask(..) => DriverMethod::KillTask,
+            DriverCall::ReconcileTasks(..) => DriverMethod::ReconcileTasks,
+            DriverCall::SendFrameworkMessage(..) =>
+                DriverMethod::SendFrameworkMessage,
+        }
+    }
+
+    /// The name of the driver method, e.g. `launch_tasks`.
+    pub fn name(&self) -> &'static str {
+        self.method().name()
+    }
+}
+
+/// A call made on a `MockExecutorDriver`, with copies of its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecutorCall {
+    Run,
+    Stop,
+    SendStatusUpdate(pb::TaskStatus),
+    SendFrameworkMessage(Vec<u8>),
+}
+
+/// A method of `MockExecutorDriver`, for `return_status_for`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExecutorMethod {
+    Run,
+    Stop,
+    SendStatusUpdate,
+    SendFrameworkMessage,
+}
+
+impl ExecutorMethod {
+
+    /// The name of the method, e.g. `send_status_update`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ExecutorMethod::Run => "run",
+            ExecutorMethod::Stop => "stop",
+            ExecutorMethod::SendStatusUpdate => "send_status_update",
+            ExecutorMethod::SendFrameworkMessage => "send_framework_message",
+        }
+    }
+}
+
+impl ExecutorCall {
+
+    /// The driver method that was called.
+    pub fn method(&self) -> ExecutorMethod {
+        match *self {
+            ExecutorCall::Run => ExecutorMethod::Run,
+            ExecutorCall::Stop => ExecutorMethod::Stop,
+            ExecutorCall::SendStatusUpdate(..) =>
+                ExecutorMethod::SendStatusUpdate,
+            ExecutorCall::SendFrameworkMessage(..) =>
+                ExecutorMethod::SendFrameworkMessage,
+        }
+    }
+
+    /// The name of the driver method, e.g. `send_status_update`.
+    pub fn name(&self) -> &'static str {
+        self.method().name()
+    }
+}
+
+// The statuses returned by the calls of a mock driver, by method.
+#[derive(Debug)]
+struct Statuses<M: Eq + Hash> {
+    default: pb::Status,
+    by_method: HashMap<M, pb::Status>,
+}
+
+impl<M: Eq + Hash> Statuses<M> {
+    fn new() -> Statuses<M> {
+        Statuses {
+            default: pb::Status::DRIVER_RUNNING,
+            by_method: HashMap::new(),
+        }
+    }
+
+    fn get(&self, method: M) -> i32 {
+        *self.by_method.get(&method).unwrap_or(&self.default) as i32
+    }
+}
+
+/// A `SchedulerDriver` that records the calls made on it, for assertions
+/// in tests.  Calls return `DRIVER_RUNNING` unless configured otherwise.
+///
+/// The drivers of this crate acknowledge status updates implicitly, so
+/// there are no acknowledgements to record.
+#[derive(Debug)]
+pub struct MockSchedulerDriver {
+    calls: RefCell<Vec<DriverCall>>,
+    statuses: RefCell<Statuses<DriverMethod>>,
+}
+
+impl MockSchedulerDriver {
+
+    pub fn new() -> MockSchedulerDriver {
+        MockSchedulerDriver {
+            calls: RefCell::new(vec![]),
+            statuses: RefCell::new(Statuses::new()),
+        }
+    }
+
+    /// Sets the status returned by calls without a status of their own.
+    pub fn return_status(&self, status: pb::Status) {
+        self.statuses.borrow_mut().default = status;
+    }
+
+    /// Sets the status returned by one method, e.g. `return_status_for(
+    /// DriverMethod::LaunchTasks, pb::Status::DRIVER_ABORTED)`.
+    pub fn return_status_for(&self, method: DriverMethod, status: pb::Status) {
+        self.statuses.borrow_mut().by_method.insert(method, status);
+    }
+
+    /// The calls made so far, in order.
+    pub fn calls(&self) -> Vec<DriverCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Returns the calls made so far and forgets them.
+    pub fn take_calls(&self) -> Vec<DriverCall> {
+        self.calls.borrow_mut().drain(..).collect()
+    }
+
+    /// The tasks launched so far, across all launches.
+    pub fn launched_tasks(&self) -> Vec<pb::TaskInfo> {
+        self.calls.borrow().iter()
+            .filter_map(|call| match *call {
+                DriverCall::LaunchTasks(_, ref tasks, _) => Some(tasks),
+                _ => None,
+            })
+            .flat_map(|tasks| tasks.iter().cloned())
+            .collect()
+    }
+
+    pub fn declined_offers(&self) -> Vec<pb::OfferID> {
+        self.calls.borrow().iter()
+            .filter_map(|call| match *call {
+                DriverCall::DeclineOffer(ref offer_id, _) =>
+                    Some(offer_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn killed_tasks(&self) -> Vec<pb::TaskID> {
+        self.calls.borrow().iter()
+            .filter_map(|call| match *call {
+                DriverCall::KillTask(ref task_id) => Some(task_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The statuses of each `reconcile_tasks` call; an empty list is an
+    /// implicit reconciliation.
+    pub fn reconciliations(&self) -> Vec<Vec<pb::TaskStatus>> {
+        self.calls.borrow().iter()
+            .filter_map(|call| match *call {
+                DriverCall::ReconcileTasks(ref statuses) =>
+                    Some(statuses.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn framework_messages(
+        &self
+    ) -> Vec<(pb::ExecutorID, pb::SlaveID, Vec<u8>)> {
+        self.calls.borrow().iter()
+            .filter_map(|call| match *call {
+                DriverCall::SendFrameworkMessage(ref executor_id,
+                                                 ref slave_id, ref data) =>
+                    Some((executor_id.clone(), slave_id.clone(),
+                          data.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, call: DriverCall) -> i32 {
+        let status = self.statuses.borrow().get(call.method());
+        self.calls.borrow_mut().push(call);
+        status
+    }
+}
+
+impl SchedulerDriver for MockSchedulerDriver {
+
+    fn run(&mut self) -> i32 {
+        self.record(DriverCall::Run)
+    }
+
+    fn stop(&self, failover: bool) -> i32 {
+        self.record(DriverCall::Stop { failover: failover })
+    }
+
+    fn request_resources(&self, requests: &Vec<&pb::Request>) -> i32 {
+        self.record(DriverCall::RequestResources(
+            requests.iter().map(|&request| request.clone()).collect()))
+    }
+
+    fn decline_offer(
+        &self,
+        offer_id: &pb::OfferID,
+        filters: &pb::Filters) -> i32 {
+
+        self.record(DriverCall::DeclineOffer(offer_id.clone(),
+                                             filters.clone()))
+    }
+
+    fn launch_tasks(
+        &self,
+        offer_id: &pb::OfferID,
+        tasks: &Vec<&pb::TaskInfo>,
+        filters: &pb::Filters) -> i32 {
+
+        self.record(DriverCall::LaunchTasks(
+            offer_id.clone(),
+            tasks.iter().map(|&task| task.clone()).collect(),
+            filters.clone()))
+    }
+
+    fn revive_offers(&self) -> i32 {
+        self.record(DriverCall::ReviveOffers)
+    }
+
+    fn kill_task(&self, task_id: &pb::TaskID) -> i32 {
+        self.record(DriverCall::KillTask(task_id.clone()))
+    }
+
+    fn reconcile_tasks(&self, statuses: &Vec<&pb::TaskStatus>) -> i32 {
+        self.record(DriverCall::ReconcileTasks(
+            statuses.iter().map(|&status| status.clone()).collect()))
+    }
+
+    fn send_framework_message(
+        &self,
+        executor_id: &pb::ExecutorID,
+        slave_id: &pb::SlaveID,
+        data: &Vec<u8>) -> i32 {
+
+        self.record(DriverCall::SendFrameworkMessage(
+            executor_id.clone(), slave_id.clone(), data.clone()))
+    }
+}
+
+/// An `ExecutorDriver` that records the calls made on it, for assertions
+/// in tests.  Calls return `DRIVER_RUNNING` unless configured otherwise.
+///
+/// Unlike `MockSchedulerDriver`, it can be shared with the threads of an
+/// executor such as `CommandExecutor`; `wait_for_calls` waits for them.
+#[derive(Debug)]
+pub struct MockExecutorDriver {
+    calls: Mutex<Vec<ExecutorCall>>,
+    called: Condvar,
+    statuses: Mutex<Statuses<ExecutorMethod>>,
+}
+
+impl MockExecutorDriver {
+
+    pub fn new() -> MockExecutorDriver {
+        MockExecutorDriver {
+            calls: Mutex::new(vec![]),
+            called: Condvar::new(),
+            statuses: Mutex::new(Statuses::new()),
+        }
+    }
+
+    /// Sets the status returned by calls without a status of their own.
+    pub fn return_status(&self, status: pb::Status) {
+        self.statuses.lock().unwrap().default = status;
+    }
+
+    /// Sets the status returned by one method, e.g. `return_status_for(
+    /// ExecutorMethod::SendStatusUpdate, pb::Status::DRIVER_STOPPED)`.
+    pub fn return_status_for(
+        &self,
+        method: ExecutorMethod,
+        status: pb::Status) {
+        self.statuses.lock().unwrap().by_method.insert(method, status);
+    }
+
+    /// The calls made so far, in order.
+    pub fn calls(&self) -> Vec<ExecutorCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Returns the calls made so far and forgets them.
+    pub fn take_calls(&self) -> Vec<ExecutorCall> {
+        self.calls.lock().unwrap().drain(..).collect()
+    }
+
+    /// Waits until at least `count` calls have been made, or `timeout` has
+    /// passed, and returns the calls made so far.
+    pub fn wait_for_calls(
+        &self,
+        count: usize,
+        timeout: Duration
+    ) -> Vec<ExecutorCall> {
+        let deadline = Instant::now() + timeout;
+        let mut calls = self.calls.lock().unwrap();
+        while calls.len() < count {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            calls = self.called.wait_timeout(calls, deadline - now)
+                .unwrap().0;
+        }
+        calls.clone()
+    }
+
+    pub fn status_updates(&self) -> Vec<pb::TaskStatus> {
+        self.calls.lock().unwrap().iter()
+            .filter_map(|call| match *call {
+                ExecutorCall::SendStatusUpdate(ref status) =>
+                    Some(status.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn framework_messages(&self) -> Vec<Vec<u8>> {
+        self.calls.lock().unwrap().iter()
+            .filter_map(|call| match *call {
+                ExecutorCall::SendFrameworkMessage(ref data) =>
+                    Some(data.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, call: ExecutorCall) -> i32 {
+        let status = self.statuses.lock().unwrap().get(call.method());
+        self.calls.lock().unwrap().push(call);
+        self.called.notify_all();
+        status
+    }
+}
+
+impl ExecutorDriver for MockExecutorDriver {
+
+    fn run(&mut self) -> i32 {
+        self.record(ExecutorCall::Run)
+    }
+
+    fn stop(&self) -> i32 {
+        self.record(ExecutorCall::Stop)
+    }
+
+    fn send_status_update(&self, task_status: &pb::TaskStatus) -> i32 {
+        self.record(ExecutorCall::SendStatusUpdate(task_status.clone()))
+    }
+
+    fn send_framework_message(&self, data: &Vec<u8>) -> i32 {
+        self.record(ExecutorCall::SendFrameworkMessage(data.clone()))
+    }
+}
@@ -0,0 +1,173 @@
+//! # Testing schedulers without a cluster.
+//!
+//! `FakeMaster` simulates a Mesos master and its agents in process, so that
+//! a `Scheduler` can be exercised under plain `cargo test`.  It implements
+//! `SchedulerDriver`, and delivers callbacks to the scheduler as the test
+//! drives the simulation:
+//!
+//! ```ignore
+//! let master = FakeMaster::new(&scheduler, &framework_info);
+//! master.add_agent(FakeAgent::new("agent-1").cpus(4.0).mem(4096.0));
+//! master.set_default_script(TaskScript::new()
+//!     .then(Duration::from_secs(0), pb::TaskState::TASK_RUNNING)
+//!     .then(Duration::from_secs(30), pb::TaskState::TASK_FINISHED));
+//!
+//! master.start();                        // registered, then offers
+//! master.advance(Duration::from_secs(60));
+//! assert_eq!(master.task_state("task-1"),
+//!            Some(pb::TaskState::TASK_FINISHED));
+//! ```
+//!
+//! Time is simulated: it only moves when the test calls `advance` (or
+//! `run`), and status timestamps, offer filters and scripted updates all
+//! use the simulated clock, so runs are deterministic.  Offers are made
+//! every `allocation_interval`, for all unused, unfiltered resources of
+//! each agent.
+//!
+//! Launched tasks follow their `TaskScript`, by default reaching
+//! `TASK_RUNNING` right away and staying there.  Tasks launched on invalid
+//! offers are `TASK_LOST`, and tasks that do not fit in their offer, or
+//! reuse a task id, get `TASK_ERROR`, as with a real master.
+//!
+//! Schedulers and executors can also be called directly, with a
+//! `MockSchedulerDriver` or a `MockExecutorDriver` that records the calls
+//! made on it and returns configurable statuses.
+
+mod master;
+mod mock;
+mod resources;
+mod tests;
+
+pub use self::master::FakeMaster;
+pub use self::mock::{DriverCall, DriverMethod, MockSchedulerDriver};
+pub use self::mock::{ExecutorCall, ExecutorMethod, MockExecutorDriver};
+
+use builder::resource;
+use proto::mesos as pb;
+use std::time::Duration;
+
+/// An agent of the simulated cluster.
+#[derive(Clone, Debug)]
+pub struct FakeAgent {
+    id: String,
+    hostname: String,
+    resources: Vec<pb::Resource>,
+    attributes: Vec<pb::Attribute>,
+}
+
+impl FakeAgent {
+
+    /// An agent without resources, named after its id.
+    pub fn new(id: &str) -> FakeAgent {
+        FakeAgent {
+            id: id.to_string(),
+            hostname: id.to_string(),
+            resources: vec![],
+            attributes: vec![],
+        }
+    }
+
+    pub fn hostname(mut self, hostname: &str) -> FakeAgent {
+        self.hostname = hostname.to_string();
+        self
+    }
+
+    pub fn resource(mut self, resource: pb::Resource) -> FakeAgent {
+        self.resources.push(resource);
+        self
+    }
+
+    pub fn cpus(self, cpus: f64) -> FakeAgent {
+        self.resource(resource::scalar("cpus", cpus))
+    }
+
+    pub fn mem(self, megabytes: f64) -> FakeAgent {
+        self.resource(resource::scalar("mem", megabytes))
+    }
+
+    pub fn disk(self, megabytes: f64) -> FakeAgent {
+        self.resource(resource::scalar("disk", megabytes))
+    }
+
+    /// Adds the inclusive range of ports `begin..end`.
+    pub fn ports(self, begin: u64, end: u64) -> FakeAgent {
+        self.resource(resource::ranges("ports", &[(begin, end)]))
+    }
+
+    /// Adds a text attribute, e.g. `attribute("rack", "r1")`.
+    pub fn attribute(mut self, name: &str, value: &str) -> FakeAgent {
+        let mut text = pb::Value_Text::new();
+        text.set_value(value.to_string());
+
+        let mut attribute = pb::Attribute::new();
+        attribute.set_name(name.to_string());
+        attribute.set_field_type(pb::Value_Type::TEXT);
+        attribute.set_text(text);
+        self.attributes.push(attribute);
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn slave_id(&self) -> pb::SlaveID {
+        let mut slave_id = pb::SlaveID::new();
+        slave_id.set_value(self.id.clone());
+        slave_id
+    }
+}
+
+/// The status updates a launched task goes through, each sent a given
+/// time after the launch.  Steps after a terminal state are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskScript {
+    steps: Vec<ScriptStep>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ScriptStep {
+    after: Duration,
+    state: pb::TaskState,
+    message: Option<String>,
+}
+
+impl TaskScript {
+
+    /// A script without updates: launched tasks stay `TASK_STAGING`.
+    pub fn new() -> TaskScript {
+        TaskScript { steps: vec![] }
+    }
+
+    /// Sends `state` once `after` has passed since the launch.
+    pub fn then(
+        mut self,
+        after: Duration,
+        state: pb::TaskState
+    ) -> TaskScript {
+        self.steps.push(ScriptStep {
+            after: after,
+            state: state,
+            message: None,
+        });
+        self
+    }
+
+    /// Sets the message of the last update, e.g. `Command exited with
+    /// status 1`.
+    pub fn message(mut self, message: &str) -> TaskScript {
+        if let Some(step) = self.steps.last_mut() {
+            step.message = Some(message.to_string());
+        }
+        self
+    }
+}
+
+impl Default for TaskScript {
+
+    /// Tasks reach `TASK_RUNNING` as soon as they are launched.
+    fn default() -> TaskScript {
+        TaskScript::new()
+            .then(Duration::from_secs(0), pb::TaskState::TASK_RUNNING)
+    }
+}
@@ -0,0 +1,118 @@
+// Just enough resource arithmetic to track what the agents of a
+// `FakeMaster` have left to offer.
+
+use proto::mesos as pb;
+
+// Resources of the same kind can be added to and subtracted from each
+// other.
+fn same_kind(a: &pb::Resource, b: &pb::Resource) -> bool {
+    a.get_name() == b.get_name() &&
+        a.get_field_type() == b.get_field_type() &&
+        a.get_role() == b.get_role()
+}
+
+pub fn is_empty(resource: &pb::Resource) -> bool {
+    match resource.get_field_type() {
+        pb::Value_Type::SCALAR => resource.get_scalar().get_value() <= 1e-9,
+        pb::Value_Type::RANGES =>
+            resource.get_ranges().get_range().is_empty(),
+        pb::Value_Type::SET => resource.get_set().get_item().is_empty(),
+        pb::Value_Type::TEXT => true,
+    }
+}
+
+// Removes `resource` from `resources`, or returns false and leaves them
+// unchanged if they do not contain it.
+pub fn subtract(
+    resources: &mut Vec<pb::Resource>,
+    resource: &pb::Resource
+) -> bool {
+    let mut remaining = resources.clone();
+    let contained = match resource.get_field_type() {
+        pb::Value_Type::SCALAR => subtract_scalar(&mut remaining, resource),
+        pb::Value_Type::RANGES => resource.get_ranges().get_range().iter()
+            .all(|range| subtract_range(&mut remaining, resource, range)),
+        pb::Value_Type::SET => resource.get_set().get_item().iter()
+            .all(|item| subtract_item(&mut remaining, resource, item)),
+        pb::Value_Type::TEXT => false,
+    };
+
+    if contained {
+        remaining.retain(|resource| !is_empty(resource));
+        *resources = remaining;
+    }
+    contained
+}
+
+fn subtract_scalar(
+    resources: &mut Vec<pb::Resource>,
+    resource: &pb::Resource
+) -> bool {
+    let mut needed = resource.get_scalar().get_value();
+    for available in resources.iter_mut() {
+        if needed <= 1e-9 {
+            break;
+        }
+        if same_kind(available, resource) {
+            let value = available.get_scalar().get_value();
+            let used = value.min(needed);
+            available.mut_scalar().set_value(value - used);
+            needed -= used;
+        }
+    }
+    needed <= 1e-9
+}
+
+fn subtract_range(
+    resources: &mut Vec<pb::Resource>,
+    resource: &pb::Resource,
+    range: &pb::Value_Range
+) -> bool {
+    let (begin, end) = (range.get_begin(), range.get_end());
+    for available in resources.iter_mut() {
+        if !same_kind(available, resource) {
+            continue;
+        }
+
+        let ranges = available.mut_ranges().mut_range();
+        let position = ranges.iter()
+            .position(|r| r.get_begin() <= begin && end <= r.get_end());
+        if let Some(position) = position {
+            let outer = ranges.remove(position);
+            if outer.get_begin() < begin {
+                let mut below = pb::Value_Range::new();
+                below.set_begin(outer.get_begin());
+                below.set_end(begin - 1);
+                ranges.push(below);
+            }
+            if end < outer.get_end() {
+                let mut above = pb::Value_Range::new();
+                above.set_begin(end + 1);
+                above.set_end(outer.get_end());
+                ranges.push(above);
+            }
+            ranges.sort_by_key(|r| r.get_begin());
+            return true;
+        }
+    }
+    false
+}
+
+fn subtract_item(
+    resources: &mut Vec<pb::Resource>,
+    resource: &pb::Resource,
+    item: &str
+) -> bool {
+    for available in resources.iter_mut() {
+        if !same_kind(available, resource) {
+            continue;
+        }
+
+        let items = available.mut_set().mut_item();
+        if let Some(position) = items.iter().position(|i| i == item) {
+            items.remove(position);
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,411 @@
+#[cfg(test)]
+mod tests {
+
+    use ::builder::resource;
+    use ::executor::ExecutorDriver;
+    use ::proto::mesos as pb;
+    use ::scheduler::{Scheduler, SchedulerDriver};
+    use ::testing::*;
+    use std::cell::{Cell, RefCell};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    // Launches a task with a single cpu on every offer that has one, up to
+    // `max_tasks`, and declines the rest; remembers every callback.
+    struct Recorder {
+        events: RefCell<Vec<String>>,
+        launched: Cell<usize>,
+        max_tasks: usize,
+    }
+
+    impl Recorder {
+        fn new(max_tasks: usize) -> Recorder {
+            Recorder {
+                events: RefCell::new(vec![]),
+                launched: Cell::new(0),
+                max_tasks: max_tasks,
+            }
+        }
+
+        fn take(&self) -> Vec<String> {
+            self.events.borrow_mut().drain(..).collect()
+        }
+
+        fn record(&self, event: String) {
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    impl Scheduler for Recorder {
+        fn registered(
+            &self,
+            _: &SchedulerDriver,
+            framework_id: &pb::FrameworkID,
+            _: &pb::MasterInfo) {
+            self.record(format!("registered {}", framework_id.get_value()));
+        }
+
+        fn reregistered(&self, _: &SchedulerDriver, _: &pb::MasterInfo) {
+            self.record("reregistered".to_string());
+        }
+
+        fn disconnected(&self, _: &SchedulerDriver) {
+            self.record("disconnected".to_string());
+        }
+
+        fn resource_offers(
+            &self,
+            driver: &SchedulerDriver,
+            offers: Vec<pb::Offer>) {
+            let filters = pb::Filters::new();
+            for offer in offers {
+                self.record(format!("offer {} {}", offer.get_id().get_value(),
+                                    offer.get_hostname()));
+                let cpus: f64 = offer.get_resources().iter()
+                    .filter(|resource| resource.get_name() == "cpus")
+                    .map(|resource| resource.get_scalar().get_value())
+                    .sum();
+                if cpus < 1.0 || self.launched.get() == self.max_tasks {
+                    driver.decline_offer(offer.get_id(), &filters);
+                    continue;
+                }
+
+                self.launched.set(self.launched.get() + 1);
+                let mut task = pb::TaskInfo::new();
+                task.set_name("task".to_string());
+                task.mut_task_id().set_value(
+                    format!("task-{}", self.launched.get()));
+                task.set_slave_id(offer.get_slave_id().clone());
+                task.mut_resources().push(resource::scalar("cpus", 1.0));
+                driver.launch_tasks(offer.get_id(), &vec![&task], &filters);
+            }
+        }
+
+        fn offer_rescinded(&self, _: &SchedulerDriver, id: &pb::OfferID) {
+            self.record(format!("rescinded {}", id.get_value()));
+        }
+
+        fn status_update(&self, _: &SchedulerDriver, status: &pb::TaskStatus) {
+            self.record(format!("update {} {:?} {}",
+                                status.get_task_id().get_value(),
+                                status.get_state(),
+                                status.get_timestamp()));
+        }
+
+        fn slave_lost(&self, _: &SchedulerDriver, slave_id: &pb::SlaveID) {
+            self.record(format!("lost {}", slave_id.get_value()));
+        }
+    }
+
+    fn framework_info() -> pb::FrameworkInfo {
+        let mut framework_info = pb::FrameworkInfo::new();
+        framework_info.set_name("test".to_string());
+        framework_info.set_user("nobody".to_string());
+        framework_info
+    }
+
+    fn strings(events: &[&str]) -> Vec<String> {
+        events.iter().map(|event| event.to_string()).collect()
+    }
+
+    #[test]
+    fn offers_launches_and_scripted_updates() {
+        let scheduler = Recorder::new(2);
+        let master = FakeMaster::new(&scheduler, &framework_info());
+        master.add_agent(FakeAgent::new("agent-1").cpus(1.5).mem(1024.0));
+        master.set_default_script(TaskScript::new()
+            .then(Duration::from_secs(0), pb::TaskState::TASK_RUNNING)
+            .then(Duration::from_secs(10), pb::TaskState::TASK_FINISHED));
+
+        master.start();
+        assert_eq!(scheduler.take(), strings(&[
+            "registered fake-framework",
+            "offer offer-1 agent-1",
+            "update task-1 TASK_RUNNING 0",
+        ]));
+        assert_eq!(master.tasks().len(), 1);
+
+        // The remaining half cpu is filtered for 5 seconds, and does not
+        // fit a second task once offered again.
+        master.advance(Duration::from_secs(4));
+        assert!(scheduler.take().is_empty());
+        master.advance(Duration::from_secs(6));
+        assert_eq!(scheduler.take(), strings(&[
+            "offer offer-2 agent-1",
+            "update task-1 TASK_FINISHED 10",
+            "offer offer-3 agent-1",
+            "update task-2 TASK_RUNNING 10",
+        ]));
+
+        let task = master.tasks()[1].clone();
+        assert_eq!(task.get_task_id().get_value(), "task-2");
+        assert_eq!(master.task_state("task-1"),
+                   Some(pb::TaskState::TASK_FINISHED));
+        assert_eq!(master.now(), Duration::from_secs(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "allocation interval must not be zero")]
+    fn zero_allocation_interval_is_rejected() {
+        let scheduler = Recorder::new(0);
+        let master = FakeMaster::new(&scheduler, &framework_info());
+        master.set_allocation_interval(Duration::from_secs(0));
+    }
+
+    #[test]
+    fn declines_filter_until_revived() {
+        let scheduler = Recorder::new(0);
+        let master = FakeMaster::new(&scheduler, &framework_info());
+        master.add_agent(FakeAgent::new("a").cpus(1.0));
+        master.add_agent(FakeAgent::new("b").cpus(1.0)
+                         .attribute("rack", "r1"));
+
+        master.start();
+        scheduler.take();
+        assert!(master.offers().is_empty());
+
+        master.advance(Duration::from_secs(2));
+        assert!(scheduler.take().is_empty());
+
+        master.revive_offers();
+        master.advance(Duration::from_secs(1));
+        assert_eq!(scheduler.take(), strings(&[
+            "offer offer-3 a",
+            "offer offer-4 b",
+        ]));
+    }
+
+    #[test]
+    fn invalid_launches_are_rejected() {
+        // Keeps its offers, for the test to use.
+        struct Holder {
+            updates: RefCell<Vec<String>>,
+        }
+
+        impl Scheduler for Holder {
+            fn status_update(
+                &self,
+                _: &SchedulerDriver,
+                status: &pb::TaskStatus) {
+                self.updates.borrow_mut().push(format!(
+                    "{} {:?}", status.get_task_id().get_value(),
+                    status.get_state()));
+            }
+        }
+
+        let scheduler = Holder { updates: RefCell::new(vec![]) };
+        let master = FakeMaster::new(&scheduler, &framework_info());
+        master.add_agent(FakeAgent::new("a").cpus(1.0).ports(31000, 31001));
+        master.start();
+        let offer = master.offers()[0].clone();
+
+        let mut big = pb::TaskInfo::new();
+        big.set_name("big".to_string());
+        big.mut_task_id().set_value("big".to_string());
+        big.set_slave_id(offer.get_slave_id().clone());
+        big.mut_resources().push(resource::scalar("cpus", 2.0));
+
+        let mut port = big.clone();
+        port.mut_task_id().set_value("port".to_string());
+        port.clear_resources();
+        port.mut_resources().push(resource::ranges("ports",
+                                                   &[(31001, 31001)]));
+
+        let filters = pb::Filters::new();
+        master.launch_tasks(offer.get_id(), &vec![&big, &port], &filters);
+        master.launch_tasks(offer.get_id(), &vec![&port], &filters);
+        assert_eq!(*scheduler.updates.borrow(), strings(&[
+            "big TASK_ERROR",
+            "port TASK_RUNNING",
+            "port TASK_LOST",
+        ]));
+        assert_eq!(master.task_state("big"), None);
+        assert_eq!(master.task_state("port"),
+                   Some(pb::TaskState::TASK_RUNNING));
+
+        // The rest of the agent is offered once the filter expires.
+        master.advance(Duration::from_secs(5));
+        let offers = master.offers();
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].get_resources().to_vec(), vec![
+            resource::scalar("cpus", 1.0),
+            resource::ranges("ports", &[(31000, 31000)]),
+        ]);
+
+        master.rescind(offers[0].get_id().get_value());
+        assert!(master.offers().is_empty());
+    }
+
+    #[test]
+    fn agent_loss_and_failover() {
+        let scheduler = Recorder::new(1);
+        let master = FakeMaster::new(&scheduler, &framework_info());
+        master.add_agent(FakeAgent::new("a").cpus(2.0));
+        master.add_agent(FakeAgent::new("b").cpus(1.0));
+        master.start();
+        assert_eq!(scheduler.take(), strings(&[
+            "registered fake-framework",
+            "offer offer-1 a",
+            "offer offer-2 b",
+            "update task-1 TASK_RUNNING 0",
+        ]));
+
+        master.advance(Duration::from_secs(5));
+        assert_eq!(scheduler.take(), strings(&[
+            "offer offer-3 a",
+            "offer offer-4 b",
+        ]));
+
+        master.lose_agent("a");
+        assert_eq!(scheduler.take(), strings(&[
+            "update task-1 TASK_LOST 5",
+            "lost a",
+        ]));
+        assert_eq!(master.task_state("task-1"),
+                   Some(pb::TaskState::TASK_LOST));
+
+        master.fail_over();
+        assert_eq!(scheduler.take(), strings(&[
+            "disconnected",
+            "reregistered",
+        ]));
+        assert!(master.offers().is_empty());
+
+        let mut status = pb::TaskStatus::new();
+        status.mut_task_id().set_value("task-1".to_string());
+        status.set_state(pb::TaskState::TASK_RUNNING);
+        master.reconcile_tasks(&vec![&status]);
+        assert_eq!(scheduler.take(), strings(&[
+            "update task-1 TASK_LOST 5",
+        ]));
+    }
+
+    #[test]
+    fn run_until_stopped() {
+        struct Stopper {
+            launched: Cell<bool>,
+            updates: Cell<usize>,
+        }
+
+        impl Scheduler for Stopper {
+            fn resource_offers(
+                &self,
+                driver: &SchedulerDriver,
+                offers: Vec<pb::Offer>) {
+                let offer = &offers[0];
+                if self.launched.get() {
+                    driver.decline_offer(offer.get_id(),
+                                         &pb::Filters::new());
+                    return;
+                }
+
+                self.launched.set(true);
+                let mut task = pb::TaskInfo::new();
+                task.set_name("once".to_string());
+                task.mut_task_id().set_value("once".to_string());
+                task.set_slave_id(offer.get_slave_id().clone());
+                driver.launch_tasks(offer.get_id(), &vec![&task],
+                                    &pb::Filters::new());
+            }
+
+            fn status_update(
+                &self,
+                driver: &SchedulerDriver,
+                status: &pb::TaskStatus) {
+                self.updates.set(self.updates.get() + 1);
+                if status.get_state() == pb::TaskState::TASK_FAILED {
+                    driver.stop(false);
+                }
+            }
+        }
+
+        let scheduler = Stopper {
+            launched: Cell::new(false),
+            updates: Cell::new(0),
+        };
+        let mut master = FakeMaster::new(&scheduler, &framework_info());
+        master.add_agent(FakeAgent::new("a").cpus(1.0));
+        master.set_script("once", TaskScript::new()
+            .then(Duration::from_secs(1), pb::TaskState::TASK_RUNNING)
+            .then(Duration::from_secs(90), pb::TaskState::TASK_FAILED)
+            .message("Command exited with status 3"));
+
+        assert_eq!(master.run(), pb::Status::DRIVER_STOPPED as i32);
+        assert_eq!(scheduler.updates.get(), 2);
+        assert_eq!(master.now(), Duration::from_secs(90));
+        assert_eq!(master.launch_tasks(&pb::OfferID::new(), &vec![],
+                                       &pb::Filters::new()),
+                   pb::Status::DRIVER_STOPPED as i32);
+    }
+
+    #[test]
+    fn mock_scheduler_driver_records_calls() {
+        let scheduler = Recorder::new(1);
+        let driver = MockSchedulerDriver::new();
+        driver.return_status_for(DriverMethod::DeclineOffer,
+                                 pb::Status::DRIVER_ABORTED);
+
+        let mut offers = vec![];
+        for &(id, cpus) in &[("offer-1", 2.0), ("offer-2", 1.0)] {
+            let mut offer = pb::Offer::new();
+            offer.mut_id().set_value(id.to_string());
+            offer.mut_slave_id().set_value("agent-1".to_string());
+            offer.mut_resources().push(resource::scalar("cpus", cpus));
+            offers.push(offer);
+        }
+        scheduler.resource_offers(&driver, offers);
+
+        assert_eq!(driver.launched_tasks().len(), 1);
+        assert_eq!(driver.launched_tasks()[0].get_task_id().get_value(),
+                   "task-1");
+        assert_eq!(driver.declined_offers()[0].get_value(), "offer-2");
+        assert_eq!(driver.kill_task(&pb::TaskID::new()),
+                   pb::Status::DRIVER_RUNNING as i32);
+        assert_eq!(driver.decline_offer(&pb::OfferID::new(),
+                                        &pb::Filters::new()),
+                   pb::Status::DRIVER_ABORTED as i32);
+
+        driver.return_status(pb::Status::DRIVER_STOPPED);
+        assert_eq!(driver.revive_offers(), pb::Status::DRIVER_STOPPED as i32);
+
+        let names: Vec<&str> = driver.take_calls().iter()
+            .map(|call| call.name())
+            .collect();
+        assert_eq!(names, vec!["launch_tasks", "decline_offer", "kill_task",
+                               "decline_offer", "revive_offers"]);
+        assert!(driver.calls().is_empty());
+    }
+
+    #[test]
+    fn mock_executor_driver_waits_for_calls() {
+        let driver = Arc::new(MockExecutorDriver::new());
+        driver.return_status_for(ExecutorMethod::SendFrameworkMessage,
+                                 pb::Status::DRIVER_STOPPED);
+
+        let executor = driver.clone();
+        let sender = thread::spawn(move || {
+            let mut status = pb::TaskStatus::new();
+            status.mut_task_id().set_value("t1".to_string());
+            status.set_state(pb::TaskState::TASK_RUNNING);
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(executor.send_status_update(&status),
+                       pb::Status::DRIVER_RUNNING as i32);
+            assert_eq!(executor.send_framework_message(&b"done".to_vec()),
+                       pb::Status::DRIVER_STOPPED as i32);
+        });
+
+        let calls = driver.wait_for_calls(2, Duration::from_secs(10));
+        sender.join().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(driver.status_updates()[0].get_task_id().get_value(),
+                   "t1");
+        assert_eq!(driver.framework_messages(), vec![b"done".to_vec()]);
+        assert_eq!(calls[1], ExecutorCall::SendFrameworkMessage(
+            b"done".to_vec()));
+
+        // Times out with the calls made so far.
+        assert_eq!(driver.wait_for_calls(3, Duration::from_millis(10)),
+                   calls);
+    }
+}